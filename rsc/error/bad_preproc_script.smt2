@@ -0,0 +1,13 @@
+; `unknown_strat` is not a pre-processing strategy.
+(set-option :preproc-script "simplify; fixpoint(arg_red; unknown_strat)")
+
+(declare-fun p (Int) Bool)
+
+(assert
+  (forall ( (x Int) ) (=> (= x 0) (p x)))
+)
+(assert
+  (forall ( (x Int) ) (=> (and (p x) (> x 10)) false))
+)
+
+(check-sat)
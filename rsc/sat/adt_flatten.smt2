@@ -0,0 +1,24 @@
+; `p` has a datatype argument, it is flattened and `cfg_red` and `coi` then run on the new
+; predicate.
+(set-option :preproc-script "simplify; adt_flatten; cfg_red; coi; fixpoint@10(arg_red; one_rhs; one_lhs)")
+
+(declare-datatypes ( (Pos 0) ) ( ( (pos (x Int) (y Int)) ) ))
+
+(declare-fun p ( Pos ) Bool)
+
+(assert
+  (forall ( (n Int) ) (=> (>= n 0) (p (pos n n))))
+)
+(assert
+  (forall ( (s Pos) ) (=> (and (p s) (< (x s) 10)) (p (pos (+ (x s) 1) (y s)))))
+)
+(assert
+  (forall ( (s Pos) ) (=> (and (p s) (< (x s) (y s))) false))
+)
+(assert
+  (forall ( (s Pos) ) (=> (and (p s) (< (y s) 0)) false))
+)
+
+(check-sat)
+
+(get-model)
@@ -0,0 +1,26 @@
+; The last two arguments of `p` are propagated, `q` is then forced by `one_rhs`. Both definitions
+; must be conjoined with the invariants of `p`.
+(set-option :preproc-script "simplify; arg_prop; fixpoint@10(arg_red; one_rhs; one_lhs); cfg_red")
+
+(declare-fun p ( Int Int Int ) Bool)
+(declare-fun q ( Int Int Int ) Bool)
+
+(assert
+  (forall ( (n Int) ) (=> (= n 0) (p n 7 (+ n 1))))
+)
+(assert
+  (forall ( (n Int) (m Int) (k Int) ) (=> (and (p n m k) (< n 10)) (p (+ n 1) m (+ k 1))))
+)
+(assert
+  (forall ( (n Int) (m Int) (k Int) ) (=> (p n m k) (q n m k)))
+)
+(assert
+  (forall ( (n Int) (m Int) (k Int) ) (=> (and (q n m k) (> n k)) false))
+)
+(assert
+  (forall ( (n Int) (m Int) (k Int) ) (=> (and (q n m k) (not (= m 7))) false))
+)
+
+(check-sat)
+
+(get-model)
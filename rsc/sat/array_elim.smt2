@@ -0,0 +1,21 @@
+; The array argument of `p` is only read at index `0`, it is eliminated and `cfg_red` and `coi`
+; then run on the new predicate.
+(set-option :preproc-script "simplify; array_elim; cfg_red; coi; fixpoint@10(arg_red; one_rhs; one_lhs)")
+
+(declare-fun p ( Int (Array Int Int) ) Bool)
+
+(assert
+  (forall ( (n Int) (a (Array Int Int)) ) (=> (and (= n 0) (= (select a 0) 0)) (p n a)))
+)
+(assert
+  (forall ( (n Int) (a (Array Int Int)) )
+    (=> (and (p n a) (< n 10)) (p (+ n 1) (store a 0 (+ (select a 0) 1))))
+  )
+)
+(assert
+  (forall ( (n Int) (a (Array Int Int)) ) (=> (and (p n a) (> (select a 0) n)) false))
+)
+
+(check-sat)
+
+(get-model)
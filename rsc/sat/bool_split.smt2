@@ -0,0 +1,24 @@
+; `p` is split on its boolean argument, `cfg_red` and `coi` then run on the new predicates.
+(set-option :preproc-script "simplify; bool_split; cfg_red; coi; fixpoint@10(arg_red; one_rhs; one_lhs)")
+
+(declare-fun p (Bool Int) Bool)
+
+(assert
+  (forall ( (n Int) ) (=> (= n 0) (p true n)))
+)
+(assert
+  (forall ( (n Int) ) (=> (and (p true n) (< n 10)) (p false (+ n 1))))
+)
+(assert
+  (forall ( (n Int) ) (=> (and (p false n) (< n 10)) (p true (+ n 1))))
+)
+(assert
+  (forall ( (b Bool) (n Int) ) (=> (and (p b n) (or (< n 0) (> n 10))) false))
+)
+(assert
+  (forall ( (n Int) ) (=> (and (p false n) (= n 0)) false))
+)
+
+(check-sat)
+
+(get-model)
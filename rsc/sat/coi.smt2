@@ -0,0 +1,30 @@
+(set-option :preproc-script "simplify; bool_split; coi; simplify")
+
+(declare-fun p (Bool Int) Bool)
+(declare-fun q (Int) Bool)
+(declare-fun r (Int) Bool)
+
+(assert
+  (forall ( (n Int) ) (=> (= n 0) (p true n)))
+)
+(assert
+  (forall ( (n Int) ) (=> (and (p true n) (< n 10)) (p false (+ n 1))))
+)
+(assert
+  (forall ( (n Int) ) (=> (and (p false n) (< n 10)) (p true (+ n 1))))
+)
+(assert
+  (forall ( (b Bool) (n Int) ) (=> (and (p b n) (< n 0)) false))
+)
+; `q` is not reachable from the facts, this clause is sliced away.
+(assert
+  (forall ( (n Int) ) (=> (and (q n) (> n 0)) (p true n)))
+)
+; `r` cannot reach a negative clause, this clause is sliced away.
+(assert
+  (forall ( (n Int) ) (=> (p false n) (r (- n 1))))
+)
+
+(check-sat)
+
+(get-model)
@@ -0,0 +1,17 @@
+; Counts from `0` to `10`, `(and (>= x 0) (<= x 10))` is an invariant.
+(set-logic HORN)
+
+(declare-fun p (Int) Bool)
+
+(assert
+  (forall ( (x Int) ) (=> (= x 0) (p x)))
+)
+(assert
+  (forall ( (x Int) ) (=> (and (p x) (< x 10)) (p (+ x 1))))
+)
+(assert
+  (forall ( (x Int) ) (=> (and (p x) (> x 10)) false))
+)
+
+(check-sat)
+(get-model)
@@ -0,0 +1,21 @@
+; Queries on the last model, after `check-sat`.
+(set-option :preproc-script "simplify")
+
+(declare-fun p (Int) Bool)
+
+(assert
+  (forall ( (x Int) ) (=> (= x 0) (p x)))
+)
+(assert
+  (forall ( (x Int) ) (=> (and (p x) (< x 10)) (p (+ x 1))))
+)
+(assert
+  (forall ( (x Int) ) (=> (and (p x) (> x 10)) false))
+)
+
+(check-sat)
+(get-info :name)
+(get-info :version)
+(get-option :preproc-script)
+(get-value ((p 0) (p 11) (p (- 1))))
+(get-model)
@@ -0,0 +1,22 @@
+; Both self-loops of `p` are accelerated: `p` relates its arguments across any number of
+; iterations of either loop.
+(set-option :preproc-script "simplify; loop_accel; simplify")
+
+(declare-fun p ( Int Int ) Bool)
+
+(assert
+  (forall ( (x Int) (y Int) ) (=> (and (= x 0) (= y 0)) (p x y)))
+)
+(assert
+  (forall ( (x Int) (y Int) ) (=> (and (p x y) (< x 100)) (p (+ x 1) (+ y 2))))
+)
+(assert
+  (forall ( (x Int) (y Int) ) (=> (and (p x y) (>= x 100) (< x 200)) (p (+ x 2) (+ y 4))))
+)
+(assert
+  (forall ( (x Int) (y Int) ) (=> (and (p x y) (not (= y (* 2 x)))) false))
+)
+
+(check-sat)
+
+(get-model)
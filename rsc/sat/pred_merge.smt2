@@ -0,0 +1,24 @@
+; `p` and `q` are defined by isomorphic clauses, `pred_merge` merges `q` into `p`.
+(set-option :preproc-script "simplify; pred_merge; simplify")
+
+(declare-fun p (Int) Bool)
+(declare-fun q (Int) Bool)
+
+(assert
+  (forall ( (n Int) ) (=> (= n 0) (p n)))
+)
+(assert
+  (forall ( (n Int) ) (=> (and (p n) (< n 10)) (p (+ n 1))))
+)
+(assert
+  (forall ( (m Int) ) (=> (= m 0) (q m)))
+)
+(assert
+  (forall ( (m Int) ) (=> (and (q m) (< m 10)) (q (+ m 1))))
+)
+(assert
+  (forall ( (n Int) ) (=> (and (p n) (q n) (> n 10)) false))
+)
+
+(check-sat)
+(get-model)
@@ -0,0 +1,17 @@
+; Same system as `counter.smt2`, pre-processed by a script with budgets.
+(set-option :preproc-script "simplify; fixpoint@2(arg_red; one_rhs@1; one_lhs@1); cfg_red")
+
+(declare-fun p (Int) Bool)
+
+(assert
+  (forall ( (x Int) ) (=> (= x 0) (p x)))
+)
+(assert
+  (forall ( (x Int) ) (=> (and (p x) (< x 10)) (p (+ x 1))))
+)
+(assert
+  (forall ( (x Int) ) (=> (and (p x) (> x 10)) false))
+)
+
+(check-sat)
+(get-model)
@@ -0,0 +1,27 @@
+; The refutation only uses `p_init`, `p_step` and `p_safe`. The assertions over `q` could take
+; part in a refutation, but are not in the core.
+(set-option :produce-unsat-cores true)
+
+(set-logic HORN)
+
+(declare-fun p (Int) Bool)
+(declare-fun q (Int) Bool)
+
+(assert
+  (! (forall ((x Int)) (=> (= x 0) (p x))) :named p_init)
+)
+(assert
+  (! (forall ((x Int)) (=> (and (p x) (< x 2)) (p (+ x 1)))) :named p_step)
+)
+(assert
+  (! (forall ((x Int)) (=> (= x 0) (q x))) :named q_init)
+)
+(assert
+  (! (forall ((x Int)) (=> (and (q x) (> x 5)) false)) :named q_safe)
+)
+(assert
+  (! (forall ((x Int)) (=> (and (p x) (= x 2)) false)) :named p_safe)
+)
+
+(check-sat)
+(get-unsat-core)
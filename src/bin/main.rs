@@ -19,6 +19,11 @@ fn main() {
     // renice();
     // Work and report error if any.
     if let Err(errs) = ::hoice::work() {
+        // Strict checking failures have their own exit code.
+        let code = match *errs.kind() {
+            ErrorKind::CheckUnknown(_) => 3,
+            _ => 2,
+        };
         let errs = match *errs.kind() {
             ErrorKind::Z3SpawnError => format!(
                 "could not spawn z3 using command `{}`\n\
@@ -31,7 +36,7 @@ fn main() {
             _ => errs,
         };
         print_err(&errs);
-        ::std::process::exit(2)
+        ::std::process::exit(code)
     } else {
         ::std::process::exit(0)
     }
@@ -3,6 +3,7 @@
 //! This code is completely separated from the rest of the code, on purpose. It
 //! basically takes the original [`smt2`][smt2] file, a file containing the
 //! output of the [`hoice`][hoice] run, and checks that the result makes sense.
+//! Unsat proofs are checked by the [`proof`](proof/index.html) module.
 //!
 //! It does so using an SMT solver, and performing string substitution
 //! (roughly) to rewrite the problem as a pure SMT query. In particular, there
@@ -17,6 +18,7 @@ use crate::{
 };
 
 pub mod parse;
+pub mod proof;
 
 use self::parse::InParser;
 use self::smt::*;
@@ -77,6 +79,8 @@ pub struct Clause {
     pub args: Args,
     /// Body.
     pub body: Term,
+    /// Name of the assertion, if any.
+    pub name: Option<Ident>,
 }
 
 /// Data from the input file.
@@ -202,6 +206,38 @@ impl Output {
     }
 }
 
+/// Verdict of the check of a clause.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The clause is verified.
+    Valid,
+    /// The clause is falsifiable.
+    Falsifiable,
+    /// The solver could not decide.
+    Unknown,
+    /// An error occured.
+    Error,
+}
+impl Verdict {
+    /// String representation.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Valid => "valid",
+            Verdict::Falsifiable => "falsifiable",
+            Verdict::Unknown => "unknown",
+            Verdict::Error => "error",
+        }
+    }
+}
+
+/// Sets the timeout for checking a single clause, if any.
+fn set_clause_timeout(solver: &mut Solver<Parser>) -> Res<()> {
+    if conf.check_tmo > 0 {
+        solver.set_option(":timeout", &format!("{}000", conf.check_tmo))?
+    }
+    Ok(())
+}
+
 /// Aggregates the input and output data.
 pub struct Data {
     /// Input data.
@@ -226,10 +262,11 @@ impl Data {
     pub fn check_clause(
         &self,
         solver: &mut Solver<Parser>,
-        Clause { args, body }: &Clause,
+        Clause { args, body, .. }: &Clause,
         count: usize,
     ) -> Res<Option<bool>> {
         solver.reset()?;
+        set_clause_timeout(solver)?;
 
         for unknown in &self.input.unknown {
             use std::io::Write;
@@ -306,37 +343,71 @@ impl Data {
         } else if let Some(false) = res {
             log_info!("clause {} is fine", count);
             Ok(Some(true))
+        } else if conf.check_strict {
+            log_info!("got unknown on clause {}", count);
+            Ok(None)
         } else {
             log_info!("got unknown on clause {}, assuming it's okay", count);
             Ok(None)
         }
     }
 
-    /// Checks the output data works with the input data using an SMT solver.
-    pub fn check(&self, solver: &mut Solver<Parser>) -> Res<()> {
-        let mut okay = true;
-        let mut err = false;
+    /// Verdict of each clause of the input data.
+    pub fn verdicts(&self, solver: &mut Solver<Parser>) -> Vec<Verdict> {
+        let mut verdicts = Vec::with_capacity(self.input.clauses.len());
 
         // Check all clauses one by one.
         for (count, clause) in self.input.clauses.iter().enumerate() {
-            match self.check_clause(solver, clause, count) {
-                Ok(Some(ok)) => okay = okay && ok,
-                Ok(None) => (),
+            let verdict = match self.check_clause(solver, clause, count) {
+                Ok(Some(true)) => Verdict::Valid,
+                Ok(Some(false)) => Verdict::Falsifiable,
+                Ok(None) => Verdict::Unknown,
                 Err(e) => {
-                    err = true;
                     let e = e.chain_err(|| format!("while checking clause {}", count));
-                    print_err(&e)
+                    print_err(&e);
+                    Verdict::Error
                 }
-            }
+            };
+            verdicts.push(verdict)
         }
 
-        if !okay {
-            error_chain::bail!("predicates do not verify all the clauses of the input file")
-        } else if err {
-            error_chain::bail!("at least one error while checking the clauses")
-        } else {
-            Ok(())
-        }
+        verdicts
+    }
+
+    /// Checks the output data works with the input data using an SMT solver.
+    ///
+    /// In strict mode, clauses the solver cannot decide are failures.
+    pub fn check(&self, solver: &mut Solver<Parser>) -> Res<()> {
+        judge(&self.verdicts(solver), conf.check_strict)
+    }
+}
+
+/// Prints the verdict of each clause.
+fn print_verdicts(verdicts: &[Verdict]) {
+    println!("(verdicts");
+    for (count, verdict) in verdicts.iter().enumerate() {
+        println!("  (clause {} {})", count, verdict.as_str())
+    }
+    println!(")")
+}
+
+/// Turns the verdicts of the clauses into a result.
+///
+/// Fails if a clause is falsifiable or could not be checked. Clauses the solver cannot decide
+/// are failures only if `strict`, in which case the error is [`ErrorKind::CheckUnknown`].
+///
+/// [`ErrorKind::CheckUnknown`]: ../errors/enum.ErrorKind.html (ErrorKind enum)
+pub fn judge(verdicts: &[Verdict], strict: bool) -> Res<()> {
+    let count = |verdict: Verdict| verdicts.iter().filter(|v| **v == verdict).count();
+
+    if count(Verdict::Falsifiable) > 0 {
+        error_chain::bail!("predicates do not verify all the clauses of the input file")
+    } else if count(Verdict::Error) > 0 {
+        error_chain::bail!("at least one error while checking the clauses")
+    } else if strict && count(Verdict::Unknown) > 0 {
+        error_chain::bail!(ErrorKind::CheckUnknown(count(Verdict::Unknown)))
+    } else {
+        Ok(())
     }
 }
 
@@ -348,7 +419,11 @@ pub fn do_it(input_file: &str, output_file: &str) -> Res<()> {
 
     let mut solver = conf.solver.spawn("check", Parser, &Instance::new())?;
 
-    let res = data.check(&mut solver);
+    let verdicts = data.verdicts(&mut solver);
+    if conf.check_strict {
+        print_verdicts(&verdicts)
+    }
+    let res = judge(&verdicts, conf.check_strict);
     if res.is_ok() {
         println!("(safe)")
     }
@@ -358,6 +433,34 @@ pub fn do_it(input_file: &str, output_file: &str) -> Res<()> {
     res.and_then(|_| end_res)
 }
 
+/// Validates a model against a script, both given as strings.
+///
+/// Used to validate models before answering `sat`, see `conf.validate`.
+pub fn validate(script: &str, model: &str) -> Res<()> {
+    let data = Data::new(
+        Input::of_str(script).chain_err(|| "while loading script")?,
+        Output::of_str(model).chain_err(|| "while loading model")?,
+    )?;
+
+    let mut solver = conf.solver.spawn("validate", Parser, &Instance::new())?;
+    let res = data.check(&mut solver);
+    let end_res = solver.kill().chain_err(|| "While killing solver");
+    res.and_then(|_| end_res)
+}
+
+/// Verdict of each clause of a script for a model, both given as strings.
+pub fn verdicts_from_str(script: &str, model: &str) -> Res<Vec<Verdict>> {
+    let data = Data::new(
+        Input::of_str(script).chain_err(|| "while loading script")?,
+        Output::of_str(model).chain_err(|| "while loading model")?,
+    )?;
+
+    let mut solver = conf.solver.spawn("check", Parser, &Instance::new())?;
+    let verdicts = data.verdicts(&mut solver);
+    solver.kill().chain_err(|| "While killing solver")?;
+    Ok(verdicts)
+}
+
 /// Checks a `hoice` run, script from a file, model from a string.
 ///
 /// This is currently only used for testing purposes.
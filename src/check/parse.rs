@@ -2,7 +2,7 @@
 
 use std::iter::Extend;
 
-use crate::check::*;
+use crate::check::{proof::*, *};
 
 /// Parser.
 #[derive(Clone)]
@@ -173,6 +173,50 @@ impl<'a> InParser<'a> {
         Ok(s)
     }
 
+    /// Parses an atom or an s-expression, without quoting identifiers.
+    fn raw_sexpr(&mut self) -> Res<Term> {
+        if self.char_opt('(') {
+            self.txen('(');
+            return self.sexpr();
+        } else if self.char_opt('|') {
+            let id = self.not_char('|');
+            self.char('|')?;
+            return Ok(format!("|{}|", id));
+        }
+        let mut s = String::new();
+        while let Some(next) = self.next() {
+            if next.is_whitespace() || next == '(' || next == ')' {
+                self.txen(next);
+                break;
+            } else {
+                s.push(next)
+            }
+        }
+        if s.is_empty() {
+            error_chain::bail!("expected atom or s-expression")
+        }
+        Ok(s)
+    }
+
+    /// Elements of an s-expression, `None` if it is an atom.
+    pub fn elems_of(sexpr: &str) -> Res<Option<Vec<Term>>> {
+        let mut parser = InParser::new(sexpr);
+        parser.ws_cmt();
+        if !parser.char_opt('(') {
+            return Ok(None);
+        }
+        parser.ws_cmt();
+        let mut elems = vec![];
+        while !parser.char_opt(')') {
+            if !parser.has_next() {
+                error_chain::bail!("found eof while parsing sexpr")
+            }
+            elems.push(parser.raw_sexpr()?);
+            parser.ws_cmt()
+        }
+        Ok(Some(elems))
+    }
+
     /// Reads whitespaces and comments.
     fn ws_cmt(&mut self) {
         'ws: while let Some(next) = self.next() {
@@ -345,6 +389,14 @@ impl<'a> InParser<'a> {
         self.ws_cmt();
         self.char('(')?;
 
+        let named = if self.char_opt('!') {
+            self.ws_cmt();
+            self.char('(')?;
+            true
+        } else {
+            false
+        };
+
         let negated = if self.tag_opt("not") {
             self.ws_cmt();
             self.char('(')?;
@@ -406,8 +458,21 @@ impl<'a> InParser<'a> {
         if negated {
             self.char(')').chain_err(|| "closing negation")?;
         }
+        let name = if named {
+            self.ws_cmt();
+            self.tag(":named").chain_err(|| "unexpected tag")?;
+            self.ws_cmt();
+            let name = self
+                .ident()
+                .chain_err(|| "expected identifier after `:named` tag")?;
+            self.ws_cmt();
+            self.char(')').chain_err(|| "closing named assertion")?;
+            Some(name)
+        } else {
+            None
+        };
 
-        self.clauses.push(Clause { args, body });
+        self.clauses.push(Clause { args, body, name });
 
         Ok(true)
     }
@@ -543,7 +608,8 @@ impl<'a> InParser<'a> {
         if !conf.check_eld {
             self.char('(').chain_err(|| error)?;
             self.ws_cmt();
-            self.tag("model").chain_err(|| error)?;
+            // Witnesses have no `model` keyword.
+            self.tag_opt("model");
             self.ws_cmt()
         }
 
@@ -594,4 +660,321 @@ impl<'a> InParser<'a> {
             pred_defs: self.pred_defs,
         })
     }
+
+    /// Parses a natural number.
+    fn usize(&mut self) -> Res<usize> {
+        let token = self.raw_sexpr()?;
+        token
+            .parse()
+            .map_err(|_| format!("expected a natural number, found `{}`", token).into())
+    }
+
+    /// Parses a fact, without the opening paren.
+    fn fact(&mut self) -> Res<Fact> {
+        self.ws_cmt();
+        let pred = self
+            .ident()
+            .chain_err(|| "while parsing the predicate of a fact")?;
+        self.fact_args(pred)
+    }
+
+    /// Parses the arguments of a fact, and its closing paren.
+    fn fact_args(&mut self, pred: Pred) -> Res<Fact> {
+        self.ws_cmt();
+        let mut args = vec![];
+        while !self.char_opt(')') {
+            if !self.has_next() {
+                error_chain::bail!("found eof while parsing a fact")
+            }
+            args.push(self.raw_sexpr()?);
+            self.ws_cmt()
+        }
+        Ok(Fact { pred, args })
+    }
+
+    /// Parses a node of a derivation, without the opening paren.
+    fn node(&mut self) -> Res<(usize, Node)> {
+        self.ws_cmt();
+        self.tag("node").chain_err(|| "expected `node` keyword")?;
+        self.ws_cmt();
+        let id = self
+            .usize()
+            .chain_err(|| "while parsing the id of a node")?;
+        self.ws_cmt();
+
+        self.char('(')
+            .chain_err(|| "opening the clause of a node")?;
+        self.ws_cmt();
+        self.tag("clause")
+            .chain_err(|| "expected `clause` keyword")?;
+        self.ws_cmt();
+        let clause = self
+            .usize()
+            .chain_err(|| "while parsing the index of a clause")?;
+        let clause = Some(clause);
+        self.ws_cmt();
+        let name = self
+            .ident_opt()
+            .chain_err(|| "while parsing the name of a clause")?;
+        self.ws_cmt();
+        self.char(')')
+            .chain_err(|| "closing the clause of a node")?;
+        self.ws_cmt();
+
+        self.char('(')
+            .chain_err(|| "opening the values of a node")?;
+        self.ws_cmt();
+        self.tag("values")
+            .chain_err(|| "expected `values` keyword")?;
+        self.ws_cmt();
+        let mut values = vec![];
+        while self.char_opt('(') {
+            self.ws_cmt();
+            let var = self
+                .ident()
+                .chain_err(|| "while parsing the variable of a value")?;
+            self.ws_cmt();
+            let val = self.raw_sexpr().chain_err(|| "while parsing a value")?;
+            self.ws_cmt();
+            self.char(')').chain_err(|| "closing a value")?;
+            self.ws_cmt();
+            values.push((var, val))
+        }
+        self.char(')')
+            .chain_err(|| "closing the values of a node")?;
+        self.ws_cmt();
+
+        self.char('(')
+            .chain_err(|| "opening the conclusion of a node")?;
+        self.ws_cmt();
+        self.tag("derives")
+            .chain_err(|| "expected `derives` keyword")?;
+        self.ws_cmt();
+        let fact = if self.tag_opt("false") {
+            None
+        } else {
+            self.char('(')
+                .chain_err(|| "expected `false` or a predicate application")?;
+            Some(self.fact()?)
+        };
+        self.ws_cmt();
+        self.char(')')
+            .chain_err(|| "closing the conclusion of a node")?;
+        self.ws_cmt();
+
+        self.char('(')
+            .chain_err(|| "opening the children of a node")?;
+        self.ws_cmt();
+        self.tag("children")
+            .chain_err(|| "expected `children` keyword")?;
+        self.ws_cmt();
+        let mut children = vec![];
+        while !self.char_opt(')') {
+            if !self.has_next() {
+                error_chain::bail!("found eof while parsing the children of a node")
+            }
+            children.push(self.usize().chain_err(|| "while parsing a child")?);
+            self.ws_cmt()
+        }
+        self.ws_cmt();
+
+        self.char(')').chain_err(|| "closing node")?;
+
+        Ok((
+            id,
+            Node {
+                clause,
+                name,
+                values,
+                fact,
+                children,
+            },
+        ))
+    }
+
+    /// Parses the nodes of a derivation, after the `derivation` keyword.
+    fn derivation(&mut self) -> Res<Proof> {
+        let mut nodes = vec![];
+        while self.char_opt('(') {
+            let (id, node) = self
+                .node()
+                .chain_err(|| format!("while parsing node #{}", nodes.len()))?;
+            if id != nodes.len() {
+                error_chain::bail!(
+                    "expected node {}, found node {}: nodes must be listed by increasing id",
+                    nodes.len(),
+                    id
+                )
+            }
+            nodes.push(node);
+            self.ws_cmt()
+        }
+        Ok(Proof { nodes })
+    }
+
+    /// Parses a frame of a trace, without the opening paren.
+    fn frame(&mut self) -> Res<Frame> {
+        self.ws_cmt();
+        let clause = self
+            .ident()
+            .chain_err(|| "while parsing the clause of a frame")?;
+        self.ws_cmt();
+
+        self.char('(')
+            .chain_err(|| "opening the values of a frame")?;
+        self.ws_cmt();
+        let mut values = vec![];
+        while self.char_opt('(') {
+            self.ws_cmt();
+            self.tag("define-fun")
+                .chain_err(|| "expected `define-fun` keyword")?;
+            self.ws_cmt();
+            let var = self
+                .ident()
+                .chain_err(|| "while parsing the variable of a value")?;
+            self.ws_cmt();
+            self.raw_sexpr()
+                .chain_err(|| "while parsing the type of a value")?;
+            self.ws_cmt();
+            let val = self.raw_sexpr().chain_err(|| "while parsing a value")?;
+            self.ws_cmt();
+            self.char(')').chain_err(|| "closing a value")?;
+            self.ws_cmt();
+            values.push((var, val))
+        }
+        self.char(')')
+            .chain_err(|| "closing the values of a frame")?;
+        self.ws_cmt();
+
+        self.char('(')
+            .chain_err(|| "opening the implication of a frame")?;
+        self.ws_cmt();
+        self.tag("=>").chain_err(|| "expected `=>` keyword")?;
+        self.ws_cmt();
+        let mut lhs = vec![];
+        if !self.tag_opt("true") {
+            self.char('(')
+                .chain_err(|| "expected `true` or a conjunction")?;
+            self.ws_cmt();
+            self.tag("and").chain_err(|| "expected `and` keyword")?;
+            self.ws_cmt();
+            while self.char_opt('(') {
+                lhs.push(self.fact()?);
+                self.ws_cmt()
+            }
+            self.char(')').chain_err(|| "closing conjunction")?
+        }
+        self.ws_cmt();
+        let rhs = if self.tag_opt("false") {
+            None
+        } else {
+            self.char('(')
+                .chain_err(|| "expected `false` or a predicate application")?;
+            Some(self.fact()?)
+        };
+        self.ws_cmt();
+        self.char(')')
+            .chain_err(|| "closing the implication of a frame")?;
+        self.ws_cmt();
+
+        self.char('(')
+            .chain_err(|| "opening the result of a frame")?;
+        self.ws_cmt();
+        let pred = self
+            .ident()
+            .chain_err(|| "while parsing the result of a frame")?;
+        let (positive, yields) = if pred == "|not|" {
+            self.ws_cmt();
+            self.char('(')
+                .chain_err(|| "opening the negated result of a frame")?;
+            let fact = self.fact()?;
+            self.ws_cmt();
+            self.char(')')
+                .chain_err(|| "closing the negated result of a frame")?;
+            (false, fact)
+        } else {
+            (true, self.fact_args(pred)?)
+        };
+        self.ws_cmt();
+
+        self.char(')').chain_err(|| "closing frame")?;
+
+        Ok(Frame {
+            clause,
+            values,
+            lhs,
+            rhs,
+            positive,
+            yields,
+        })
+    }
+
+    /// Parses the frames of a trace, after its opening paren.
+    fn trace(&mut self) -> Res<Proof> {
+        self.ws_cmt();
+        // Contradiction, redundant with the last frames of the derivations.
+        self.raw_sexpr()
+            .chain_err(|| "while parsing the contradiction of a trace")?;
+        self.ws_cmt();
+
+        let mut frames = vec![];
+        // Derivations of the positive and the negative sample.
+        for _ in 0..2 {
+            self.char('(')
+                .chain_err(|| "opening the derivation of a sample")?;
+            self.ws_cmt();
+            while self.char_opt('(') {
+                let frame = self
+                    .frame()
+                    .chain_err(|| format!("while parsing frame #{}", frames.len()))?;
+                frames.push(frame);
+                self.ws_cmt()
+            }
+            self.char(')')
+                .chain_err(|| "closing the derivation of a sample")?;
+            self.ws_cmt()
+        }
+
+        Proof::of_frames(frames)
+    }
+
+    /// Parses the unsat proof of a `hoice` run.
+    ///
+    /// Proofs are either derivations as printed by `--proof_format sexpr`, or traces as printed
+    /// by `--proof_format trace`.
+    pub fn parse_proof(mut self) -> Res<Proof> {
+        self.ws_cmt();
+        if self.tag_opt("unsat") {
+            self.ws_cmt();
+        }
+
+        let error = "expected a derivation or a trace, see `--proof_format`";
+
+        self.char('(').chain_err(|| error)?;
+        self.ws_cmt();
+        let proof = if self.tag_opt("derivation") {
+            self.ws_cmt();
+            self.derivation()?
+        } else {
+            self.trace()?
+        };
+
+        self.char(')').chain_err(|| "closing proof")?;
+        self.ws_cmt();
+
+        if self.has_next() {
+            print!("> `");
+            while let Some(next) = self.next() {
+                if next != '\n' {
+                    print!("{}", next)
+                } else {
+                    break;
+                }
+            }
+            println!("`");
+            error_chain::bail!("could not parse the whole proof file")
+        }
+
+        Ok(proof)
+    }
 }
@@ -0,0 +1,576 @@
+//! Checks the unsat proofs of `hoice` runs.
+//!
+//! A proof is a derivation, as printed by `--proof_format sexpr`, or a trace as printed by
+//! `--proof_format trace` which is turned into a derivation. Each node of the derivation
+//! references a clause of the input, gives values for (some of) its variables, and derives either
+//! a fact, *i.e.* a predicate applied to values, or `false` from the facts of its children. The
+//! root of the derivation is node `0` and derives `false`.
+//!
+//! The checker replays each node reachable from the root exactly once: the predicates are defined
+//! as the facts of the children of the node, and the premise of the referenced clause must be
+//! satisfiable together with the values of the node and the negation of its conclusion (for
+//! `false`) or the equality between its conclusion and the fact derived. The proof is valid if
+//! all the steps are, and the derivation is acyclic.
+//!
+//! As for model checking, clauses are only manipulated as strings: the body of a clause is split
+//! in its premise and its conclusion, but is not parsed further. Unknown results from the solver
+//! count as failures.
+
+use crate::{
+    check::{
+        parse::InParser, set_clause_timeout, Args, Clause, Ident, Input, Parser, Pred, Term, Value,
+    },
+    common::{conf, ColorExt, Instance, Read, Solver},
+    errors::*,
+};
+
+/// A fact of a proof: a predicate applied to values.
+///
+/// Values `_` are not specified: the fact stands for all the values of these arguments.
+#[derive(Clone)]
+pub struct Fact {
+    /// Predicate.
+    pub pred: Pred,
+    /// Arguments.
+    pub args: Vec<Value>,
+}
+impl ::std::fmt::Display for Fact {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(fmt, "({}", self.pred)?;
+        for arg in &self.args {
+            write!(fmt, " {}", arg)?
+        }
+        write!(fmt, ")")
+    }
+}
+
+/// A node of a derivation.
+#[derive(Clone)]
+pub struct Node {
+    /// Index of the clause used, if known.
+    pub clause: Option<usize>,
+    /// Name of the clause used, if any.
+    pub name: Option<Ident>,
+    /// Values of the variables of the clause.
+    pub values: Vec<(Ident, Value)>,
+    /// Fact derived, `None` for `false`.
+    pub fact: Option<Fact>,
+    /// Nodes deriving the facts used by the clause.
+    pub children: Vec<usize>,
+}
+
+/// A frame of a trace.
+///
+/// Positive frames derive the fact they yield, negative ones derive their right-hand side.
+#[derive(Clone)]
+pub struct Frame {
+    /// Name of the clause used, `clause_<index>` if it has none.
+    pub clause: Ident,
+    /// Values of the variables of the clause.
+    pub values: Vec<(Ident, Value)>,
+    /// Facts of the left-hand side.
+    pub lhs: Vec<Fact>,
+    /// Fact of the right-hand side, `None` for `false`.
+    pub rhs: Option<Fact>,
+    /// True if the frame yields a positive sample.
+    pub positive: bool,
+    /// Sample yielded.
+    pub yields: Fact,
+}
+
+/// Unsat proof from the output of a `hoice` run.
+pub struct Proof {
+    /// Nodes of the derivation, the root is the first one.
+    pub nodes: Vec<Node>,
+}
+impl Proof {
+    /// Turns the frames of a trace into a derivation.
+    ///
+    /// The root is the frame deriving `false`, and the children of a node are the nodes deriving
+    /// the facts of its left-hand side.
+    pub fn of_frames(frames: Vec<Frame>) -> Res<Self> {
+        let root = if let Some(root) = frames
+            .iter()
+            .position(|frame| !frame.positive && frame.rhs.is_none())
+        {
+            root
+        } else {
+            error_chain::bail!("no frame of the trace derives false")
+        };
+
+        let mut lhss = Vec::with_capacity(frames.len());
+        let mut nodes = Vec::with_capacity(frames.len());
+        let order = Some(root)
+            .into_iter()
+            .chain((0..frames.len()).filter(|idx| *idx != root));
+        for idx in order {
+            let Frame {
+                clause,
+                values,
+                lhs,
+                rhs,
+                positive,
+                yields,
+            } = frames[idx].clone();
+            let index = clause
+                .trim_matches('|')
+                .trim_start_matches("clause_")
+                .parse()
+                .ok();
+            lhss.push(lhs);
+            nodes.push(Node {
+                clause: index,
+                name: Some(clause),
+                values,
+                fact: if positive { Some(yields) } else { rhs },
+                children: vec![],
+            })
+        }
+
+        for (id, lhs) in lhss.into_iter().enumerate() {
+            for fact in lhs {
+                let child = nodes.iter().enumerate().position(|(child, node)| {
+                    child != id
+                        && node.fact.as_ref().map_or(false, |derived| {
+                            derived.pred == fact.pred
+                                && derived.args.len() == fact.args.len()
+                                && derived
+                                    .args
+                                    .iter()
+                                    .zip(fact.args.iter())
+                                    .all(|(derived, arg)| derived == "_" || derived == arg)
+                        })
+                });
+                if let Some(child) = child {
+                    nodes[id].children.push(child)
+                } else {
+                    error_chain::bail!(
+                        "no frame of the trace derives {}",
+                        conf.emph(fact.to_string())
+                    )
+                }
+            }
+        }
+
+        Ok(Proof { nodes })
+    }
+
+    /// Loads a proof from a file.
+    pub fn of_file(file: &str) -> Res<Self> {
+        use std::fs::OpenOptions;
+        log_info! { "loading hoice proof file {}...", conf.emph(file) }
+        let mut buff = String::new();
+        OpenOptions::new()
+            .read(true)
+            .open(file)
+            .chain_err(|| format!("while opening file {}", conf.emph(file)))?
+            .read_to_string(&mut buff)
+            .chain_err(|| format!("while reading file {}", conf.emph(file)))?;
+        Self::of_str(&buff)
+    }
+    /// Loads a proof from a string.
+    pub fn of_str(data: &str) -> Res<Self> {
+        InParser::new(data).parse_proof()
+    }
+}
+
+/// Conclusion of a clause.
+enum Conclusion {
+    /// Predicate application.
+    App(Pred, Vec<Term>),
+    /// Anything else.
+    Term(Term),
+}
+
+/// A clause split in its premise and its conclusion.
+struct SplitClause {
+    /// Arguments.
+    args: Args,
+    /// Premise.
+    premise: Term,
+    /// Conclusion.
+    conclusion: Conclusion,
+}
+
+/// Aggregates the input data and the proof.
+pub struct ProofData {
+    /// Input data.
+    pub input: Input,
+    /// Proof.
+    pub proof: Proof,
+    /// Clauses of the input, split.
+    clauses: Vec<SplitClause>,
+}
+impl ProofData {
+    /// Direct constructor.
+    pub fn new(input: Input, proof: Proof) -> Res<Self> {
+        for fact in proof.nodes.iter().filter_map(|node| node.fact.as_ref()) {
+            if let Some(dec) = input.pred_decs.iter().find(|dec| dec.pred == fact.pred) {
+                if dec.sig.len() != fact.args.len() {
+                    error_chain::bail!(
+                        "fact {} does not match the signature of {}",
+                        conf.emph(fact.to_string()),
+                        conf.emph(&fact.pred)
+                    )
+                }
+            } else {
+                error_chain::bail!("unknown predicate {} in proof", conf.emph(&fact.pred))
+            }
+        }
+
+        let mut clauses = Vec::with_capacity(input.clauses.len());
+        for (count, clause) in input.clauses.iter().enumerate() {
+            let clause = Self::split_clause(&input, clause)
+                .chain_err(|| format!("while splitting clause {}", count))?;
+            clauses.push(clause)
+        }
+
+        Ok(ProofData {
+            input,
+            proof,
+            clauses,
+        })
+    }
+
+    /// Reads two files for input data and proof.
+    pub fn of_files(input_file: &str, proof_file: &str) -> Res<Self> {
+        let input = Input::of_file(input_file)?;
+        let proof = Proof::of_file(proof_file)?;
+        Self::new(input, proof)
+    }
+
+    /// Splits a clause in its premise and its conclusion.
+    fn split_clause(input: &Input, Clause { args, body, .. }: &Clause) -> Res<SplitClause> {
+        let (premise, conclusion) = match InParser::elems_of(body)? {
+            Some(ref elems) if elems.len() > 2 && elems[0] == "=>" => {
+                let last = elems.len() - 1;
+                let mut premise = "(and".to_string();
+                for elem in &elems[1..last] {
+                    premise.push(' ');
+                    premise.push_str(elem)
+                }
+                premise.push(')');
+                (premise, elems[last].clone())
+            }
+            Some(ref elems) if elems.len() == 2 && elems[0] == "not" => {
+                (elems[1].clone(), "false".into())
+            }
+            _ => ("true".into(), body.clone()),
+        };
+
+        let (head, conclusion_args) = match InParser::elems_of(&conclusion)? {
+            Some(mut elems) => {
+                if elems.is_empty() {
+                    error_chain::bail!("illegal empty s-expression")
+                }
+                let head = elems.remove(0);
+                (head, elems)
+            }
+            None => (conclusion.clone(), vec![]),
+        };
+
+        let unquote = |s: &str| s.trim_matches('|').to_string();
+        let head = unquote(&head);
+        let conclusion = if let Some(dec) = input
+            .pred_decs
+            .iter()
+            .find(|dec| unquote(&dec.pred) == head)
+        {
+            if dec.sig.len() != conclusion_args.len() {
+                error_chain::bail!(
+                    "application of {} does not match its signature",
+                    conf.emph(&dec.pred)
+                )
+            }
+            Conclusion::App(dec.pred.clone(), conclusion_args)
+        } else {
+            Conclusion::Term(conclusion)
+        };
+
+        Ok(SplitClause {
+            args: args.clone(),
+            premise,
+            conclusion,
+        })
+    }
+
+    /// Resets the solver and defines the predicates as the facts derived so far.
+    fn prepare(&self, solver: &mut Solver<Parser>, derived: &[&Fact]) -> Res<()> {
+        solver.reset()?;
+        set_clause_timeout(solver)?;
+
+        for unknown in &self.input.unknown {
+            use std::io::Write;
+            writeln!(solver, "{}", unknown)?
+        }
+
+        for dec in &self.input.pred_decs {
+            let args: Args = dec
+                .sig
+                .iter()
+                .enumerate()
+                .map(|(idx, typ)| (format!("|v_{}|", idx), typ.clone()))
+                .collect();
+            let mut body = "(or".to_string();
+            for fact in derived.iter().filter(|fact| fact.pred == dec.pred) {
+                body.push_str(" (and true");
+                for ((arg, _), val) in args.iter().zip(fact.args.iter()) {
+                    if val != "_" {
+                        body.push_str(&format!(" (= {} {})", arg, val))
+                    }
+                }
+                body.push(')')
+            }
+            body.push_str(" false)");
+            solver.define_fun(&dec.pred, &args, &"Bool".to_string(), &body)?
+        }
+
+        Ok(())
+    }
+
+    /// Clause referenced by a node.
+    ///
+    /// Uses the name of the clause if it is the name of a clause of the input, its index
+    /// otherwise.
+    fn clause_of(&self, node: &Node) -> Res<&SplitClause> {
+        if let Some(name) = node.name.as_ref() {
+            if let Some(idx) = self
+                .input
+                .clauses
+                .iter()
+                .position(|clause| clause.name.as_ref() == Some(name))
+            {
+                return Ok(&self.clauses[idx]);
+            }
+        }
+        if let Some(clause) = node.clause.and_then(|idx| self.clauses.get(idx)) {
+            Ok(clause)
+        } else if let Some(name) = node.name.as_ref() {
+            error_chain::bail!("unknown clause {}", conf.emph(name))
+        } else {
+            error_chain::bail!(
+                "unknown clause, the input has {} clauses",
+                self.clauses.len()
+            )
+        }
+    }
+
+    /// Checks a derivation step.
+    ///
+    /// The premise of the clause of the node must be satisfiable when its variables have the
+    /// values of the node, and its conclusion is the fact of the node (false if none) given the
+    /// facts of its children. For the unspecified arguments of the fact, this must hold for all
+    /// their values.
+    fn check_step(&self, solver: &mut Solver<Parser>, node: &Node) -> Res<Option<bool>> {
+        let clause = self.clause_of(node)?;
+
+        let mut derived = Vec::with_capacity(node.children.len());
+        for &child in &node.children {
+            match self.proof.nodes.get(child) {
+                Some(Node {
+                    fact: Some(fact), ..
+                }) => derived.push(fact),
+                Some(_) => error_chain::bail!("child {} derives false", child),
+                None => error_chain::bail!("unknown child {}", child),
+            }
+        }
+
+        // Unspecified arguments of the fact.
+        let mut any: Args = vec![];
+
+        let mut step = format!("(and {}", clause.premise);
+
+        let unquote = |s: &str| s.trim_matches('|').to_string();
+        for (var, val) in &node.values {
+            if let Some((ident, _)) = clause
+                .args
+                .iter()
+                .find(|(ident, _)| unquote(ident) == unquote(var))
+            {
+                step.push_str(&format!(" (= {} {})", ident, val))
+            } else {
+                warn!(
+                    "ignoring value of unknown variable {} of the clause",
+                    conf.emph(var)
+                )
+            }
+        }
+
+        match (node.fact.as_ref(), &clause.conclusion) {
+            (Some(fact), Conclusion::App(pred, args)) if *pred == fact.pred => {
+                let sig = self
+                    .input
+                    .pred_decs
+                    .iter()
+                    .find(|dec| dec.pred == fact.pred)
+                    .map(|dec| &dec.sig)
+                    .ok_or_else::<Error, _>(|| {
+                        format!("unknown predicate {}", conf.emph(&fact.pred)).into()
+                    })?;
+                for (idx, (arg, val)) in args.iter().zip(fact.args.iter()).enumerate() {
+                    if val == "_" {
+                        let ident = format!("|hoice_any_{}|", idx);
+                        step.push_str(&format!(" (= {} {})", arg, ident));
+                        any.push((ident, sig[idx].clone()))
+                    } else {
+                        step.push_str(&format!(" (= {} {})", arg, val))
+                    }
+                }
+            }
+            (None, Conclusion::Term(term)) => step.push_str(&format!(" (not {})", term)),
+            (Some(fact), _) => {
+                log_info! { "the clause of the node does not conclude with {}", fact.pred }
+                return Ok(Some(false));
+            }
+            (None, _) => {
+                log_info! { "the clause of the node concludes with a predicate application" }
+                return Ok(Some(false));
+            }
+        }
+        step.push(')');
+
+        self.prepare(solver, &derived)?;
+
+        if any.is_empty() {
+            for &(ref ident, ref typ) in &clause.args {
+                solver.declare_const(ident, typ)?
+            }
+            solver.assert(&step)?;
+            return Ok(solver.check_sat_or_unk()?);
+        }
+
+        for &(ref ident, ref typ) in &any {
+            solver.declare_const(ident, typ)?
+        }
+        let step = if clause.args.is_empty() {
+            step
+        } else {
+            let mut qvars = String::new();
+            for &(ref ident, ref typ) in &clause.args {
+                qvars.push_str(&format!(" ({} {})", ident, typ))
+            }
+            format!("(exists ({}) {})", qvars, step)
+        };
+        solver.assert(&format!("(not {})", step))?;
+
+        Ok(solver.check_sat_or_unk()?.map(|sat| !sat))
+    }
+
+    /// Nodes reachable from the root, fails if the derivation is not a DAG.
+    fn reachable(&self) -> Res<Vec<usize>> {
+        // `None`: not visited, `Some(false)`: being visited, `Some(true)`: visited.
+        let mut visited: Vec<Option<bool>> = vec![None; self.proof.nodes.len()];
+        let mut reachable = vec![];
+        // Node, and whether its children have been pushed.
+        let mut stack = vec![(0, false)];
+
+        while let Some((id, done)) = stack.pop() {
+            if done {
+                visited[id] = Some(true);
+                reachable.push(id);
+                continue;
+            }
+            match visited[id] {
+                Some(true) => continue,
+                Some(false) => error_chain::bail!("node {} is part of a cycle", id),
+                None => visited[id] = Some(false),
+            }
+            stack.push((id, true));
+            for &child in &self.proof.nodes[id].children {
+                if child >= self.proof.nodes.len() {
+                    error_chain::bail!("unknown child {} of node {}", child, id)
+                }
+                if visited[child] == Some(false) {
+                    error_chain::bail!("node {} is part of a cycle", child)
+                }
+                if visited[child].is_none() {
+                    stack.push((child, false))
+                }
+            }
+        }
+
+        Ok(reachable)
+    }
+
+    /// Checks the proof using an SMT solver.
+    pub fn check(&self, solver: &mut Solver<Parser>) -> Res<()> {
+        if self.proof.nodes.is_empty() {
+            error_chain::bail!("the derivation is empty")
+        }
+        if self.proof.nodes[0].fact.is_some() {
+            error_chain::bail!("the root of the derivation does not derive false")
+        }
+
+        let reachable = self.reachable()?;
+        if reachable.len() < self.proof.nodes.len() {
+            warn!(
+                "{} nodes of the derivation are not reachable from the root",
+                self.proof.nodes.len() - reachable.len()
+            )
+        }
+
+        let mut okay = true;
+        let mut unknown = false;
+
+        for id in reachable {
+            let node = &self.proof.nodes[id];
+            match self
+                .check_step(solver, node)
+                .chain_err(|| format!("while checking node {}", id))?
+            {
+                Some(true) => log_info! { "node {} is okay", id },
+                Some(false) => {
+                    okay = false;
+                    println!();
+                    println!("({} \"", conf.bad("error"));
+                    if let Some(fact) = node.fact.as_ref() {
+                        println!("  node {} cannot derive {}", id, fact);
+                    } else {
+                        println!("  node {} cannot derive false", id);
+                    }
+                    println!("\")");
+                }
+                None => unknown = true,
+            }
+        }
+
+        if !okay {
+            error_chain::bail!("some steps of the derivation are not valid")
+        } else if unknown {
+            error_chain::bail!("some steps of the derivation could not be checked (unknown)")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Checks the unsat proof of a `hoice` run from two files.
+pub fn do_it(input_file: &str, proof_file: &str) -> Res<()> {
+    let data = ProofData::of_files(input_file, proof_file)?;
+
+    log! { @4 "spawning solver" }
+
+    let mut solver = conf.solver.spawn("check", Parser, &Instance::new())?;
+
+    let res = data.check(&mut solver);
+    if res.is_ok() {
+        println!("(unsafe)")
+    }
+
+    let end_res = solver.kill().chain_err(|| "While killing solver");
+
+    res.and_then(|_| end_res)
+}
+
+/// Checks the unsat proof of a `hoice` run, given as a string, against an input file.
+pub fn do_it_from_str<P: AsRef<::std::path::Path>>(input_file: P, proof: &str) -> Res<()> {
+    let data = ProofData::new(
+        Input::of_file(input_file).chain_err(|| "while loading input file")?,
+        Proof::of_str(proof).chain_err(|| "while loading proof")?,
+    )?;
+
+    let mut solver = conf.solver.spawn("check", Parser, &Instance::new())?;
+    let res = data.check(&mut solver);
+    let end_res = solver.kill().chain_err(|| "While killing solver");
+    res.and_then(|_| end_res)
+}
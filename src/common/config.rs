@@ -10,7 +10,7 @@ use clap::{crate_authors, crate_name, Arg};
 use error_chain::bail;
 use rsmt2::SmtConf as SolverConf;
 
-use crate::{common::mk_dir, errors::*, instance::Instance};
+use crate::{common::mk_dir, errors::*, instance::Instance, unsat_core::ProofFormat};
 
 /// Creates a function adding arguments to a `::clap::App`.
 macro_rules! app_fun {
@@ -375,6 +375,42 @@ make_conf! {
             |val| bool_of_match(val)
         }
 
+        log_provenance, log_provenance: bool {
+            help "(De)activates the provenance log of pre-processing.",
+            long_help "\
+                If active, the predicates pre-processing creates, defines or removes arguments \
+                from, and the clauses it creates or drops, are logged as JSON in \
+                `preproc/provenance.json` in the output directory, with the strategy \
+                responsible and the original clause each clause stems from.\
+            ",
+            long "--log_provenance",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "no",
+        } {
+            |val| bool_of_match(val)
+        }
+
+        qe, qe: bool {
+            help "(De)activates quantifier elimination for linear arithmetic.",
+            long_help "\
+                If active, existentially quantified arithmetic variables in the predicates' \
+                definitions and clause-local variables are eliminated when the result does not \
+                blow up.\
+            ",
+            long "--qe",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "off",
+            hidden,
+        } {
+            |val| bool_of_match(val)
+        }
+
         prune_terms, prune_terms: bool {
             help "(De)activates expensive clause term pruning when simplifying clauses.",
             long_help "\
@@ -464,6 +500,132 @@ make_conf! {
             |val| bool_of_match(val)
         }
 
+        coi, coi: bool {
+            help "(De)activates cone-of-influence slicing.",
+            long_help "\
+                If active, predicates that cannot be reached from the positive clauses are forced \
+                to false, and predicates that cannot reach a negative clause are forced to true. \
+                Reachability is computed on the dependency graph of the predicates.\
+            ",
+            long "--coi",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "off",
+            hidden,
+        } {
+            |val| bool_of_match(val)
+        }
+
+        bool_split, bool_split: bool {
+            help "(De)activates case-splitting of predicates on their boolean arguments.",
+            long_help "\
+                If active, predicates with a boolean argument that is a constant in all the \
+                clauses they are the consequent of are split in two predicates, one for each \
+                value of the argument. The definitions are recombined in the final model.\
+            ",
+            long "--bool_split",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "off",
+            hidden,
+        } {
+            |val| bool_of_match(val)
+        }
+
+        pred_merge, pred_merge: bool {
+            help "(De)activates merging of equivalent predicates.",
+            long_help "\
+                If active, predicates with the same signature whose defining clauses are the \
+                same modulo renaming are merged into a single predicate. The definitions of the \
+                predicates merged are restored in the final model.\
+            ",
+            long "--pred_merge",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "off",
+            hidden,
+        } {
+            |val| bool_of_match(val)
+        }
+
+        arg_prop, arg_prop: bool {
+            help "(De)activates constant and equality propagation across predicate arguments.",
+            long_help "\
+                If active, predicate arguments that are always equal to a constant or to another \
+                argument (plus a constant) are removed. The invariants are inferred by a fixpoint \
+                over the clauses, and restored in the final model.\
+            ",
+            long "--arg_prop",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "off",
+            hidden,
+        } {
+            |val| bool_of_match(val)
+        }
+
+        adt_flatten, adt_flatten: bool {
+            help "(De)activates flattening of non-recursive datatypes.",
+            long_help "\
+                If active, predicate arguments of a non-recursive datatype are replaced by their \
+                fields, and by an integer tag for datatypes with more than one constructor. The \
+                model is reassembled over the original signature.\
+            ",
+            long "--adt_flatten",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "off",
+            hidden,
+        } {
+            |val| bool_of_match(val)
+        }
+
+        array_elim, array_elim: bool {
+            help "(De)activates the elimination of arrays only accessed at fixed indices.",
+            long_help "\
+                If active, predicate arguments of array type that are only read at constant \
+                indices or at indices given by other arguments are replaced by one argument per \
+                index. The model is reassembled over the original signature.\
+            ",
+            long "--array_elim",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "off",
+            hidden,
+        } {
+            |val| bool_of_match(val)
+        }
+
+        loop_accel, loop_accel: bool {
+            help "(De)activates the acceleration of self-loops with constant increments.",
+            long_help "\
+                If active, self-loops that increment integer arguments by constants under a \
+                conjunction of linear constraints are accelerated: a clause relating the \
+                arguments before and after any number of iterations is added to the instance.\
+            ",
+            long "--loop_accel",
+            takes_val,
+            val_name bool_format,
+            val_nb 1,
+            validator bool_validator,
+            default "off",
+            hidden,
+        } {
+            |val| bool_of_match(val)
+        }
+
         log_pred_dep, log_pred_dep: bool {
             help "(De)activates predicate dependency dumps (cfg_red).",
             long_help "\
@@ -587,6 +749,31 @@ make_conf! {
             |val| bool_of_match(val)
         }
 
+        preproc_script, script: Option<crate::preproc::Script> {
+            help "Pre-processing script, `default` for the built-in pipeline.",
+            long_help "\
+                Describes the order in which the pre-processors run, for instance \
+                `simplify; fixpoint(arg_red; one_rhs; one_lhs); cfg_red; strict_neg`. Steps are \
+                separated by `;`, `fixpoint(...)` runs its steps until they do not change the \
+                instance. A step can be given a budget with `@<int>`: the maximum number of runs \
+                for a pre-processor, the maximum number of iterations for a fixpoint. \
+                Pre-processors mentioned in the script run even if their own flag is off. Can \
+                also be given in the input file with `(set-option :preproc-script \"...\")`.\
+            ",
+            long "--preproc_script",
+            takes_val,
+            val_name "script",
+            val_nb 1,
+            validator crate::preproc::script::script_validator,
+            default "default",
+        } {
+            |val| if val == "default" {
+                None
+            } else {
+                Some(val.parse().expect("unreachable: script was validated"))
+            }
+        }
+
         fun_preds, fun_preds: bool {
             help "(De)activates predicate-to-function reduction.",
             long_help "\
@@ -607,7 +794,7 @@ make_conf! {
 
     impl SubConf for PreprocConf {
         fn need_out_dir(&self) -> bool {
-            self.log_preproc && self.active || self.log_pred_dep
+            (self.log_preproc || self.log_provenance) && self.active || self.log_pred_dep
         }
     }
 
@@ -652,6 +839,30 @@ make_conf! {
             }
         }
 
+        /// Provenance log file.
+        pub fn provenance_file(&self, instance: &Instance) -> Res<Option<::std::fs::File>> {
+            use std::fs::OpenOptions;
+            if self.log_provenance && self.active {
+                let mut path = self.log_dir("", instance)?;
+                path.push("provenance");
+                path.set_extension("json");
+                let file = OpenOptions::new()
+                    .write(true)
+                    .truncate(true)
+                    .create(true)
+                    .open(&path)
+                    .chain_err(|| {
+                        format!(
+                            "while creating provenance log file {}",
+                            path.to_string_lossy()
+                        )
+                    })?;
+                Ok(Some(file))
+            } else {
+                Ok(None)
+            }
+        }
+
         /// Predicate dependency file.
         pub fn pred_dep_file<S: AsRef<str>>(
             &self,
@@ -1103,6 +1314,78 @@ make_conf! {
         } {
             |mtch| bool_of_match(mtch)
         }
+
+        incremental, incremental: bool {
+            help "(De)activates incremental counterexample queries in the teacher.",
+            long_help "\
+                If active, the teacher declares the predicates once and guards each candidate \
+                definition by a fresh activation literal. Clause checks then use \
+                `check-sat-assuming`, so that the solver keeps what it learnt across learning \
+                steps. Ignored when the solver is restarted for each counterexample query (see \
+                `--restart_on_cex`).\
+            ",
+            long "--incremental",
+            validator bool_validator,
+            val_name bool_format,
+            default "off",
+            takes_val,
+            val_nb 1,
+        } {
+            |mtch| bool_of_match(mtch)
+        }
+
+        cexs_per_clause, cexs_per_clause: usize {
+            help "Maximum number of (unbiased) counterexamples extracted for each clause.",
+            long_help "\
+                Number of counterexamples the teacher tries to extract for each falsifiable \
+                clause. After the first one, each new counterexample is required to differ from \
+                the previous ones (see `--cex_signs`). Biased counterexamples do not count \
+                towards this limit.\
+            ",
+            long "--cexs_per_clause",
+            validator int_validator,
+            val_name "int",
+            default "1",
+            takes_val,
+            val_nb 1,
+        } {
+            |mtch| int_of_match(mtch)
+        }
+
+        cex_signs, cex_signs: bool {
+            help "Forces additional counterexamples to differ on the sign of arithmetic variables.",
+            long_help "\
+                If active and `--cexs_per_clause` is greater than one, additional \
+                counterexamples for a clause are required to have a different sign pattern for \
+                the arithmetic variables than the previous ones. Otherwise, they are only \
+                required to have different values.\
+            ",
+            long "--cex_signs",
+            validator bool_validator,
+            val_name bool_format,
+            default "off",
+            takes_val,
+            val_nb 1,
+        } {
+            |mtch| bool_of_match(mtch)
+        }
+
+        cex_cache, cex_cache: bool {
+            help "(De)activates caching of valid clauses between counterexample queries.",
+            long_help "\
+                If active, the teacher remembers the clauses it proved valid along with the \
+                candidates of the predicates they mention. Such a clause is not checked again \
+                until the candidate of one of its predicates changes.\
+            ",
+            long "--cex_cache",
+            validator bool_validator,
+            val_name bool_format,
+            default "off",
+            takes_val,
+            val_nb 1,
+        } {
+            |mtch| bool_of_match(mtch)
+        }
     }
 
     impl SubConf for TeacherConf {
@@ -1127,6 +1410,16 @@ pub struct Config {
     pub split: bool,
     /// Pause between negative clauses when in split mode.
     pub split_step: bool,
+    /// Minimize unsat cores by re-solving subsets of the assertions.
+    pub minimize_core: bool,
+    /// Validate models against the original script before answering `sat`.
+    pub validate: bool,
+    /// Format of the unsat proofs.
+    pub proof_format: ProofFormat,
+    /// Output models and proofs as CHC-COMP witnesses.
+    pub witness: bool,
+    /// Simplify models before validating and printing them.
+    pub simplify_model: bool,
     /// Instant at which we'll timeout.
     timeout: Option<Instant>,
     /// Output directory.
@@ -1136,12 +1429,24 @@ pub struct Config {
 
     /// Result check file.
     check: Option<String>,
+    /// Unsat proof check file.
+    check_proof: Option<String>,
     /// Eldarica result checking flag.
     pub check_eld: bool,
+    /// Strict result checking flag, clauses that cannot be checked are failures.
+    pub check_strict: bool,
+    /// Timeout in seconds for checking a single clause, `0` for none.
+    pub check_tmo: usize,
     /// If true, SMT-check all simplifications.
     pub check_simpl: bool,
     /// Level of term simplification.
     pub term_simpl: usize,
+    /// File to dump the learning data to.
+    dump_data: Option<String>,
+    /// File to load learning data from.
+    load_data: Option<String>,
+    /// Model file to warm-start the teacher with.
+    warm_start: Option<String>,
 
     /// Instance and factory configuration.
     pub instance: InstanceConf,
@@ -1180,6 +1485,35 @@ impl Config {
     pub fn check_file(&self) -> Option<&String> {
         self.check.as_ref()
     }
+    /// Unsat proof to check file.
+    #[inline]
+    pub fn check_proof_file(&self) -> Option<&String> {
+        self.check_proof.as_ref()
+    }
+
+    /// File to dump the learning data to.
+    #[inline]
+    pub fn dump_data_file(&self) -> Option<&String> {
+        self.dump_data.as_ref()
+    }
+    /// Temporary file the learning data is dumped to, replaces the dump file at the end of the run.
+    ///
+    /// Dumping to the actual file directly would erase it before it is loaded when it is also the
+    /// file to load learning data from.
+    #[inline]
+    pub fn dump_data_tmp_file(&self) -> Option<String> {
+        self.dump_data.as_ref().map(|file| format!("{}.tmp", file))
+    }
+    /// File to load learning data from.
+    #[inline]
+    pub fn load_data_file(&self) -> Option<&String> {
+        self.load_data.as_ref()
+    }
+    /// Model file to warm-start the teacher with.
+    #[inline]
+    pub fn warm_start_file(&self) -> Option<&String> {
+        self.warm_start.as_ref()
+    }
 
     /// Checks if we're out of time.
     #[inline]
@@ -1261,14 +1595,37 @@ impl Config {
 
         let split = bool_of_matches(&matches, "split");
 
+        // Unsat core minimization.
+        let minimize_core = bool_of_matches(&matches, "minimize_core");
+
+        // Model validation.
+        let validate = bool_of_matches(&matches, "validate");
+
+        // Unsat proof format.
+        let proof_format = matches
+            .value_of("proof_format")
+            .expect("unreachable(proof_format): default is provided")
+            .parse()
+            .expect("unreachable(proof_format): value is validated");
+        let witness = bool_of_matches(&matches, "witness");
+        let simplify_model = bool_of_matches(&matches, "simplify_model");
+
         // Result checking.
         let check = matches.value_of("check").map(|s| s.to_string());
+        let check_proof = matches.value_of("check_proof").map(|s| s.to_string());
         let check_eld = bool_of_matches(&matches, "check_eld");
+        let check_strict = bool_of_matches(&matches, "check_strict");
+        let check_tmo = int_of_matches(&matches, "check_timeout");
         let check_simpl = bool_of_matches(&matches, "check_simpl");
 
         // Timeout.
         let term_simpl = int_of_matches(&matches, "term_simpl");
 
+        // Learning data persistence.
+        let dump_data = matches.value_of("dump_data").map(|s| s.to_string());
+        let load_data = matches.value_of("load_data").map(|s| s.to_string());
+        let warm_start = matches.value_of("warm_start").map(|s| s.to_string());
+
         let instance = InstanceConf::new(&matches);
         let preproc = PreprocConf::new(&matches);
         let solver = SmtConf::new(&matches);
@@ -1282,13 +1639,24 @@ impl Config {
             infer,
             split,
             split_step,
+            minimize_core,
+            validate,
+            proof_format,
+            witness,
+            simplify_model,
             timeout,
             out_dir,
             styles,
             check,
+            check_proof,
             check_eld,
+            check_strict,
+            check_tmo,
             check_simpl,
             term_simpl,
+            dump_data,
+            load_data,
+            warm_start,
             instance,
             preproc,
             solver,
@@ -1410,6 +1778,74 @@ impl Config {
                     .number_of_values(1)
                     .display_order(order()),
             )
+            .arg(
+                Arg::with_name("minimize_core")
+                    .long("--minimize_core")
+                    .help("minimizes unsat cores by re-solving subsets of the assertions")
+                    .validator(bool_validator)
+                    .value_name(bool_format)
+                    .default_value("off")
+                    .takes_value(true)
+                    .number_of_values(1)
+                    .display_order(order()),
+            )
+            .arg(
+                Arg::with_name("validate")
+                    .long("--validate")
+                    .help(
+                        "checks models against the original script before answering `sat`, \
+                         answers `unknown` if the check fails",
+                    )
+                    .validator(bool_validator)
+                    .value_name(bool_format)
+                    .default_value("off")
+                    .takes_value(true)
+                    .number_of_values(1)
+                    .display_order(order()),
+            )
+            .arg(
+                Arg::with_name("proof_format")
+                    .long("--proof_format")
+                    .help(
+                        "format of the unsat proofs: traces of the contradicting samples \
+                         (default, `get-proof` used to print the entry points), entry points, \
+                         or derivation trees as S-expressions or in JSON",
+                    )
+                    .validator(proof_format_validator)
+                    .value_name("trace|entries|sexpr|json")
+                    .default_value("trace")
+                    .takes_value(true)
+                    .number_of_values(1)
+                    .display_order(order()),
+            )
+            .arg(
+                Arg::with_name("witness")
+                    .long("--witness")
+                    .help(
+                        "outputs models and unsat proofs as CHC-COMP witnesses, \
+                         overrides `--proof_format`",
+                    )
+                    .validator(bool_validator)
+                    .value_name(bool_format)
+                    .default_value("off")
+                    .takes_value(true)
+                    .number_of_values(1)
+                    .display_order(order()),
+            )
+            .arg(
+                Arg::with_name("simplify_model")
+                    .long("--simplify_model")
+                    .help(
+                        "simplifies models before validating and printing them, simplified \
+                         definitions are checked equivalent to the original ones",
+                    )
+                    .validator(bool_validator)
+                    .value_name(bool_format)
+                    .default_value("off")
+                    .takes_value(true)
+                    .number_of_values(1)
+                    .display_order(order()),
+            )
             .arg(
                 Arg::with_name("term_simpl")
                     .long("--term_simpl")
@@ -1434,6 +1870,36 @@ impl Config {
                     .display_order(order())
                     .hidden(true),
             )
+            .arg(
+                Arg::with_name("dump_data")
+                    .long("--dump_data")
+                    .help("dumps the learning data to a file at the end of the run")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .number_of_values(1)
+                    .display_order(order()),
+            )
+            .arg(
+                Arg::with_name("load_data")
+                    .long("--load_data")
+                    .help(
+                        "seeds the learning data with the facts of a dump that \
+                         can be justified on the input system",
+                    )
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .number_of_values(1)
+                    .display_order(order()),
+            )
+            .arg(
+                Arg::with_name("warm_start")
+                    .long("--warm_start")
+                    .help("uses the model in a file as the first candidate")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .number_of_values(1)
+                    .display_order(order()),
+            )
     }
 
     /// Add args related to result checking.
@@ -1452,6 +1918,18 @@ impl Config {
                 .number_of_values(1)
                 .display_order(order()),
         )
+        .arg(
+            Arg::with_name("check_proof")
+                .long("--check_proof")
+                .help(
+                    "checks an unsat proof, in the `trace` or `sexpr` proof format, for the input \
+                     system (does not run inference)",
+                )
+                .value_name("FILE")
+                .takes_value(true)
+                .number_of_values(1)
+                .display_order(order()),
+        )
         .arg(
             Arg::with_name("check_eld")
                 .long("--check_eld")
@@ -1463,6 +1941,31 @@ impl Config {
                 .number_of_values(1)
                 .display_order(order()),
         )
+        .arg(
+            Arg::with_name("check_strict")
+                .long("--check_strict")
+                .help(
+                    "if `check` is active, clauses that cannot be checked are failures, \
+                     and a summary of the verdicts is printed",
+                )
+                .validator(bool_validator)
+                .value_name(bool_format)
+                .default_value("no")
+                .takes_value(true)
+                .number_of_values(1)
+                .display_order(order()),
+        )
+        .arg(
+            Arg::with_name("check_timeout")
+                .long("--check_timeout")
+                .help("timeout in seconds for checking a single clause, `0` for none")
+                .validator(int_validator)
+                .value_name("int")
+                .default_value("0")
+                .takes_value(true)
+                .number_of_values(1)
+                .display_order(order()),
+        )
     }
 }
 
@@ -1617,6 +2120,12 @@ pub fn bounded_int_validator(s: String, lo: usize, hi: usize) -> Result<(), Stri
     }
 }
 
+/// Validates unsat proof format input.
+#[cfg_attr(feature = "cargo-clippy", allow(needless_pass_by_value))]
+pub fn proof_format_validator(s: String) -> Result<(), String> {
+    s.parse::<ProofFormat>().map(|_| ())
+}
+
 /// Validates boolean input.
 #[cfg_attr(feature = "cargo-clippy", allow(needless_pass_by_value))]
 pub fn bool_validator(s: String) -> Result<(), String> {
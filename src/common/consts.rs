@@ -89,7 +89,7 @@ pub mod errors {
     /// Unsat core asked but not active.
     pub const no_unsat_cores: &str = "\
         unsat core production is not active:\n\
-        consider adding `(set-option :produce-unsat-core true)`\n\
+        consider adding `(set-option :produce-unsat-cores true)`\n\
         at the start of your script
     ";
 }
@@ -182,6 +182,9 @@ pub mod keywords {
                 get_model ("get-model", doc = "Get-model keyword.")
                 get_unsat_core ("get-unsat-core", doc = "Get-unsat-core keyword.")
                 get_proof ("get-proof", doc = "Get-proof keyword.")
+                get_info ("get-info", doc = "Get-info keyword.")
+                get_option ("get-option", doc = "Get-option keyword.")
+                get_value ("get-value", doc = "Get-value keyword.")
 
                 reset ("reset", doc = "Reset keyword.")
                 exit  ("exit", doc = "Exit keyword.")
@@ -50,12 +50,15 @@ macro_rules! unsat {
   }) ;
 }
 
-/// Bails with unknown.
+/// Bails with unknown, the message is kept in the error chain as the reason.
 #[macro_export]
 macro_rules! unknown {
   ($($stuff:tt)*) => ({
     log! { @debug $($stuff)* } ;
-    bail!($crate::errors::ErrorKind::Unknown)
+    bail!($crate::errors::Error::with_chain(
+      $crate::errors::Error::from($crate::errors::ErrorKind::Unknown),
+      format!($($stuff)*)
+    ))
   }) ;
 }
 
@@ -335,6 +335,17 @@ impl Profiler {
         ()
     }
 
+    /// Statistics of the profiler, does not include its sub-profilers.
+    #[cfg(not(feature = "bench"))]
+    pub fn stats(&self) -> Stats {
+        self.stats.borrow().clone()
+    }
+    /// Statistics of the profiler, always empty in bench mode.
+    #[cfg(feature = "bench")]
+    pub fn stats(&self) -> Stats {
+        Stats::new()
+    }
+
     /// Ticks.
     #[cfg(not(feature = "bench"))]
     pub fn tick(&self, scope: Vec<&'static str>) {
@@ -591,6 +591,60 @@ impl<'a, T> Expr2Smt<()> for SmtActSamples<&'a VarValsMap<T>> {
     }
 }
 
+/// Wrapper for an activation literal guarding the definition of a predicate.
+///
+/// `Expr2Smt` writes
+///
+/// ```bash
+/// (=> <actlit> (forall ( (v_0 <typ>) ... ) (= (<pred> v_0 ...) <def>)))
+/// ```
+///
+/// or `(=> <actlit> (= <pred> <def>))` if the predicate has no arguments. Used by the teacher in
+/// incremental mode, where predicates are declared once and never defined: each definition is
+/// asserted once at top level, and holds as long as its actlit is active.
+pub struct SmtActPredDef<'a> {
+    /// Activation literal.
+    pub actlit: &'a Actlit,
+    /// Predicate.
+    pub pred: &'a crate::info::Pred,
+    /// Definition.
+    pub def: &'a Term,
+}
+impl<'a> SmtActPredDef<'a> {
+    /// Constructor.
+    pub fn new(actlit: &'a Actlit, pred: &'a crate::info::Pred, def: &'a Term) -> Self {
+        SmtActPredDef { actlit, pred, def }
+    }
+}
+impl<'a> Expr2Smt<()> for SmtActPredDef<'a> {
+    fn expr_to_smt2<Writer: Write>(&self, w: &mut Writer, _: ()) -> SmtRes<()> {
+        write!(w, "(=> ")?;
+        self.actlit.write(w)?;
+        if self.pred.sig.is_empty() {
+            write!(w, " (= {} ", self.pred.name)?;
+            self.def.write(w, |w, var| var.default_write(w))?;
+            write!(w, "))")?;
+            return Ok(());
+        }
+
+        write!(w, " (forall (")?;
+        for (var, typ) in self.pred.sig.index_iter() {
+            write!(w, " (")?;
+            var.default_write(w)?;
+            write!(w, " {})", typ)?
+        }
+        write!(w, " ) (= ({}", self.pred.name)?;
+        for (var, _) in self.pred.sig.index_iter() {
+            write!(w, " ")?;
+            var.default_write(w)?
+        }
+        write!(w, ") ")?;
+        self.def.write(w, |w, var| var.default_write(w))?;
+        write!(w, ")))")?;
+        Ok(())
+    }
+}
+
 /// Wrapper around some terms and some values for these terms.
 ///
 /// Asserts that each term is equal to the corresponding value.
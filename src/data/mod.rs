@@ -17,8 +17,12 @@ use crate::{
 
 pub mod constraint;
 mod info;
+pub mod persist;
 pub mod sample;
 
+#[cfg(test)]
+mod test;
+
 pub use self::constraint::Constraint;
 use self::info::CstrInfo;
 pub use self::sample::Sample;
@@ -649,7 +653,7 @@ impl Data {
         }
         // let track_samples = instance.track_samples() ;
 
-        let entry_points = if instance.proofs() {
+        let entry_points = if instance.produces_proofs() {
             Some(crate::unsat_core::entry_points::EntryPoints::new())
         } else {
             None
@@ -1172,6 +1176,7 @@ impl Data {
     ///     ).expect("while adding positive data");
     ///     match data.get_unsat_proof().expect("during get_unsat_proof") {
     ///         UnsatRes::None => panic!("expected unsat proof, got none"),
+    ///         UnsatRes::Proof { .. } => panic!("expected entry points, got full proof"),
     ///         UnsatRes::Entry(entry) => {
     ///             assert_eq! { entry.samples.len(), 1 }
     ///             assert! { entry.samples.iter().all(
@@ -0,0 +1,606 @@
+//! Persistence of the learning data across runs.
+//!
+//! At the end of a run, the teacher can [`dump`] its learning data to a file. Samples are written
+//! over the *original* signature of the predicates, so that a dump makes sense regardless of the
+//! pre-processing that happened. Arguments that were removed by pre-processing are written as
+//! *don't care* values `_`. A dump is a list of facts of the form
+//!
+//! ```bash
+//! (pos (<pred> (<sort>*) (<value>*)))
+//! (neg (<pred> (<sort>*) (<value>*)))
+//! (cstr ((<pred> (<sort>*) (<value>*))*) <rhs>)
+//! ```
+//!
+//! where `<rhs>` is either `false` or a predicate application of the same form as the other ones.
+//!
+//! A dump can be [`load`]ed at the beginning of a later run, possibly on a (slightly) different
+//! instance. Facts are matched against the predicates of the new instance by name and original
+//! signature, and the ones mentioning unknown predicates are discarded. Since the instance might
+//! have changed, the samples are **not** trusted: [`Dump::seed`] only adds the ones it can
+//! justify by an instance of a clause of the new instance.
+//!
+//! [`dump`]: fn.dump.html (dump function)
+//! [`load`]: fn.load.html (load function)
+//! [`Dump::seed`]: struct.Dump.html#method.seed (seed function)
+
+use crate::{
+    common::{
+        smt::{DisjArgs, EqConj, FullParser as Parser, SmtTerm},
+        var_to::vals::{RVarVals, VarValsSet},
+        *,
+    },
+    data::{Data, Sample},
+};
+
+/// Writes a predicate application over the original signature of the predicate.
+fn write_app<W: Write>(w: &mut W, instance: &Instance, pred: PrdIdx, args: &RVarVals) -> Res<()> {
+    let info = &instance[pred];
+    let mut vals: VarMap<Val> = info
+        .original_sig()
+        .iter()
+        .map(|typ| val::none(typ.clone()))
+        .collect();
+    for (tgt, src) in info.original_sig_map().index_iter() {
+        vals[*src] = args[tgt].clone()
+    }
+
+    write!(w, "({} (", info.name)?;
+    for typ in info.original_sig().iter() {
+        write!(w, " {}", typ)?
+    }
+    write!(w, " ) (")?;
+    for val in vals.iter() {
+        write!(w, " {}", val)?
+    }
+    write!(w, " ))")?;
+    Ok(())
+}
+
+/// Dumps some learning data.
+///
+/// Tautological constraints are ignored.
+pub fn dump<W: Write>(data: &Data, w: &mut W) -> Res<()> {
+    let instance = &data.instance;
+
+    if let Some(clause) = instance.split() {
+        writeln!(w, "; split on clause #{}", clause)?
+    }
+
+    for (pred, samples) in data.pos.index_iter() {
+        for sample in samples {
+            write!(w, "(pos ")?;
+            write_app(w, instance, pred, sample)?;
+            writeln!(w, ")")?
+        }
+    }
+    for (pred, samples) in data.neg.index_iter() {
+        for sample in samples {
+            write!(w, "(neg ")?;
+            write_app(w, instance, pred, sample)?;
+            writeln!(w, ")")?
+        }
+    }
+
+    for constraint in data.constraints.iter() {
+        if constraint.is_tautology() {
+            continue;
+        }
+        write!(w, "(cstr (")?;
+        if let Some(lhs) = constraint.lhs() {
+            for (pred, samples) in lhs {
+                for sample in samples {
+                    write!(w, " ")?;
+                    write_app(w, instance, *pred, sample)?
+                }
+            }
+        }
+        write!(w, " ) ")?;
+        if let Some(rhs) = constraint.rhs() {
+            write_app(w, instance, rhs.pred, &rhs.args)?
+        } else {
+            write!(w, "false")?
+        }
+        writeln!(w, ")")?
+    }
+
+    Ok(())
+}
+
+/// S-expressions, only used to read dumps.
+enum SExpr {
+    /// An atom.
+    Atom(String),
+    /// A list.
+    List(Vec<SExpr>),
+}
+mylib::impl_fmt! {
+    SExpr(self, fmt) {
+        match self {
+            SExpr::Atom(atom) => write!(fmt, "{}", atom),
+            SExpr::List(list) => {
+                write!(fmt, "(")?;
+                for (idx, sexpr) in list.iter().enumerate() {
+                    if idx > 0 {
+                        write!(fmt, " ")?
+                    }
+                    write!(fmt, "{}", sexpr)?
+                }
+                write!(fmt, ")")
+            }
+        }
+    }
+}
+
+/// Reads all the s-expressions in a string.
+fn read_sexprs(s: &str) -> Res<Vec<SExpr>> {
+    let mut stack: Vec<Vec<SExpr>> = vec![vec![]];
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => {
+                while let Some(c) = chars.next() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => stack.push(vec![]),
+            ')' => {
+                let list = if stack.len() > 1 {
+                    stack.pop().expect("non-empty stack")
+                } else {
+                    bail!("unexpected closing parenthesis")
+                };
+                stack
+                    .last_mut()
+                    .expect("non-empty stack")
+                    .push(SExpr::List(list))
+            }
+            _ if c.is_whitespace() => (),
+            _ => {
+                let mut atom = String::new();
+                atom.push(c);
+                let quoted = c == '|';
+                while let Some(next) = chars.peek().cloned() {
+                    if quoted {
+                        chars.next();
+                        atom.push(next);
+                        if next == '|' {
+                            break;
+                        }
+                    } else if next.is_whitespace() || next == '(' || next == ')' {
+                        break;
+                    } else {
+                        chars.next();
+                        atom.push(next)
+                    }
+                }
+                stack
+                    .last_mut()
+                    .expect("non-empty stack")
+                    .push(SExpr::Atom(atom))
+            }
+        }
+    }
+
+    if stack.len() != 1 {
+        bail!("unclosed parenthesis")
+    }
+    Ok(stack.pop().expect("non-empty stack"))
+}
+
+/// Learning data read from a dump, over the predicates of some instance.
+#[derive(Default)]
+pub struct Dump {
+    /// Positive samples.
+    pos: Vec<Sample>,
+    /// Negative samples.
+    neg: Vec<Sample>,
+    /// Implication constraints.
+    cstrs: Vec<(Vec<Sample>, Option<Sample>)>,
+    /// Number of facts discarded because they do not match the instance.
+    discarded: usize,
+}
+
+/// Loads a dump for some instance.
+///
+/// Discards the facts mentioning predicates that do not exist in `instance`, that are already
+/// defined, or that have a different original signature.
+pub fn load<R: Read>(mut r: R, instance: &Instance) -> Res<Dump> {
+    let mut content = String::new();
+    r.read_to_string(&mut content)?;
+    let sexprs = read_sexprs(&content).chain_err(|| "while reading learning data dump")?;
+
+    let mut cxt = crate::parse::ParserCxt::new();
+    let dummy_profiler = Profiler::new();
+    let dummy_instance = Instance::new();
+    let no_vars = VarInfos::new();
+
+    // Parses an application, `None` if the predicate does not exist.
+    let mut app_of = |sexpr: &SExpr| -> Res<Option<Sample>> {
+        let (name, sorts, vals) = match sexpr {
+            SExpr::List(list) if list.len() == 3 => match (&list[0], &list[1], &list[2]) {
+                (SExpr::Atom(name), SExpr::List(sorts), SExpr::List(vals))
+                    if sorts.len() == vals.len() =>
+                {
+                    (name, sorts, vals)
+                }
+                _ => bail!("illegal predicate application `{}`", sexpr),
+            },
+            _ => bail!("illegal predicate application `{}`", sexpr),
+        };
+
+        let mut sig = Sig::with_capacity(sorts.len());
+        for sort in sorts {
+            if let Some(typ) = crate::parse::sort_opt(&sort.to_string())? {
+                sig.push(typ)
+            } else {
+                bail!("illegal sort `{}`", sort)
+            }
+        }
+
+        let pred = if let Some(pred) = instance
+            .preds()
+            .iter()
+            .find(|pred| pred.name == *name && *pred.original_sig() == sig)
+        {
+            if pred.is_defined() {
+                return Ok(None);
+            }
+            pred
+        } else {
+            return Ok(None);
+        };
+
+        let mut orig_vals = VarMap::with_capacity(vals.len());
+        for (val, typ) in vals.iter().zip(sig.iter()) {
+            let val_str = val.to_string();
+            let val = if val_str == "_" {
+                val::none(typ.clone())
+            } else if let Some(term) = cxt.parser(&val_str, 0, &dummy_profiler).term_opt(
+                &no_vars,
+                &BTreeMap::new(),
+                &dummy_instance,
+            )? {
+                if let Some(val) = term.val() {
+                    val.cast(typ)?
+                } else {
+                    bail!("illegal value `{}`", val_str)
+                }
+            } else {
+                bail!("illegal value `{}`", val_str)
+            };
+            orig_vals.push(val)
+        }
+
+        let args: VarMap<Val> = pred
+            .original_sig_map()
+            .iter()
+            .map(|src| orig_vals[*src].clone())
+            .collect();
+
+        Ok(Some(Sample::new(pred.idx, var_to::vals::new(args))))
+    };
+
+    let mut dump = Dump::default();
+
+    for sexpr in &sexprs {
+        let list = match sexpr {
+            SExpr::List(list) if !list.is_empty() => list,
+            _ => bail!("illegal learning data fact `{}`", sexpr),
+        };
+        match (&list[0], list.len()) {
+            (SExpr::Atom(kind), 2) if kind == "pos" || kind == "neg" => {
+                if let Some(sample) = app_of(&list[1])? {
+                    if kind == "pos" {
+                        dump.pos.push(sample)
+                    } else {
+                        dump.neg.push(sample)
+                    }
+                } else {
+                    dump.discarded += 1
+                }
+            }
+
+            (SExpr::Atom(kind), 3) if kind == "cstr" => {
+                let mut lhs = vec![];
+                let mut okay = true;
+                if let SExpr::List(apps) = &list[1] {
+                    for app in apps {
+                        if let Some(sample) = app_of(app)? {
+                            lhs.push(sample)
+                        } else {
+                            okay = false
+                        }
+                    }
+                } else {
+                    bail!("illegal constraint lhs `{}`", list[1])
+                }
+                let rhs = match &list[2] {
+                    SExpr::Atom(fls) if fls == "false" => None,
+                    app => {
+                        let rhs = app_of(app)?;
+                        okay = okay && rhs.is_some();
+                        rhs
+                    }
+                };
+                if okay {
+                    dump.cstrs.push((lhs, rhs))
+                } else {
+                    dump.discarded += 1
+                }
+            }
+
+            _ => bail!("illegal learning data fact `{}`", sexpr),
+        }
+    }
+
+    Ok(dump)
+}
+
+/// Lhs and rhs samples of an instance of a clause.
+type ClauseSamples = (Vec<(PrdIdx, RVarVals)>, Option<(PrdIdx, RVarVals)>);
+
+/// Checks samples against the clauses of an instance.
+struct Validator<'a> {
+    /// The instance.
+    instance: &'a Instance,
+    /// Solver.
+    solver: Solver<Parser>,
+    /// Positive samples known to be legal.
+    pos: PrdMap<VarValsSet>,
+    /// Negative samples known to be legal.
+    neg: PrdMap<VarValsSet>,
+}
+
+impl<'a> Validator<'a> {
+    /// Constructor.
+    fn new(instance: &'a Instance) -> Res<Self> {
+        let solver = conf.solver.spawn("load_data", Parser, instance)?;
+        let pos = instance.preds().iter().map(|_| VarValsSet::new()).collect();
+        let neg = instance.preds().iter().map(|_| VarValsSet::new()).collect();
+        Ok(Validator {
+            instance,
+            solver,
+            pos,
+            neg,
+        })
+    }
+
+    /// Looks for an instance of a clause where some applications have some values.
+    ///
+    /// Applications of the lhs that are not in `lhs_fixed` must be equal to known positive
+    /// samples. If `rhs_fixed` is `None`, the rhs (if any) must be equal to a known negative
+    /// sample.
+    fn clause_instance(
+        &mut self,
+        clause: ClsIdx,
+        lhs_fixed: &[(PrdIdx, &VarTerms, &VarVals)],
+        rhs_fixed: Option<&VarVals>,
+    ) -> Res<Option<ClauseSamples>> {
+        let instance = self.instance;
+        let clause = &instance[clause];
+
+        self.solver.push(1)?;
+        clause.declare(&mut self.solver)?;
+        for term in clause.lhs_terms() {
+            self.solver.assert(&SmtTerm::new(term))?
+        }
+
+        let mut possible = true;
+
+        'lhs: for (pred, argss) in clause.lhs_preds() {
+            for args in argss {
+                if let Some((_, _, vals)) =
+                    lhs_fixed.iter().find(|(p, a, _)| p == pred && *a == args)
+                {
+                    self.solver.assert(&EqConj::new(args, vals))?
+                } else if self.pos[*pred].is_empty() {
+                    possible = false;
+                    break 'lhs;
+                } else if !args.is_empty() {
+                    self.solver
+                        .assert(&DisjArgs::new(args, &self.pos[*pred])?)?
+                }
+            }
+        }
+
+        if let Some((pred, args)) = clause.rhs() {
+            if let Some(vals) = rhs_fixed {
+                self.solver.assert(&EqConj::new(args, vals))?
+            } else if self.neg[pred].is_empty() {
+                possible = false
+            } else if !args.is_empty() {
+                self.solver.assert(&DisjArgs::new(args, &self.neg[pred])?)?
+            }
+        }
+
+        // Unknown means the facts cannot be justified, they are dropped.
+        let model = if possible && self.solver.check_sat_or_unk()? == Some(true) {
+            let model = self.solver.get_model()?;
+            Some(Cex::of_model(
+                clause.vars(),
+                Parser.fix_model(model)?,
+                false,
+            )?)
+        } else {
+            None
+        };
+
+        self.solver.pop(1)?;
+
+        if let Some(model) = model {
+            let mut lhs = vec![];
+            for (pred, argss) in clause.lhs_preds() {
+                for args in argss {
+                    let mut sample = VarMap::with_capacity(args.len());
+                    for arg in args.iter() {
+                        sample.push(arg.eval(&model)?)
+                    }
+                    lhs.push((*pred, sample.into()))
+                }
+            }
+            let rhs = if let Some((pred, args)) = clause.rhs() {
+                let mut sample = VarMap::with_capacity(args.len());
+                for arg in args.iter() {
+                    sample.push(arg.eval(&model)?)
+                }
+                Some((pred, sample.into()))
+            } else {
+                None
+            };
+            Ok(Some((lhs, rhs)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Tries to justify a positive sample.
+    fn validate_pos(&mut self, sample: &Sample) -> Res<Option<(ClsIdx, ClauseSamples)>> {
+        let instance = self.instance;
+        for clause in instance.rhs_clauses_of(sample.pred) {
+            if let Some(samples) = self.clause_instance(*clause, &[], Some(&sample.args))? {
+                if let Some((pred, ref args)) = samples.1 {
+                    self.pos[pred].insert(var_to::vals::new(args.clone()));
+                }
+                return Ok(Some((*clause, samples)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Tries to justify a negative sample.
+    fn validate_neg(&mut self, sample: &Sample) -> Res<Option<(ClsIdx, ClauseSamples)>> {
+        let instance = self.instance;
+        for clause in instance.lhs_clauses_of(sample.pred) {
+            if let Some(argss) = instance[*clause].lhs_preds().get(&sample.pred) {
+                for args in argss {
+                    let fixed = [(sample.pred, args, &sample.args)];
+                    if let Some(samples) = self.clause_instance(*clause, &fixed, None)? {
+                        self.neg[sample.pred].insert(sample.args.clone());
+                        return Ok(Some((*clause, samples)));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Tries to justify a constraint.
+    ///
+    /// Looks for a clause with the same predicate applications, matching the samples of the
+    /// constraint with the applications of the clause in order.
+    fn validate_cstr(
+        &mut self,
+        lhs: &[Sample],
+        rhs: Option<&Sample>,
+    ) -> Res<Option<(ClsIdx, ClauseSamples)>> {
+        let instance = self.instance;
+
+        let candidates: Vec<ClsIdx> = if let Some(rhs) = rhs {
+            instance.rhs_clauses_of(rhs.pred).iter().cloned().collect()
+        } else {
+            instance.neg_clauses().iter().cloned().collect()
+        };
+
+        'clauses: for clause in candidates {
+            let mut fixed = vec![];
+            let mut remaining: Vec<&Sample> = lhs.iter().collect();
+            for (pred, argss) in instance[clause].lhs_preds() {
+                for args in argss {
+                    if let Some(idx) = remaining.iter().position(|s| s.pred == *pred) {
+                        let sample = remaining.swap_remove(idx);
+                        fixed.push((*pred, args, &sample.args))
+                    } else {
+                        continue 'clauses;
+                    }
+                }
+            }
+            if !remaining.is_empty() {
+                continue 'clauses;
+            }
+
+            if let Some(samples) = self.clause_instance(clause, &fixed, rhs.map(|rhs| &rhs.args))? {
+                return Ok(Some((clause, samples)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Dump {
+    /// Number of facts discarded while loading.
+    pub fn discarded(&self) -> usize {
+        self.discarded
+    }
+
+    /// Seeds some learning data with the facts of the dump that can be justified.
+    ///
+    /// A positive sample is justified by an instance of a clause producing it, where all the lhs
+    /// applications are justified positive samples. A negative sample is justified by an instance
+    /// of a clause where it appears in the lhs, the other lhs applications are justified positive
+    /// samples, and the rhs (if any) is a justified negative sample. A constraint is justified if
+    /// it is an instance of a clause. Justification of samples runs to a fixed point.
+    ///
+    /// The instances of the clauses are added to `data`, not the facts themselves. Returns the
+    /// number of facts justified.
+    pub fn seed(self, data: &mut Data) -> Res<usize> {
+        let instance = data.instance.clone();
+        let mut validator = Validator::new(&instance)?;
+        let mut count = 0;
+
+        let Dump {
+            pos, neg, cstrs, ..
+        } = self;
+
+        let mut to_do = pos;
+        loop {
+            let len = to_do.len();
+            let mut postponed = Vec::with_capacity(len);
+            for sample in to_do {
+                if let Some((clause, (lhs, rhs))) = validator.validate_pos(&sample)? {
+                    data.add_data(clause, lhs, rhs)?;
+                    count += 1
+                } else {
+                    postponed.push(sample)
+                }
+            }
+            if postponed.len() == len {
+                break;
+            }
+            to_do = postponed
+        }
+
+        let mut to_do = neg;
+        loop {
+            let len = to_do.len();
+            let mut postponed = Vec::with_capacity(len);
+            for sample in to_do {
+                if let Some((clause, (lhs, rhs))) = validator.validate_neg(&sample)? {
+                    data.add_data(clause, lhs, rhs)?;
+                    count += 1
+                } else {
+                    postponed.push(sample)
+                }
+            }
+            if postponed.len() == len {
+                break;
+            }
+            to_do = postponed
+        }
+
+        for (lhs, rhs) in cstrs {
+            if let Some((clause, (lhs, rhs))) = validator.validate_cstr(&lhs, rhs.as_ref())? {
+                data.add_data(clause, lhs, rhs)?;
+                count += 1
+            }
+        }
+
+        validator.solver.kill()?;
+        data.propagate()?;
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,61 @@
+//! Tests for the learning data.
+
+use crate::{
+    common::*,
+    data::{persist, Data},
+    parse,
+};
+
+/// Instance of `rsc/sat/counter.smt2`.
+///
+/// The only predicate is `p`, the clauses are `x = 0 => (p x)`, `(p x) /\ x < 10 => (p (+ x 1))`
+/// and `(p x) /\ x > 10 => false`.
+fn counter() -> Arc<Instance> {
+    let mut instance = parse::instance(
+        &::std::fs::read_to_string("rsc/sat/counter.smt2").expect("while reading counter.smt2"),
+    );
+    instance.finalize().unwrap();
+    Arc::new(instance)
+}
+
+#[test]
+fn load_and_seed_dump() {
+    let instance = counter();
+
+    let dump = "\
+        (pos (p ( Int ) ( 1 )))
+        (pos (p ( Int ) ( 0 )))
+        (pos (p ( Int ) ( 20 )))
+        (neg (p ( Int ) ( 11 )))
+        (pos (r ( Int ) ( 0 )))
+        (pos (p ( Int Int ) ( 0 0 )))
+    ";
+    let dump = persist::load(dump.as_bytes(), &instance).unwrap();
+    // Unknown predicate `r`, and wrong signature for `p`.
+    assert_eq! { dump.discarded(), 2 }
+
+    let mut data = Data::new(instance);
+    // `(p 20)` cannot be justified from the other facts.
+    assert_eq! { dump.seed(&mut data).unwrap(), 3 }
+    let p: PrdIdx = 0.into();
+    assert_eq! { data.pos[p].len(), 2 }
+    assert_eq! { data.neg[p].len(), 1 }
+}
+
+#[test]
+fn dump_round_trip() {
+    let instance = counter();
+
+    let dump = "(pos (p ( Int ) ( 0 )))\n(neg (p ( Int ) ( 11 )))\n";
+    let mut data = Data::new(instance.clone());
+    persist::load(dump.as_bytes(), &instance)
+        .unwrap()
+        .seed(&mut data)
+        .unwrap();
+
+    let mut buff: Vec<u8> = vec![];
+    persist::dump(&data, &mut buff).unwrap();
+    let dumped = String::from_utf8(buff).unwrap();
+    assert! { dumped.contains("(pos (p ( Int ) ( 0 )))") }
+    assert! { dumped.contains("(neg (p ( Int ) ( 11 )))") }
+}
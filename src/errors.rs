@@ -172,6 +172,11 @@ error_chain::error_chain! {
             description("timeout")
             display("timeout")
         }
+        #[doc = "Some clauses could not be checked, in strict checking mode."]
+        CheckUnknown(count: usize) {
+            description("some clauses could not be checked")
+            display("could not check {} clause(s)", count)
+        }
     }
 }
 
@@ -204,6 +209,22 @@ impl Error {
         false
     }
 
+    /// Messages of the error chain, innermost first, without the bare `unknown` messages.
+    ///
+    /// Used to explain unknown results.
+    pub fn reasons(&self) -> Vec<String> {
+        let mut res: Vec<String> = self
+            .iter()
+            .filter(|err| {
+                err.description() != consts::err::unknown_desc
+                    && err.description() != ::rsmt2::errors::ErrorKind::Unknown.description()
+            })
+            .map(|err| err.to_string())
+            .collect();
+        res.reverse();
+        res
+    }
+
     /// Returns the clause explaining an unsat result if any.
     pub fn unsat_cause(&self) -> Option<ClsIdx> {
         match self.kind() {
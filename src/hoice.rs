@@ -60,6 +60,9 @@ pub fn work() -> Res<()> {
         if let Some(output_file) = conf.check_file() {
             return check::do_it(file_path, output_file);
         }
+        if let Some(proof_file) = conf.check_proof_file() {
+            return check::proof::do_it(file_path, proof_file);
+        }
 
         // Not in check mode, open file
         let file = OpenOptions::new()
@@ -67,16 +70,62 @@ pub fn work() -> Res<()> {
             .open(file_path)
             .chain_err(|| format!("while opening input file `{}`", conf.emph(file_path)))?;
 
-        read_and_work(file, true, false, false)?;
-        Ok(())
+        dumping_data(|| read_and_work(file, true, false, false).map(|_| ()))
     } else {
         // Reading from stdin.
 
         let stdin = ::std::io::stdin();
 
-        read_and_work(stdin, false, false, false)?;
-        Ok(())
+        dumping_data(|| read_and_work(stdin, false, false, false).map(|_| ()))
+    }
+}
+
+/// Runs something while teachers dump their learning data.
+///
+/// Teachers append their learning data to a temporary file, which replaces the dump file once
+/// `run` is done. So the dump file is left untouched until then, in case it is also the file
+/// learning data is loaded from. If `run` fails or no teacher dumped anything, the dump file is
+/// left as it is and the temporary file is removed.
+fn dumping_data<F>(run: F) -> Res<()>
+where
+    F: FnOnce() -> Res<()>,
+{
+    let (dump_file, tmp_file) = match (conf.dump_data_file(), conf.dump_data_tmp_file()) {
+        (Some(dump_file), Some(tmp_file)) => (dump_file, tmp_file),
+        _ => return run(),
+    };
+
+    // Leftover of a run that did not finish.
+    remove_tmp_dump()?;
+
+    let res = run();
+
+    if res.is_err() {
+        if let Err(e) = remove_tmp_dump() {
+            print_err(&e)
+        }
+    } else if ::std::path::Path::new(&tmp_file).exists() {
+        ::std::fs::rename(&tmp_file, dump_file).chain_err(|| {
+            format!(
+                "while moving data file `{}` to `{}`",
+                conf.emph(&tmp_file),
+                conf.emph(dump_file)
+            )
+        })?
     }
+
+    res
+}
+
+/// Removes the temporary learning data file, if any.
+fn remove_tmp_dump() -> Res<()> {
+    if let Some(tmp_file) = conf.dump_data_tmp_file() {
+        if ::std::path::Path::new(&tmp_file).exists() {
+            ::std::fs::remove_file(&tmp_file)
+                .chain_err(|| format!("while removing data file `{}`", conf.emph(&tmp_file)))?
+        }
+    }
+    Ok(())
 }
 
 /// Reads a script from a `Read`er and works.
@@ -99,6 +148,7 @@ pub fn read_and_work<R: ::std::io::Read>(
     use crate::parse::{ItemRead, ParserCxt};
 
     let profiler = Profiler::new();
+    let start = ::std::time::Instant::now();
 
     let mut reader = ::std::io::BufReader::new(reader);
     // String buffer.
@@ -122,8 +172,17 @@ pub fn read_and_work<R: ::std::io::Read>(
     // Original instance.
     let mut original_instance = None;
 
+    // Instance before pre-processing, for unsat cores.
+    let mut core_instance = None;
+
+    // Items of the script, for model validation.
+    let mut script = String::new();
+
+    // Explanation of the last unknown result, for `(get-info :reason-unknown)`.
+    let mut reason_unknown = None;
+
     'parse_work: loop {
-        use crate::parse::Parsed;
+        use crate::parse::{InfoFlag, Parsed};
 
         profile! { |profiler| tick "parsing" }
 
@@ -155,6 +214,11 @@ pub fn read_and_work<R: ::std::io::Read>(
 
         profile! { |profiler| mark "parsing" }
 
+        if conf.validate && parse_res == Parsed::Items {
+            script.push_str(buf);
+            script.push('\n')
+        }
+
         match parse_res {
             // Check-sat on unsat instance?
             Parsed::CheckSat if unsat.is_some() => {
@@ -167,12 +231,16 @@ pub fn read_and_work<R: ::std::io::Read>(
 
             // Check-sat, start class.
             Parsed::CheckSat => {
-                if instance.proofs() {
+                reason_unknown = None;
+                if instance.produces_proofs() {
                     let mut old = instance.clone();
                     old.finalize()
                         .chain_err(|| "while finalizing original instance")?;
                     original_instance = Some(old)
                 }
+                if instance.unsat_cores() {
+                    core_instance = Some(instance.clone())
+                }
                 log! { @info "Running top pre-processing" }
 
                 let preproc_profiler = Profiler::new();
@@ -186,12 +254,16 @@ pub fn read_and_work<R: ::std::io::Read>(
                         if e.is_timeout() {
                             println!("timeout");
                             print_stats("top", profiler);
+                            if let Err(e) = remove_tmp_dump() {
+                                print_err(&e)
+                            }
                             ::std::process::exit(0)
                         } else if e.is_unknown() {
                             println!("unknown");
+                            reason_unknown = Some(explain_unknown("pre-processing", &e));
                             continue;
                         } else if e.is_unsat() {
-                            unsat = Some(unsat_core::UnsatRes::None)
+                            unsat = Some(unsat_core::UnsatRes::of_instance(&instance))
                         } else {
                             bail!(e)
                         }
@@ -202,18 +274,19 @@ pub fn read_and_work<R: ::std::io::Read>(
                 model = if let Some(maybe_model) = instance.is_trivial_conj()? {
                     // Pre-processing already decided satisfiability.
                     log! { @info "solved by pre-processing" }
-                    if !maybe_model.is_unsat() {
-                        println!("sat")
+                    if let Some(model) = maybe_model.into_option() {
+                        let model = simplify_model(&instance, model);
+                        if validate_model(&script, &instance, &model, &mut reason_unknown)? {
+                            println!("sat");
+                            Some(model)
+                        } else {
+                            None
+                        }
                     } else {
-                        use crate::unsat_core::UnsatRes;
                         println!("unsat");
-                        unsat = Some(if instance.proofs() {
-                            UnsatRes::empty_entry()
-                        } else {
-                            UnsatRes::None
-                        })
+                        unsat = Some(unsat_core::UnsatRes::of_instance(&instance));
+                        None
                     }
-                    maybe_model.into_option()
                 } else {
                     let arc_instance = Arc::new(instance);
                     let solve_res = split::work(&arc_instance, &profiler);
@@ -223,11 +296,19 @@ pub fn read_and_work<R: ::std::io::Read>(
 
                     match solve_res {
                         Ok(Some(Either::Left(res))) => {
-                            println!("sat");
-                            Some(instance.extend_model(res)?)
+                            let model = instance.extend_model(res)?;
+                            let model = simplify_model(&instance, model);
+                            if validate_model(&script, &instance, &model, &mut reason_unknown)? {
+                                println!("sat");
+                                Some(model)
+                            } else {
+                                None
+                            }
                         }
                         Ok(None) => {
                             println!("unknown");
+                            reason_unknown =
+                                Some("inference is deactivated (`--infer off`)".into());
                             None
                         }
                         Ok(Some(Either::Right(res))) => {
@@ -236,21 +317,27 @@ pub fn read_and_work<R: ::std::io::Read>(
                             None
                         }
                         Err(ref e) if e.is_unsat() => {
-                            unsat = Some(unsat_core::UnsatRes::None);
-                            warn!(
-                                "unsat was obtained by a legacy mechanism, \
-                                 core/proof will not be available"
-                            );
+                            unsat = Some(unsat_core::UnsatRes::of_instance(&instance));
+                            if !instance.produces_proofs() {
+                                warn!(
+                                    "unsat was obtained by a legacy mechanism, \
+                                     proof will not be available"
+                                )
+                            }
                             println!("unsat");
                             None
                         }
                         Err(ref e) if e.is_timeout() => {
                             println!("timeout");
                             print_stats("top", profiler);
+                            if let Err(e) = remove_tmp_dump() {
+                                print_err(&e)
+                            }
                             ::std::process::exit(0)
                         }
                         Err(ref e) if e.is_unknown() => {
                             println!("unknown");
+                            reason_unknown = Some(explain_unknown("learning", e));
                             None
                         }
                         Err(e) => {
@@ -260,15 +347,40 @@ pub fn read_and_work<R: ::std::io::Read>(
                     }
                 };
 
+                // Complete the unsat result with a full proof on the original instance.
+                if let Some(original) = original_instance.as_ref() {
+                    unsat = unsat.map(|res| res.complete(&instance, original))
+                }
+
                 if stop_on_check {
                     return Ok((model, instance));
                 }
             }
 
-            Parsed::GetUnsatCore | Parsed::GetModel if !conf.infer => (),
+            Parsed::GetUnsatCore | Parsed::GetModel | Parsed::GetValue(_) if !conf.infer => (),
+
+            Parsed::GetUnsatCore if !instance.unsat_cores() => {
+                print_err(&crate::common::consts::errors::no_unsat_cores.into())
+            }
 
             // Print unsat core if available.
-            Parsed::GetUnsatCore => println!("unsupported"),
+            Parsed::GetUnsatCore => {
+                if let (Some(unsat), Some(original)) = (unsat.as_ref(), core_instance.as_ref()) {
+                    match unsat_core::assertions::named_core(original, unsat, &profiler) {
+                        Ok(names) => {
+                            print!("(");
+                            for (idx, name) in names.iter().enumerate() {
+                                let sep = if idx == 0 { "" } else { " " };
+                                print!("{}{}", sep, name)
+                            }
+                            println!(")")
+                        }
+                        Err(e) => print_err(&e.chain_err(|| "while computing unsat core")),
+                    }
+                } else {
+                    print_err(&"no unsat core available".into())
+                }
+            }
 
             // Print unsat core if available.
             Parsed::GetProof => {
@@ -293,16 +405,54 @@ pub fn read_and_work<R: ::std::io::Read>(
 
             // Print model if available.
             Parsed::GetModel => {
-                if let Some(model) = model.as_mut() {
-                    // Simplify model before writing it.
-                    // instance.simplify_pred_defs(model) ? ;
+                if let Some(model) = model.as_ref() {
                     let stdout = &mut stdout();
-                    instance.write_model(&model, stdout)?
+                    if conf.witness {
+                        instance.write_witness(model, stdout)?
+                    } else {
+                        instance.write_model(model, stdout)?
+                    }
                 } else {
                     bail!("no model available")
                 }
             }
 
+            // Evaluate predicate applications in the model if available.
+            Parsed::GetValue(apps) => {
+                if let Some(model) = model.as_ref() {
+                    if let Err(e) = instance.write_values(model, &apps, &mut stdout()) {
+                        print_err(&e.chain_err(|| "while answering get-value"))
+                    }
+                } else {
+                    print_err(&"no model available".into())
+                }
+            }
+
+            Parsed::GetInfo(InfoFlag::Name) => println!("(:name \"hoice\")"),
+
+            Parsed::GetInfo(InfoFlag::Version) => println!("(:version \"{}\")", *version),
+
+            Parsed::GetInfo(InfoFlag::AllStatistics) => {
+                print_all_stats(&instance, &profiler, start)
+            }
+
+            // Explain the last unknown result if any.
+            Parsed::GetInfo(InfoFlag::ReasonUnknown) => {
+                if let Some(reason) = reason_unknown.as_ref() {
+                    println!("(:reason-unknown \"{}\")", reason.replace('"', "\"\""))
+                } else {
+                    print_err(&"no unknown result to explain".into())
+                }
+            }
+
+            Parsed::GetOption(key) => {
+                if let Some(val) = instance.get_option(&key) {
+                    println!("{}", val)
+                } else {
+                    println!("unsupported")
+                }
+            }
+
             Parsed::Items => {
                 if instance.print_success() {
                     println!("success")
@@ -312,7 +462,12 @@ pub fn read_and_work<R: ::std::io::Read>(
             Parsed::Reset => {
                 parser_cxt.reset();
                 instance = Instance::new();
-                model = None
+                script.clear();
+                model = None;
+                unsat = None;
+                reason_unknown = None;
+                original_instance = None;
+                core_instance = None
             }
 
             Parsed::Eof => {
@@ -332,6 +487,94 @@ pub fn read_and_work<R: ::std::io::Read>(
     Ok((model, instance))
 }
 
+/// Simplifies a model if `conf.simplify_model` is active.
+///
+/// Models are simplified when they are found, so that validation (see `validate_model`) is
+/// performed on the model that is printed.
+fn simplify_model(instance: &Instance, model: ConjModel) -> ConjModel {
+    if conf.simplify_model {
+        instance.simplified_model(model)
+    } else {
+        model
+    }
+}
+
+/// Validates a model against the items of the script, if `conf.validate` is set.
+///
+/// Prints `unknown` and the reason why validation failed if it fails, and stores this reason in
+/// `reason_unknown`.
+fn validate_model(
+    script: &str,
+    instance: &Instance,
+    model: ConjModelRef,
+    reason_unknown: &mut Option<String>,
+) -> Res<bool> {
+    if !conf.validate {
+        return Ok(true);
+    }
+    let mut model_str = vec![];
+    instance.write_model(model, &mut model_str)?;
+    let model_str = String::from_utf8_lossy(&model_str);
+    if let Err(e) = check::validate(script, &model_str) {
+        println!("unknown");
+        let e = e.chain_err(|| "model validation failed");
+        *reason_unknown = Some(explain_unknown("model validation", &e));
+        print_err(&e);
+        Ok(false)
+    } else {
+        Ok(true)
+    }
+}
+
+/// Prints the answer to `(get-info :all-statistics)`.
+///
+/// Lists the time elapsed, the size of the instance and the metrics of the profiler as a flat
+/// attribute list. The metrics of the profiler are only collected when `--stats` is active.
+fn print_all_stats(instance: &Instance, profiler: &Profiler, start: ::std::time::Instant) {
+    use crate::common::profiling::DurationExt;
+    let mut stats = vec![
+        ("time".to_string(), start.elapsed().to_str()),
+        ("predicates".into(), instance.preds().len().to_string()),
+        ("clauses".into(), instance.clauses().len().to_string()),
+    ];
+    for (name, val) in profiler.stats() {
+        stats.push((stat_key(&name), val.to_string()))
+    }
+    print!("(");
+    for (idx, (key, val)) in stats.iter().enumerate() {
+        let sep = if idx == 0 { "" } else { "\n " };
+        print!("{}:{} {}", sep, key, val)
+    }
+    println!(")")
+}
+
+/// Turns the name of a profiler metric into a legal SMT-LIB keyword, without the `:`.
+///
+/// Words are joined with `-`, characters that cannot appear in a simple symbol become `_`.
+fn stat_key(name: &str) -> String {
+    let name: Vec<_> = name.split_whitespace().collect();
+    name.join("-")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "~!@$%^&*_-+=<>.?/".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Explains an unknown result from the component that gave up and the error it produced.
+fn explain_unknown(component: &str, e: &Error) -> String {
+    let mut reason = format!("{} gave up", component);
+    for blah in e.reasons() {
+        reason.push('\n');
+        reason.push_str(&blah)
+    }
+    reason
+}
+
 /// Waits until an `Arc` is unwrap-able.
 fn unwrap_arc<T>(arc: Arc<T>) -> Res<T> {
     while Arc::strong_count(&arc) != 1 {}
@@ -85,6 +85,16 @@ pub struct Pred {
     ///
     /// Populated by finalize.
     original_sig_term_map: Option<VarMap<Term>>,
+    /// Argument invariants, over the original signature.
+    ///
+    /// Arguments removed because of these invariants are restored by conjoining them to the
+    /// definition of the predicate in the model.
+    invariants: Vec<Term>,
+    /// Definitions of the arguments removed because of argument invariants, in order of removal.
+    ///
+    /// Maps variables of the original signature to terms over the variables of the original
+    /// signature that were still in the signature when the argument was removed.
+    arg_defs: Vec<(VarIdx, Term)>,
     /// Definition, if any. Set by preprocessing.
     def: Option<TTerms>,
     /// Strengthener, if any. Currently, this comes from strict negative clauses. It means the
@@ -109,6 +119,8 @@ impl Pred {
             original_sig,
             original_sig_map,
             original_sig_term_map: None,
+            invariants: vec![],
+            arg_defs: vec![],
             def: None,
             strength: None,
             funs: vec![],
@@ -355,6 +367,66 @@ impl Pred {
         })
     }
 
+    /// Argument invariants of the predicate, over the original signature.
+    pub fn invariants(&self) -> &[Term] {
+        &self.invariants
+    }
+
+    /// Definitions of the arguments removed because of argument invariants, in order of removal.
+    pub fn arg_defs(&self) -> &[(VarIdx, Term)] {
+        &self.arg_defs
+    }
+
+    /// Registers some argument invariants, and the definitions of the arguments they removed.
+    ///
+    /// Both are over the original signature. The definitions are used to recover the original
+    /// arguments of an application, see [`original_args`].
+    ///
+    /// [`original_args`]: struct.Pred.html#method.original_args (original_args function)
+    pub fn add_invariants(&mut self, invariants: Vec<Term>, arg_defs: Vec<(VarIdx, Term)>) {
+        self.invariants.extend(invariants);
+        self.arg_defs.extend(arg_defs)
+    }
+
+    /// Arguments of an application over the original signature.
+    ///
+    /// Arguments removed because of an invariant are restored from their definition, the other
+    /// removed arguments get the default value of their type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use hoice::{ common::*, info::Pred };
+    /// let sig: VarMap<_> = vec![ typ::int(), typ::int(), typ::bool() ].into();
+    /// let mut pred = Pred::new("pred", 0.into(), sig.clone());
+    /// // Drop the first two arguments, `v_1` is always `v_0 + 1` and `v_0` is always `7`.
+    /// let nu_sig: VarMap<_> = vec![ typ::bool() ].into();
+    /// let map: VarMap<VarIdx> = vec![ 2.into() ].into();
+    /// pred.set_sig(nu_sig, map);
+    /// pred.add_invariants(vec![], vec![
+    ///     (1.into(), term::add(vec![ term::int_var(0), term::int(1) ])),
+    /// ]);
+    /// pred.add_invariants(vec![], vec![ (0.into(), term::int(7)) ]);
+    /// let args: VarMap<_> = vec![ term::tru() ].into();
+    /// let expected: VarMap<_> = vec![ term::int(7), term::int(8), term::tru() ].into();
+    /// assert_eq! { pred.original_args(&args), expected }
+    /// ```
+    pub fn original_args(&self, args: &VarMap<Term>) -> VarMap<Term> {
+        let mut nu_args: VarMap<Term> = self
+            .original_sig
+            .iter()
+            .map(|typ| term::val(typ.default_val()))
+            .collect();
+        for (var, arg) in args.index_iter() {
+            nu_args[self.original_sig_map[var]] = arg.clone()
+        }
+        for (var, def) in self.arg_defs.iter().rev() {
+            let (arg, _) = def.subst(&nu_args);
+            nu_args[*var] = arg
+        }
+        nu_args
+    }
+
     /// Sets the predicate's definition.
     ///
     /// Only legal if the predicate has no definition.
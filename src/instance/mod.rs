@@ -3,10 +3,14 @@
 use crate::{common::*, data::Data, info::*, var_to::terms::VarTermsSet};
 
 mod clause;
+mod model_simpl;
 mod pre_instance;
+mod provenance;
 
 pub use self::clause::Clause;
 pub use self::pre_instance::PreInstance;
+pub(crate) use self::provenance::json_str;
+pub use self::provenance::{Event, LoggedClause, Provenance};
 
 /// Stores the instance: the clauses, the factory and so on.
 ///
@@ -138,6 +142,53 @@ pub struct Instance {
     ///
     /// Can only be set by `(set-option :produce-proofs true)`.
     proofs: bool,
+    /// Pre-processing script.
+    ///
+    /// Can only be set by `(set-option :preproc-script "<script>")`.
+    preproc_script: Option<crate::preproc::Script>,
+    /// True if the teacher loads and dumps learning data and warm-starts, see `--load_data`,
+    /// `--dump_data` and `--warm_start`.
+    ///
+    /// Deactivated for the instances solved to minimize unsat cores.
+    persist_data: bool,
+    /// Predicates split on a boolean argument by pre-processing.
+    ///
+    /// Used to recombine the definitions of the predicates created by the split in the model.
+    bool_splits: PrdHMap<BoolSplitDef>,
+    /// Predicates replaced by a predicate over a flattened signature by pre-processing.
+    ///
+    /// Used to restore the definitions of the predicates flattened in the model.
+    flattenings: PrdHMap<FlattenDef>,
+    /// Provenance log of the pre-processing.
+    provenance: Provenance,
+}
+
+/// Split of a predicate on one of its boolean arguments.
+///
+/// The predicate is defined as `(ite cond (pos args) (neg args))`, where `cond` and `args` are
+/// over the signature the predicate had when it was split.
+#[derive(Clone, Debug)]
+pub struct BoolSplitDef {
+    /// Boolean argument the predicate was split on.
+    pub cond: Term,
+    /// Arguments of the new predicates.
+    pub args: VarTerms,
+    /// Predicate for the `true` case.
+    pub pos: PrdIdx,
+    /// Predicate for the `false` case.
+    pub neg: PrdIdx,
+}
+
+/// Flattening of the arguments of a predicate, by datatype flattening or array elimination.
+///
+/// The predicate is defined as `(flat args)`, where `args` is over the signature the predicate had
+/// when it was flattened.
+#[derive(Clone, Debug)]
+pub struct FlattenDef {
+    /// Arguments of the new predicate.
+    pub args: VarTerms,
+    /// Predicate over the flattened signature.
+    pub flat: PrdIdx,
 }
 
 impl Default for Instance {
@@ -173,6 +224,11 @@ impl Instance {
             print_success: false,
             unsat_cores: false,
             proofs: false,
+            preproc_script: None,
+            persist_data: true,
+            bool_splits: PrdHMap::new(),
+            flattenings: PrdHMap::new(),
+            provenance: Provenance::new(conf.preproc.log_provenance),
         }
     }
 
@@ -208,6 +264,11 @@ impl Instance {
             print_success: false,
             unsat_cores: false,
             proofs: false,
+            preproc_script: self.preproc_script.clone(),
+            persist_data: self.persist_data,
+            bool_splits: self.bool_splits.clone(),
+            flattenings: self.flattenings.clone(),
+            provenance: Provenance::new(conf.preproc.log_provenance),
         }
     }
 
@@ -315,12 +376,16 @@ impl Instance {
     /// Returns the model corresponding to the input predicates and the forced
     /// predicates.
     ///
-    /// The model is sorted in topological order.
+    /// The model is sorted in topological order. Definitions are strengthened with the argument
+    /// invariants of the predicates, if any.
     pub fn model_of(&self, candidates: Candidates) -> Res<Model> {
         let mut model = Model::with_capacity(self.preds.len());
         for (pred, tterms_opt) in candidates.into_index_iter() {
             if let Some(term) = tterms_opt {
-                let (term, _) = term.subst(self[pred].original_sig_term_map()?);
+                let (mut term, _) = term.subst(self[pred].original_sig_term_map()?);
+                if let Some(invariants) = self.invariants_of(pred) {
+                    term = term::and(vec![term, invariants])
+                }
                 model.push((pred, TTerms::of_term(None, term)))
             }
         }
@@ -328,7 +393,16 @@ impl Instance {
         for pred in &self.sorted_pred_terms {
             let pred = *pred;
             if let Some(tterms) = self[pred].def() {
-                model.push((pred, tterms.subst(self[pred].original_sig_term_map()?)))
+                let mut tterms = tterms.subst(self[pred].original_sig_term_map()?);
+                if let Some(invariants) = self.invariants_of(pred) {
+                    tterms = tterms.and(invariants).chain_err(|| {
+                        format!(
+                            "while conjoining the argument invariants of {} to its definition",
+                            conf.emph(&self[pred].name)
+                        )
+                    })?
+                }
+                model.push((pred, tterms))
             } else {
                 bail!("inconsistency in sorted forced predicates")
             }
@@ -340,7 +414,13 @@ impl Instance {
     /// Returns the model corresponding to the input predicates and the forced
     /// predicates.
     ///
-    /// The model is sorted in topological order.
+    /// The model is sorted in topological order. The definitions of the predicates split on a
+    /// boolean argument are recombined as `ite`s, and the definitions of the predicates flattened
+    /// are restored, when possible. The candidates are expected to
+    /// come from [`model_of`], so only the definitions of the forced predicates are strengthened
+    /// with their argument invariants.
+    ///
+    /// [`model_of`]: struct.Instance.html#method.model_of (model_of function)
     pub fn extend_model(&self, candidates: ConjCandidates) -> Res<ConjModel> {
         let mut model = ConjModel::with_capacity(self.preds.len());
         let mut known_preds = PrdSet::new();
@@ -381,15 +461,276 @@ impl Instance {
         for pred in &self.sorted_pred_terms {
             let pred = *pred;
             if let Some(tterms) = self[pred].def() {
-                let tterms = tterms.subst(self[pred].original_sig_term_map()?);
-                model.push(vec![(pred, vec![tterms])])
+                let mut conj = vec![tterms.subst(self[pred].original_sig_term_map()?)];
+                if let Some(invariants) = self.invariants_of(pred) {
+                    conj.push(TTerms::of_term(None, invariants))
+                }
+                model.push(vec![(pred, conj)])
             } else {
                 bail!("inconsistency in sorted forced predicates")
             }
         }
+
+        if !self.bool_splits.is_empty() || !self.flattenings.is_empty() {
+            let mut defs = PrdHMap::new();
+            for group in &mut model {
+                if group.len() != 1 {
+                    continue;
+                }
+                let (pred, ref mut conj) = group[0];
+                if let Some(term) = self.recombined_def(pred, &defs)? {
+                    *conj = vec![TTerms::of_term(None, term)];
+                    if let Some(invariants) = self.invariants_of(pred) {
+                        conj.push(TTerms::of_term(None, invariants))
+                    }
+                }
+                let terms: Option<Vec<Term>> = conj.iter().map(TTerms::to_term).collect();
+                if let Some(terms) = terms {
+                    defs.insert(pred, term::and(terms));
+                }
+            }
+            loop {
+                let used = model.iter().flat_map(|group| group.iter()).fold(
+                    PrdSet::new(),
+                    |mut used, (_, conj)| {
+                        for tterms in conj {
+                            used.extend(tterms.preds())
+                        }
+                        used
+                    },
+                );
+                let len = model.len();
+                model.retain(|group| {
+                    group.len() != 1 || !self.is_unused_aux_pred(group[0].0, &used)
+                });
+                if model.len() == len {
+                    break;
+                }
+            }
+        }
+
         Ok(model)
     }
 
+    /// Conjunction of the argument invariants of a predicate, over its original signature.
+    fn invariants_of(&self, pred: PrdIdx) -> Option<Term> {
+        if self[pred].invariants().is_empty() {
+            None
+        } else {
+            Some(term::and(self[pred].invariants().to_vec()))
+        }
+    }
+
+    /// Registers the split of a predicate on a boolean argument.
+    pub fn register_bool_split(&mut self, pred: PrdIdx, split: BoolSplitDef) {
+        let prev = self.bool_splits.insert(pred, split);
+        debug_assert! { prev.is_none() }
+    }
+
+    /// Registers the flattening of the arguments of a predicate.
+    pub fn register_flattening(&mut self, pred: PrdIdx, flattening: FlattenDef) {
+        let prev = self.flattenings.insert(pred, flattening);
+        debug_assert! { prev.is_none() }
+    }
+
+    /// Provenance log of the pre-processing.
+    pub fn provenance(&self) -> &Provenance {
+        &self.provenance
+    }
+
+    /// Activates the provenance log, regardless of `--log_provenance`.
+    pub fn activate_provenance(&mut self) {
+        self.provenance = Provenance::new(true)
+    }
+
+    /// Sets the strategy the events of the provenance log are attributed to.
+    ///
+    /// Events are only recorded while a strategy is set.
+    pub fn set_provenance_strat(&mut self, strat: Option<&'static str>) {
+        self.provenance.set_strat(strat)
+    }
+
+    /// Snapshots of some clauses for the provenance log, empty if the log is inactive.
+    pub fn logged_clauses<Clauses>(&self, clauses: Clauses) -> Res<Vec<LoggedClause>>
+    where
+        Clauses: IntoIterator<Item = ClsIdx>,
+    {
+        let mut res = vec![];
+        if self.provenance.is_active() {
+            for clause in clauses {
+                let clause = &self.clauses[clause];
+                res.push((clause.to_string_info(&self.preds)?, clause.from()))
+            }
+        }
+        Ok(res)
+    }
+
+    /// Sets the parents of the clauses created next in the provenance log.
+    ///
+    /// Returns the previous parents, which the caller should restore once done.
+    pub fn set_clause_parents(&mut self, parents: Vec<LoggedClause>) -> Vec<LoggedClause> {
+        self.provenance.set_parents(parents)
+    }
+
+    /// Remembers the clauses the definition of a predicate is extracted from in the provenance
+    /// log.
+    ///
+    /// Must be called before the clauses are forgotten.
+    pub fn log_def_clauses(&mut self, pred: PrdIdx, clauses: &[ClsIdx]) -> Res<()> {
+        let clauses = self.logged_clauses(clauses.iter().cloned())?;
+        self.provenance.add_def_clauses(pred, clauses);
+        Ok(())
+    }
+
+    /// Clauses the definition of a predicate was extracted from, see `log_def_clauses`.
+    pub fn def_clauses(&self, pred: PrdIdx) -> Vec<LoggedClause> {
+        self.provenance.def_clauses(pred).to_vec()
+    }
+
+    /// Records a clause rewritten in place as created again in the provenance log.
+    ///
+    /// The clause should have been recorded as dropped before being rewritten.
+    fn record_rewritten_clause(&mut self, clause: ClsIdx, parents: Vec<LoggedClause>) -> Res<()> {
+        let prev_parents = self.set_clause_parents(parents);
+        let res = self.record_clause(clause, true);
+        self.set_clause_parents(prev_parents);
+        res
+    }
+
+    /// Records the creation or the removal of a clause in the provenance log.
+    fn record_clause(&mut self, clause: ClsIdx, created: bool) -> Res<()> {
+        if self.provenance.is_active() {
+            let from = self.clauses[clause].from();
+            let text = self.clauses[clause].to_string_info(&self.preds)?;
+            let event = if created {
+                Event::ClauseCreated {
+                    clause: text,
+                    from,
+                    info: self.clauses[clause].info,
+                    parents: self.provenance.parents().to_vec(),
+                }
+            } else {
+                Event::ClauseDropped { clause: text, from }
+            };
+            self.provenance.record(event)
+        }
+        Ok(())
+    }
+
+    /// Turns a sample of a predicate created by a flattening into a sample of the predicate it
+    /// replaces.
+    ///
+    /// Returns `None` if `pred` was not created by a flattening, or if its arguments are not all
+    /// variables or `select`s over an array variable. Arrays are rebuilt as `store`s over a
+    /// constant array of default values.
+    pub fn unflatten_sample(
+        &self,
+        pred: PrdIdx,
+        args: &VarMap<Val>,
+    ) -> Res<Option<(PrdIdx, VarMap<Val>)>> {
+        let (original, flattening) = if let Some(res) = self
+            .flattenings
+            .iter()
+            .find(|(_, flattening)| flattening.flat == pred)
+        {
+            res
+        } else {
+            return Ok(None);
+        };
+        let sig = self[*original].sig();
+
+        let mut vals: VarMap<Val> = sig
+            .iter()
+            .map(|typ| val::none(typ.clone()))
+            .collect::<Vec<_>>()
+            .into();
+        let mut cells = vec![];
+        for (flat_var, arg) in flattening.args.index_iter() {
+            if let Some(var) = arg.var_idx() {
+                vals[var] = args[flat_var].clone();
+                continue;
+            }
+            match arg.app_inspect() {
+                Some((Op::Select, select_args)) if select_args[0].var_idx().is_some() => {
+                    cells.push((select_args[0].clone(), &select_args[1], flat_var))
+                }
+                _ => return Ok(None),
+            }
+        }
+
+        for (var, typ) in sig.index_iter() {
+            if vals[var].is_known() {
+                continue;
+            }
+            if let Some((src, tgt)) = typ.array_inspect() {
+                vals[var] = val::array(src.clone(), tgt.default_val())
+            }
+        }
+        for (array, idx, flat_var) in cells {
+            let cell = &args[flat_var];
+            if !cell.is_known() {
+                continue;
+            }
+            let idx = idx.eval(&vals)?;
+            if let Some(var) = array.var_idx() {
+                vals[var] = vals[var].store(idx, cell.clone())
+            }
+        }
+
+        Ok(Some((*original, vals)))
+    }
+
+    /// Definition of a predicate split on a boolean argument, as an `ite`, or of a predicate
+    /// flattened, as the definition of the flattened predicate.
+    ///
+    /// Only succeeds if the definitions of the predicates created by the split or the flattening
+    /// are in `defs`.
+    fn recombined_def(&self, pred: PrdIdx, defs: &PrdHMap<Term>) -> Res<Option<Term>> {
+        let map = self[pred].original_sig_term_map()?;
+        let original_args = |args: &VarTerms| -> VarMap<Term> {
+            args.iter()
+                .map(|arg| arg.subst(map).0)
+                .collect::<Vec<_>>()
+                .into()
+        };
+
+        if let Some(flattening) = self.flattenings.get(&pred) {
+            return Ok(defs
+                .get(&flattening.flat)
+                .map(|def| def.subst(&original_args(&flattening.args)).0));
+        }
+
+        let split = if let Some(split) = self.bool_splits.get(&pred) {
+            split
+        } else {
+            return Ok(None);
+        };
+        let (pos, neg) = match (defs.get(&split.pos), defs.get(&split.neg)) {
+            (Some(pos), Some(neg)) => (pos, neg),
+            _ => return Ok(None),
+        };
+        let args = original_args(&split.args);
+        Ok(Some(term::ite(
+            split.cond.subst(map).0,
+            pos.subst(&args).0,
+            neg.subst(&args).0,
+        )))
+    }
+
+    /// True if a predicate was created by a boolean split or a flattening, and does not appear in
+    /// `used`.
+    fn is_unused_aux_pred(&self, pred: PrdIdx, used: &PrdSet) -> bool {
+        !used.contains(&pred)
+            && (self
+                .bool_splits
+                .values()
+                .any(|split| split.pos == pred || split.neg == pred)
+                || self
+                    .flattenings
+                    .values()
+                    .any(|flattening| flattening.flat == pred))
+    }
+
     /// True if the instance is sat, false if unsat.
     fn is_trivial(&self) -> Option<bool> {
         if self.is_unsat {
@@ -445,19 +786,9 @@ impl Instance {
             |w, pred, args| {
                 let pred = &self[pred];
                 write!(w, "({}", pred)?;
-                let mut prev: VarIdx = 0.into();
-                for (var, arg) in args.index_iter() {
-                    let old_var = pred.original_sig_map()[var];
-                    for var in VarRange::new(prev, old_var) {
-                        write!(w, " {}", pred.original_sig()[var].default_val())?
-                    }
-                    prev = old_var;
-                    prev.inc();
+                for arg in pred.original_args(args.get()).iter() {
                     write!(w, " {}", arg)?
                 }
-                for var in VarRange::new(prev, pred.original_sig().next_index()) {
-                    write!(w, " {}", pred.original_sig()[var].default_val())?
-                }
                 write!(w, ")")
             },
         )
@@ -668,6 +999,7 @@ impl Instance {
         let name = name.into();
 
         self.preds.push(Pred::new(name, idx, sig));
+        self.provenance.record(Event::PredCreated { pred: idx });
 
         self.pred_to_clauses
             .push((ClsSet::with_capacity(17), ClsSet::with_capacity(17)));
@@ -736,6 +1068,7 @@ impl Instance {
     ///
     /// Also unlinks predicates from `pred_to_clauses`.
     pub fn forget_clause(&mut self, clause: ClsIdx) -> Res<Clause> {
+        self.record_clause(clause, false)?;
         for pred in self.clauses[clause].lhs_preds().keys() {
             let pred = *pred;
             let was_there = self.pred_to_clauses[pred].0.remove(&clause);
@@ -880,7 +1213,7 @@ impl Instance {
         }
 
         let idx = self.clauses.next_index();
-        let is_new = self.push_clause_unchecked(clause);
+        let is_new = self.push_clause_unchecked(clause)?;
         // self.check("after `push_clause`") ? ;
         Ok(if is_new { Some(idx) } else { None })
     }
@@ -912,13 +1245,13 @@ impl Instance {
     }
 
     /// Pushes a new clause, does not sanity-check but redundancy-checks.
-    fn push_clause_unchecked(&mut self, clause: Clause) -> bool {
+    fn push_clause_unchecked(&mut self, clause: Clause) -> Res<bool> {
         let clause_index = self.clauses.next_index();
         self.clauses.push(clause);
 
         if self.is_redundant(clause_index) {
             self.clauses.pop();
-            return false;
+            return Ok(false);
         }
 
         for pred in self.clauses[clause_index].lhs_preds().keys() {
@@ -930,7 +1263,8 @@ impl Instance {
             let is_new = self.pred_to_clauses[pred].1.insert(clause_index);
             debug_assert!(is_new)
         }
-        true
+        self.record_clause(clause_index, true)?;
+        Ok(true)
     }
 
     /// Checks that the instance has no inconsistencies.
@@ -1335,6 +1669,216 @@ impl Instance {
         Ok(())
     }
 
+    /// Eliminates the existential quantifiers of some top terms, when possible.
+    ///
+    /// See [`term::qe`](../term/qe/index.html) for the details.
+    fn eliminate_exists(tterms: &mut TTerms) {
+        let qe = |quant: &mut Option<Quant>, tterms: &mut TTermSet| {
+            if let Some(Quant::Exists(qvars)) = quant.as_mut() {
+                term::qe::exists_tterms(qvars, tterms);
+                if qvars.is_empty() {
+                    *quant = None
+                }
+            }
+        };
+        match tterms {
+            TTerms::Conj { quant, tterms } => qe(quant, tterms),
+            TTerms::Dnf { disj } => {
+                for (quant, tterms) in disj {
+                    qe(quant, tterms)
+                }
+            }
+            TTerms::True | TTerms::False | TTerms::Disj { .. } => (),
+        }
+    }
+
+    /// Writes a model as a CHC-COMP witness.
+    ///
+    /// The response is a list of definitions over the original signatures of the predicates. It
+    /// defines the functions the definitions mention, and the existential quantifiers introduced
+    /// by pre-processing are eliminated when possible. Datatypes are not declared, the input
+    /// already declares them.
+    pub fn write_witness<W: Write>(&self, model: ConjModelRef, w: &mut W) -> Res<()> {
+        let mut model = model.to_vec();
+        for defs in &mut model {
+            for (_, tterms) in defs {
+                for tterms in tterms {
+                    Self::eliminate_exists(tterms)
+                }
+            }
+        }
+
+        writeln!(w, "(")?;
+        self.write_definitions(w, "  ", &model)?;
+        writeln!(w, ")")?;
+        Ok(())
+    }
+
+    /// Writes the values of some predicate applications in a model.
+    ///
+    /// The arguments of the applications follow the original signature of the predicates. The
+    /// response has the format of a get-value response.
+    pub fn write_values<W: Write>(
+        &self,
+        model: ConjModelRef,
+        apps: &[(PrdIdx, VarMap<Val>)],
+        w: &mut W,
+    ) -> Res<()> {
+        let mut values = Vec::with_capacity(apps.len());
+        for (pred, args) in apps {
+            let value = self
+                .eval_pred_app(model, model.len(), *pred, args)
+                .chain_err(|| format!("while evaluating {}", conf.emph(&self[*pred].name)))?;
+            values.push(value)
+        }
+
+        write!(w, "(")?;
+        for (idx, ((pred, args), value)) in apps.iter().zip(values).enumerate() {
+            if idx > 0 {
+                write!(w, "\n ")?
+            }
+            write!(w, "(({}", self[*pred].name)?;
+            for arg in args.iter() {
+                write!(w, " {}", arg)?
+            }
+            write!(w, ") {})", value)?
+        }
+        writeln!(w, ")")?;
+        Ok(())
+    }
+
+    /// Evaluates a predicate application in a model.
+    ///
+    /// Only the definitions of the first `bound` groups of definitions of the model can be used,
+    /// so that a definition only mentions the predicates defined before it. Existential
+    /// quantifiers are eliminated before evaluation, the definitions where this fails cannot be
+    /// evaluated.
+    fn eval_pred_app(
+        &self,
+        model: ConjModelRef,
+        bound: usize,
+        pred: PrdIdx,
+        args: &VarMap<Val>,
+    ) -> Res<bool> {
+        let def = model.iter().enumerate().find_map(|(group, defs)| {
+            defs.iter()
+                .find(|(p, _)| *p == pred)
+                .map(|(_, def)| (group, def))
+        });
+        let (group, def) = match def {
+            Some((group, def)) if group < bound => (group, def),
+            Some(_) => bail!(
+                "cannot evaluate recursive definition of {}",
+                conf.emph(&self[pred].name)
+            ),
+            None => bail!(
+                "no definition for {} in the model",
+                conf.emph(&self[pred].name)
+            ),
+        };
+
+        for tterms in def {
+            let mut tterms = tterms.clone();
+            Self::eliminate_exists(&mut tterms);
+            if !self.eval_tterms(model, group, &tterms, args)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Evaluates some top terms in a model, see `eval_pred_app`.
+    fn eval_tterms(
+        &self,
+        model: ConjModelRef,
+        bound: usize,
+        tterms: &TTerms,
+        args: &VarMap<Val>,
+    ) -> Res<bool> {
+        match tterms {
+            TTerms::True => Ok(true),
+            TTerms::False => Ok(false),
+            TTerms::Conj {
+                quant: None,
+                tterms,
+            } => self.eval_tterm_set(model, bound, tterms, args, true),
+            TTerms::Disj {
+                quant: None,
+                tterms,
+                neg_preds,
+            } => {
+                if self.eval_tterm_set(model, bound, tterms, args, false)? {
+                    return Ok(true);
+                }
+                for (pred, argss) in neg_preds {
+                    for pred_args in argss {
+                        if !self.eval_pred_args(model, bound, *pred, pred_args, args)? {
+                            return Ok(true);
+                        }
+                    }
+                }
+                Ok(false)
+            }
+            TTerms::Dnf { disj } => {
+                for (quant, tterms) in disj {
+                    if quant.is_some() {
+                        bail!("cannot evaluate quantified definitions")
+                    }
+                    if self.eval_tterm_set(model, bound, tterms, args, true)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            TTerms::Conj { .. } | TTerms::Disj { .. } => {
+                bail!("cannot evaluate quantified definitions")
+            }
+        }
+    }
+
+    /// Evaluates a set of top terms as a conjunction if `conj`, as a disjunction otherwise.
+    fn eval_tterm_set(
+        &self,
+        model: ConjModelRef,
+        bound: usize,
+        tterms: &TTermSet,
+        args: &VarMap<Val>,
+        conj: bool,
+    ) -> Res<bool> {
+        for term in tterms.terms() {
+            match term.bool_eval(args)? {
+                Some(value) if value != conj => return Ok(value),
+                Some(_) => (),
+                None => bail!("could not evaluate {}", term),
+            }
+        }
+        for (pred, argss) in tterms.preds() {
+            for pred_args in argss {
+                let value = self.eval_pred_args(model, bound, *pred, pred_args, args)?;
+                if value != conj {
+                    return Ok(value);
+                }
+            }
+        }
+        Ok(conj)
+    }
+
+    /// Evaluates a predicate application appearing in a definition.
+    fn eval_pred_args(
+        &self,
+        model: ConjModelRef,
+        bound: usize,
+        pred: PrdIdx,
+        pred_args: &VarTerms,
+        args: &VarMap<Val>,
+    ) -> Res<bool> {
+        let mut vals = VarMap::with_capacity(pred_args.len());
+        for arg in pred_args.iter() {
+            vals.push(arg.eval(args)?)
+        }
+        self.eval_pred_app(model, bound, pred, &vals)
+    }
+
     /// Sets print-success flag.
     pub fn set_print_success(&mut self, b: bool) {
         self.print_success = b
@@ -1359,6 +1903,25 @@ impl Instance {
     pub fn proofs(&self) -> bool {
         self.proofs
     }
+    /// True if unsat results come with proofs.
+    ///
+    /// Unsat cores are extracted from proofs.
+    pub fn produces_proofs(&self) -> bool {
+        self.proofs || self.unsat_cores
+    }
+    /// Sets the learning data persistence flag.
+    pub fn set_persist_data(&mut self, b: bool) {
+        self.persist_data = b
+    }
+    /// True if the teacher loads and dumps learning data and warm-starts.
+    pub fn persist_data(&self) -> bool {
+        self.persist_data
+    }
+
+    /// Pre-processing script, if any.
+    pub fn preproc_script(&self) -> Option<&crate::preproc::Script> {
+        self.preproc_script.as_ref()
+    }
 
     /// True if the teacher needs to maintain a sample graph (unsat
     /// cores/proofs).
@@ -1392,6 +1955,10 @@ impl Instance {
                 let proofs = Self::bool_of_str(&val).chain_err(flag_err)?;
                 self.set_proofs(proofs)
             }
+            "preproc-script" => {
+                let script = val.parse().chain_err(flag_err)?;
+                self.preproc_script = Some(script)
+            }
             _ => warn!(
                 "ignoring (set-option :{} {}): unknown flag {}",
                 flag, val, flag
@@ -1399,6 +1966,21 @@ impl Instance {
         }
         Ok(())
     }
+
+    /// Value of an option, `None` if the option is not supported.
+    pub fn get_option(&self, flag: &str) -> Option<String> {
+        match flag {
+            "print-success" => Some(self.print_success().to_string()),
+            "produce-unsat-cores" => Some(self.unsat_cores().to_string()),
+            "produce-proofs" => Some(self.proofs().to_string()),
+            "preproc-script" => Some(
+                self.preproc_script()
+                    .map(|script| format!("\"{}\"", script))
+                    .unwrap_or_else(|| "\"\"".into()),
+            ),
+            _ => None,
+        }
+    }
 }
 
 /// Lhs part of a cex.
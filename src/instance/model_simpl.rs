@@ -0,0 +1,214 @@
+//! Solver-backed simplification of models.
+//!
+//! Simplifies the definitions of the predicates of a model before it is printed:
+//!
+//! - the existential quantifiers introduced by pre-processing are eliminated when possible,
+//! - the branches of nested `ite`s with the same value are merged,
+//! - the conjuncts implied by the rest of the definition are removed.
+//!
+//! Definitions are only replaced by definitions an SMT solver proves equivalent to the original
+//! ones. The predicates they mention are uninterpreted during the checks, so each definition is
+//! equivalent to the original one whatever the definitions of the other predicates are.
+
+use crate::common::*;
+
+/// Merges the branches of the `ite`s of a term.
+///
+/// Rewrites `(ite c t (ite c' t e))` as `(ite (or c c') t e)`, and `(ite c (ite c' t e) e)` as
+/// `(ite (and c c') t e)`.
+fn merge_ites(term: &Term) -> Term {
+    let (op, args) = if let Some((op, args)) = term.app_inspect() {
+        (op, args)
+    } else {
+        return term.clone();
+    };
+    let args: Vec<Term> = args.iter().map(merge_ites).collect();
+    if op != Op::Ite {
+        return term::app(op, args);
+    }
+
+    let (cnd, thn, els) = (&args[0], &args[1], &args[2]);
+    if let Some((Op::Ite, els_args)) = els.app_inspect() {
+        if els_args[1] == *thn {
+            return merge_ites(&term::ite(
+                term::or(vec![cnd.clone(), els_args[0].clone()]),
+                thn.clone(),
+                els_args[2].clone(),
+            ));
+        }
+    }
+    if let Some((Op::Ite, thn_args)) = thn.app_inspect() {
+        if thn_args[2] == *els {
+            return merge_ites(&term::ite(
+                term::and(vec![cnd.clone(), thn_args[0].clone()]),
+                thn_args[1].clone(),
+                els.clone(),
+            ));
+        }
+    }
+
+    term::app(op, args)
+}
+
+/// Merges the branches of the `ite`s of some top terms.
+fn merge_tterms_ites(tterms: &mut TTermSet) {
+    let terms: TermSet = tterms.terms().iter().map(merge_ites).collect();
+    *tterms.terms_mut() = terms
+}
+
+/// Model simplifier.
+struct Simplifier<'a> {
+    /// Instance the model is for.
+    instance: &'a Instance,
+    /// Solver.
+    solver: Solver<()>,
+}
+
+impl<'a> Simplifier<'a> {
+    /// Constructor, declares the predicates of the instance as uninterpreted functions.
+    fn new(instance: &'a Instance) -> Res<Self> {
+        let mut solver = conf.solver.preproc_spawn("model_simpl", (), instance)?;
+        for pred in instance.preds() {
+            let sig: Vec<_> = pred.original_sig().iter().map(|typ| typ.get()).collect();
+            solver.declare_fun(&pred.name, &sig, "Bool")?
+        }
+        Ok(Simplifier { instance, solver })
+    }
+
+    /// True if two definitions of a predicate are equivalent.
+    ///
+    /// Unknown results yield false.
+    fn equivalent(&mut self, pred: PrdIdx, def: &[TTerms], other: &[TTerms]) -> Res<bool> {
+        let mut query = vec![];
+        write!(query, "(not (= ")?;
+        self.instance.write_tterms_conj(&mut query, def)?;
+        write!(query, " ")?;
+        self.instance.write_tterms_conj(&mut query, other)?;
+        write!(query, "))")?;
+        let query = String::from_utf8_lossy(&query).into_owned();
+
+        self.solver.push(1)?;
+        for (var, typ) in self.instance[pred].original_sig().index_iter() {
+            self.solver.declare_const(&var, typ.get())?
+        }
+        self.solver.assert(&query)?;
+        let res = self.solver.check_sat_or_unk()?;
+        self.solver.pop(1)?;
+
+        Ok(res == Some(false))
+    }
+
+    /// Removes the conjuncts of a definition implied by the rest of the definition.
+    fn drop_implied(&mut self, pred: PrdIdx, def: &mut Vec<TTerms>) -> Res<()> {
+        let mut cnt = 0;
+        'conjuncts: while cnt < def.len() {
+            let terms = match def[cnt] {
+                TTerms::Conj {
+                    quant: None,
+                    ref tterms,
+                } => tterms.terms().clone(),
+                _ => {
+                    cnt += 1;
+                    continue 'conjuncts;
+                }
+            };
+
+            for term in terms {
+                conf.check_timeout()?;
+                let mut candidate = def.clone();
+                let is_empty = if let TTerms::Conj { tterms, .. } = &mut candidate[cnt] {
+                    tterms.terms_mut().remove(&term);
+                    tterms.is_empty()
+                } else {
+                    false
+                };
+                if is_empty {
+                    candidate.remove(cnt);
+                }
+
+                if self.equivalent(pred, def, &candidate)? {
+                    log! { @4 "dropping conjunct {} from {}", term, self.instance[pred] }
+                    *def = candidate;
+                    if is_empty {
+                        continue 'conjuncts;
+                    }
+                }
+            }
+
+            cnt += 1
+        }
+        Ok(())
+    }
+
+    /// Simplifies the definition of a predicate.
+    fn simplify(&mut self, pred: PrdIdx, def: &mut Vec<TTerms>) -> Res<()> {
+        let mut simplified = def.clone();
+        for tterms in &mut simplified {
+            Instance::eliminate_exists(tterms);
+            match tterms {
+                TTerms::Conj { tterms, .. } | TTerms::Disj { tterms, .. } => {
+                    merge_tterms_ites(tterms)
+                }
+                TTerms::Dnf { disj } => {
+                    for (_, tterms) in disj {
+                        merge_tterms_ites(tterms)
+                    }
+                }
+                TTerms::True | TTerms::False => (),
+            }
+        }
+        self.drop_implied(pred, &mut simplified)?;
+
+        if simplified == *def {
+            return Ok(());
+        }
+        if self.equivalent(pred, def, &simplified)? {
+            *def = simplified
+        } else {
+            log! { @verb
+                "could not prove the simplified definition of {} equivalent, \
+                 keeping the original one",
+                self.instance[pred]
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Instance {
+    /// Simplifies the definitions of a model.
+    ///
+    /// Eliminates existential quantifiers, merges `ite` branches and removes implied conjuncts.
+    /// Definitions that cannot be proved equivalent to their simplified version are left as they
+    /// are.
+    pub fn simplify_model(&self, model: &mut ConjModel) -> Res<()> {
+        let mut simplifier = Simplifier::new(self)?;
+        for defs in model {
+            for (pred, def) in defs {
+                simplifier
+                    .simplify(*pred, def)
+                    .chain_err(|| format!("while simplifying the definition of {}", self[*pred]))?
+            }
+        }
+        Ok(())
+    }
+
+    /// Simplified version of a model, see [`simplify_model`].
+    ///
+    /// Returns the model as it is if the simplification fails.
+    ///
+    /// [`simplify_model`]: #method.simplify_model (simplify_model function)
+    pub fn simplified_model(&self, model: ConjModel) -> ConjModel {
+        let mut simplified = model.clone();
+        match self.simplify_model(&mut simplified) {
+            Ok(()) => simplified,
+            Err(e) => {
+                warn!(
+                    "could not simplify the model, keeping it as it is";
+                    "{}", e
+                );
+                model
+            }
+        }
+    }
+}
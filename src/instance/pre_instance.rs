@@ -5,6 +5,7 @@ use crate::{
         smt::{ClauseTrivialExt, SmtImpl},
         *,
     },
+    instance::LoggedClause,
     preproc::utils::ExtractionCxt,
 };
 
@@ -429,6 +430,10 @@ impl<'a> PreInstance<'a> {
             log! { @3 "propagation..." }
             self.simplifier
                 .clause_propagate(&mut self.instance.clauses[clause], &self.instance.preds)?;
+            if conf.preproc.qe {
+                log! { @3 "quantifier elimination..." }
+                self.clause_qe(clause)?
+            }
             log! { @3 "pruning..." }
             // Remove redundant atoms.
             if conf.preproc.prune_terms {
@@ -442,6 +447,61 @@ impl<'a> PreInstance<'a> {
         }
     }
 
+    /// Eliminates the arithmetic variables of a clause that only appear in its lhs terms.
+    fn clause_qe(&mut self, clause: ClsIdx) -> Res<()> {
+        let clause = &mut self.instance.clauses[clause];
+
+        let mut keep = VarSet::new();
+        for argss in clause.lhs_preds().values() {
+            for args in argss {
+                for arg in args.iter() {
+                    keep.extend(term::vars(arg))
+                }
+            }
+        }
+        if let Some((_, args)) = clause.rhs() {
+            for arg in args.iter() {
+                keep.extend(term::vars(arg))
+            }
+        }
+
+        let qvars: VarHMap<Typ> = clause
+            .vars()
+            .iter()
+            .filter(|info| info.active && info.typ.is_arith() && !keep.contains(&info.idx))
+            .map(|info| (info.idx, info.typ.clone()))
+            .collect();
+        if qvars.is_empty() {
+            return Ok(());
+        }
+
+        let mut terms = clause.lhs_terms().clone();
+        let eliminated = term::qe::exists(&qvars, &mut terms);
+        if eliminated.is_empty() {
+            return Ok(());
+        }
+
+        let to_rm: Vec<Term> = clause
+            .lhs_terms()
+            .iter()
+            .filter(|term| !terms.contains(*term))
+            .cloned()
+            .collect();
+        for term in to_rm {
+            clause.rm_term(&term);
+        }
+        for term in terms {
+            clause.insert_term(term);
+        }
+        for var in eliminated {
+            if clause.vars()[var].active {
+                clause.deactivate(var)?
+            }
+        }
+
+        Ok(())
+    }
+
     /// Simplifies a clause.
     ///
     /// This function might create new clauses. Potentially voids the semantics
@@ -547,6 +607,9 @@ impl<'a> PreInstance<'a> {
             }
         }
 
+        let parents = self.instance.logged_clauses(Some(idx))?;
+        let prev_parents = self.instance.set_clause_parents(parents);
+
         if let Some((disj, f_subs, others)) = split {
             debug_assert! { ! f_subs.is_empty() }
 
@@ -622,6 +685,8 @@ impl<'a> PreInstance<'a> {
             }
         }
 
+        self.instance.set_clause_parents(prev_parents);
+
         Ok(info)
     }
 
@@ -787,7 +852,8 @@ impl<'a> PreInstance<'a> {
                     .collect(),
             );
             debug_assert! { !self.instance[pred].is_defined() }
-            self.instance.preds[pred].set_def(def)?
+            self.instance.preds[pred].set_def(def)?;
+            self.record_def(pred)?
         }
 
         // Check if instance is unsat before dropping clauses.
@@ -795,6 +861,13 @@ impl<'a> PreInstance<'a> {
         let is_sat = self.check_pred_defs()?;
 
         // Drop all clauses.
+        if self.instance.provenance.is_active() {
+            let mut clause: ClsIdx = 0.into();
+            while clause < self.instance.clauses.len() {
+                self.instance.record_clause(clause, false)?;
+                clause.inc()
+            }
+        }
         log_debug! { "  unlinking all predicates" }
         for &mut (ref mut lhs, ref mut rhs) in self.instance.pred_to_clauses.iter_mut() {
             lhs.clear();
@@ -850,11 +923,35 @@ impl<'a> PreInstance<'a> {
                 String::from_utf8_lossy(&s)
             )
         } else {
-            self.instance.preds[pred].set_def(tterms)?
+            self.instance.preds[pred].set_def(tterms)?;
+            self.record_def(pred)?
         }
         Ok(())
     }
 
+    /// Records the definition of a predicate in the provenance log.
+    fn record_def(&mut self, pred: PrdIdx) -> Res<()> {
+        if !self.instance.provenance.is_active() {
+            return Ok(());
+        }
+        let mut s: Vec<u8> = Vec::new();
+        if let Some(def) = self.instance[pred].def() {
+            def.write_smt2(&mut s, |w, pred, args| {
+                write!(w, "({}", self[pred])?;
+                for arg in args.iter() {
+                    write!(w, " {}", arg)?
+                }
+                write!(w, ")")
+            })
+            .chain_err(|| "while dumping a definition in the provenance log")?
+        }
+        let def = String::from_utf8_lossy(&s).into_owned();
+        self.instance
+            .provenance
+            .record(crate::instance::Event::PredForced { pred, def });
+        Ok(())
+    }
+
     /// Forces some predicate to false.
     ///
     /// Simplifies all clauses impacted.
@@ -929,6 +1026,393 @@ impl<'a> PreInstance<'a> {
         Ok(info)
     }
 
+    /// Merges a predicate into another one with the same signature.
+    ///
+    /// Replaces all the applications of `pred` by applications of `into`, and forces `pred` to be
+    /// `into` so that its definition is restored in the model.
+    ///
+    /// # Consequences
+    ///
+    /// - `pred` does not appear in any clause anymore
+    /// - simplifies all clauses impacted
+    ///
+    /// # Used by
+    ///
+    /// - `PredMerge`
+    pub fn merge_pred(&mut self, pred: PrdIdx, into: PrdIdx) -> Res<RedInfo> {
+        self.check("before `merge_pred`")?;
+
+        if pred == into || self.instance[pred].sig != self.instance[into].sig {
+            bail!(
+                "illegal context for `merge_pred`, cannot merge {} into {}",
+                conf.emph(&self.instance[pred].name),
+                conf.emph(&self.instance[into].name)
+            )
+        }
+
+        let mut info = RedInfo::new();
+
+        // Update lhs clauses.
+        debug_assert! { self.clauses_to_simplify.is_empty() }
+        self.instance
+            .unlink_pred_lhs(pred, &mut self.clauses_to_simplify);
+        for clause in &self.clauses_to_simplify {
+            let clause = *clause;
+            let argss = if let Some(argss) = self.instance.clauses[clause].drop_lhs_pred(pred) {
+                argss
+            } else {
+                bail!(
+                    "inconsistent instance state, \
+                     `pred_to_clauses` and clauses out of sync"
+                )
+            };
+            self.instance.clause_lhs_extend(
+                clause,
+                argss.into_iter().map(|args| TTerm::P { pred: into, args }),
+            )
+        }
+
+        // Update rhs clauses.
+        let lhs_count = self.clauses_to_simplify.len();
+        self.instance
+            .unlink_pred_rhs(pred, &mut self.clauses_to_simplify);
+        for clause in &self.clauses_to_simplify[lhs_count..] {
+            let clause = *clause;
+            if let Some((_, args)) = self.instance.clauses[clause].unset_rhs() {
+                self.instance
+                    .clause_rhs_force(clause, TTerm::P { pred: into, args })?
+            } else {
+                bail!(
+                    "inconsistent instance state, \
+                     `pred_to_clauses` and clauses out of sync"
+                )
+            }
+        }
+
+        // Actually force the predicate.
+        let args: VarMap<_> = self.instance[pred]
+            .sig
+            .index_iter()
+            .map(|(var, typ)| term::var(var, typ.clone()))
+            .collect();
+        let mut tterm_set = TTermSet::new();
+        tterm_set.insert_pred_app(into, var_to::terms::new(args));
+        self.force_pred(pred, TTerms::conj(None, tterm_set))?;
+
+        info += self.simplify_clauses()?;
+
+        self.check("after `merge_pred`")?;
+
+        Ok(info)
+    }
+
+    /// Name for a new predicate derived from `pred`, of the form `<pred>_<suffix>`.
+    ///
+    /// Appends a counter if the name is already taken.
+    fn fresh_pred_name(&self, pred: PrdIdx, suffix: &str) -> String {
+        let base = format!("{}_{}", self.instance[pred].name, suffix);
+        let mut name = base.clone();
+        let mut cnt = 0;
+        while self.instance.preds().iter().any(|pred| pred.name == name) {
+            cnt += 1;
+            name = format!("{}_{}", base, cnt)
+        }
+        name
+    }
+
+    /// Replaces a predicate by a new predicate over a flattened signature.
+    ///
+    /// Creates a predicate `<pred>_<suffix>` with signature `sig`. `args` are the arguments of the
+    /// new predicate in terms of the variables of `pred`. All the applications `(pred a)` are
+    /// replaced by applications of the new predicate to `args` where the variables are replaced by
+    /// `a`. `pred` is then forced to `(<pred>_<suffix> args)`, which is how its definition is
+    /// restored in the model.
+    ///
+    /// For the result to be equisatisfiable, `args` must be an injective encoding of the arguments
+    /// of `pred`, or the clauses must only depend on the arguments of `pred` through `args`.
+    ///
+    /// Returns the new predicate.
+    ///
+    /// # Consequences
+    ///
+    /// - `pred` does not appear in any clause anymore
+    /// - simplifies all clauses impacted
+    ///
+    /// # Used by
+    ///
+    /// - `AdtFlatten`
+    /// - `ArrayElim`
+    pub fn flatten_pred(
+        &mut self,
+        pred: PrdIdx,
+        suffix: &str,
+        sig: Sig,
+        args: VarTerms,
+    ) -> Res<(RedInfo, PrdIdx)> {
+        self.check("before `flatten_pred`")?;
+
+        if self.instance[pred].is_defined() || args.len() != sig.len() {
+            bail!(
+                "illegal context for `flatten_pred`, cannot flatten {}",
+                conf.emph(&self.instance[pred].name),
+            )
+        }
+
+        let mut info = RedInfo::new();
+
+        let flat_name = self.fresh_pred_name(pred, suffix);
+        let flat = self.instance.push_pred(flat_name, sig);
+
+        let flatten_args = |app_args: &VarTerms| -> Res<VarTerms> {
+            let mut nu_args = VarMap::with_capacity(args.len());
+            for arg in args.iter() {
+                if let Some((arg, _)) = arg.subst_total(app_args) {
+                    nu_args.push(arg)
+                } else {
+                    bail!("failed to flatten arguments {}", app_args)
+                }
+            }
+            Ok(var_to::terms::new(nu_args))
+        };
+
+        // Update lhs clauses.
+        debug_assert! { self.clauses_to_simplify.is_empty() }
+        self.instance
+            .unlink_pred_lhs(pred, &mut self.clauses_to_simplify);
+        for clause in &self.clauses_to_simplify {
+            let clause = *clause;
+            let argss = if let Some(argss) = self.instance.clauses[clause].drop_lhs_pred(pred) {
+                argss
+            } else {
+                bail!(
+                    "inconsistent instance state, \
+                     `pred_to_clauses` and clauses out of sync"
+                )
+            };
+            let mut apps = Vec::with_capacity(argss.len());
+            for app_args in &argss {
+                apps.push(TTerm::P {
+                    pred: flat,
+                    args: flatten_args(app_args)?,
+                })
+            }
+            self.instance.clause_lhs_extend(clause, apps)
+        }
+
+        // Update rhs clauses.
+        let lhs_count = self.clauses_to_simplify.len();
+        self.instance
+            .unlink_pred_rhs(pred, &mut self.clauses_to_simplify);
+        for clause in &self.clauses_to_simplify[lhs_count..] {
+            let clause = *clause;
+            if let Some((_, app_args)) = self.instance.clauses[clause].unset_rhs() {
+                let args = flatten_args(&app_args)?;
+                self.instance
+                    .clause_rhs_force(clause, TTerm::P { pred: flat, args })?
+            } else {
+                bail!(
+                    "inconsistent instance state, \
+                     `pred_to_clauses` and clauses out of sync"
+                )
+            }
+        }
+
+        // Actually force the predicate.
+        let mut tterm_set = TTermSet::new();
+        tterm_set.insert_pred_app(flat, args.clone());
+        self.force_pred(pred, TTerms::conj(None, tterm_set))?;
+        self.instance
+            .register_flattening(pred, crate::instance::FlattenDef { args, flat });
+
+        info += self.simplify_clauses()?;
+
+        self.check("after `flatten_pred`")?;
+
+        Ok((info, flat))
+    }
+
+    /// Splits a predicate on one of its boolean arguments.
+    ///
+    /// Creates two predicates `<pred>_true` and `<pred>_false`, with the signature of `pred` minus
+    /// `var`. The clauses mentioning `pred` are rewritten to use the new predicates. A clause
+    /// where the argument is not a constant is first case-split into a clause where it is true and
+    /// a clause where it is false. `pred` is then forced to `(ite var <pred>_true <pred>_false)`,
+    /// which is how its definition is recombined in the model.
+    ///
+    /// Returns the two new predicates.
+    ///
+    /// # Consequences
+    ///
+    /// - `pred` does not appear in any clause anymore
+    /// - simplifies all clauses impacted
+    ///
+    /// # Used by
+    ///
+    /// - `BoolSplit`
+    pub fn split_pred_on_bool(
+        &mut self,
+        pred: PrdIdx,
+        var: VarIdx,
+    ) -> Res<(RedInfo, PrdIdx, PrdIdx)> {
+        self.check("before `split_pred_on_bool`")?;
+
+        if self.instance[pred].is_defined() || !self.instance[pred].sig[var].is_bool() {
+            bail!(
+                "illegal context for `split_pred_on_bool`, cannot split {} on v_{}",
+                conf.emph(&self.instance[pred].name),
+                var
+            )
+        }
+
+        let mut info = RedInfo::new();
+
+        // Create the new predicates.
+        let sig: VarMap<Typ> = self.instance[pred]
+            .sig
+            .index_iter()
+            .filter(|(v, _)| *v != var)
+            .map(|(_, typ)| typ.clone())
+            .collect();
+        let pos_name = self.fresh_pred_name(pred, "true");
+        let pos = self.instance.push_pred(pos_name, sig.clone());
+        let neg_name = self.fresh_pred_name(pred, "false");
+        let neg = self.instance.push_pred(neg_name, sig);
+
+        // Remove all the clauses mentioning `pred`.
+        let mut clauses: Vec<ClsIdx> = self
+            .instance
+            .lhs_clauses_of(pred)
+            .iter()
+            .chain(self.instance.rhs_clauses_of(pred).iter())
+            .cloned()
+            .collect();
+        clauses.sort_unstable_by(|c_1, c_2| c_2.cmp(c_1));
+        clauses.dedup();
+        let mut to_rewrite = Vec::with_capacity(clauses.len());
+        for clause in clauses {
+            let parents = self.instance.logged_clauses(Some(clause))?;
+            to_rewrite.push((self.instance.forget_clause(clause)?, parents));
+            info.clauses_rmed += 1
+        }
+
+        let split_args = |args: &VarTerms| {
+            let value = args[var].bool() == Some(true);
+            let nu_args: VarMap<Term> = args
+                .index_iter()
+                .filter(|(v, _)| *v != var)
+                .map(|(_, arg)| arg.clone())
+                .collect();
+            (if value { pos } else { neg }, var_to::terms::new(nu_args))
+        };
+
+        debug_assert! { self.clauses_to_simplify.is_empty() }
+
+        while let Some((mut clause, parents)) = to_rewrite.pop() {
+            // Look for an application where the argument is not a constant.
+            let mut cond = clause.lhs_preds().get(&pred).and_then(|argss| {
+                argss
+                    .iter()
+                    .map(|args| &args[var])
+                    .find(|arg| arg.bool().is_none())
+                    .cloned()
+            });
+            if cond.is_none() {
+                if let Some((p, args)) = clause.rhs() {
+                    if p == pred && args[var].bool().is_none() {
+                        cond = Some(args[var].clone())
+                    }
+                }
+            }
+
+            if let Some(cond) = cond {
+                // Case-split.
+                for value in &[true, false] {
+                    let value = *value;
+                    let mut nu_clause = clause.clone();
+                    let set_value = |args: &VarTerms| {
+                        if args[var] == cond {
+                            let mut nu_args: VarMap<Term> = args.iter().cloned().collect();
+                            nu_args[var] = term::bool(value);
+                            var_to::terms::new(nu_args)
+                        } else {
+                            args.clone()
+                        }
+                    };
+                    nu_clause.lhs_map_args_of(pred, &set_value);
+                    nu_clause.rhs_map_args(|p, args| {
+                        if p == pred {
+                            (p, set_value(args))
+                        } else {
+                            (p, args.clone())
+                        }
+                    });
+                    nu_clause.insert_term(if value {
+                        cond.clone()
+                    } else {
+                        term::not(cond.clone())
+                    });
+                    to_rewrite.push((nu_clause, parents.clone()))
+                }
+                continue;
+            }
+
+            // All arguments are constants, rename.
+            if let Some(argss) = clause.drop_lhs_pred(pred) {
+                for args in &argss {
+                    let (nu_pred, nu_args) = split_args(args);
+                    clause.insert_pred_app(nu_pred, nu_args);
+                }
+            }
+            clause.rhs_map_args(|p, args| {
+                if p == pred {
+                    split_args(args)
+                } else {
+                    (p, args.clone())
+                }
+            });
+
+            let prev_parents = self.instance.set_clause_parents(parents);
+            let idx = self.instance.push_clause(clause)?;
+            self.instance.set_clause_parents(prev_parents);
+            if let Some(idx) = idx {
+                self.clauses_to_simplify.push(idx);
+                info.clauses_added += 1
+            }
+        }
+
+        // Actually force the predicate.
+        let cond = term::var(var, typ::bool());
+        let args: VarMap<Term> = self.instance[pred]
+            .sig
+            .index_iter()
+            .filter(|(v, _)| *v != var)
+            .map(|(v, typ)| term::var(v, typ.clone()))
+            .collect();
+        let args = var_to::terms::new(args);
+        let mut pos_conj = TTermSet::new();
+        pos_conj.insert_term(cond.clone());
+        pos_conj.insert_pred_app(pos, args.clone());
+        let mut neg_conj = TTermSet::new();
+        neg_conj.insert_term(term::not(cond.clone()));
+        neg_conj.insert_pred_app(neg, args.clone());
+        self.force_pred(pred, TTerms::dnf(vec![(None, pos_conj), (None, neg_conj)]))?;
+        self.instance.register_bool_split(
+            pred,
+            crate::instance::BoolSplitDef {
+                cond,
+                args,
+                pos,
+                neg,
+            },
+        );
+
+        info += self.simplify_clauses()?;
+
+        self.check("after `split_pred_on_bool`")?;
+
+        Ok((info, pos, neg))
+    }
+
     /// Forces the lhs occurences of a predicate to be equal to something.
     ///
     /// If `pred` appears in `pred /\ apps /\ trms => rhs`, the clause will
@@ -957,11 +1441,15 @@ impl<'a> PreInstance<'a> {
     pub fn force_pred_left(
         &mut self,
         pred: PrdIdx,
-        qvars: Quantfed,
-        tterm_set: TTermSet,
+        mut qvars: Quantfed,
+        mut tterm_set: TTermSet,
     ) -> Res<RedInfo> {
         self.check("before `force_pred_left`")?;
 
+        if conf.preproc.qe {
+            term::qe::exists_tterms(&mut qvars, &mut tterm_set);
+        }
+
         // let mut tterm_set = TTermSet::new() ;
         // tterm_set.insert_terms(terms) ;
         // for (pred, args) in pred_apps {
@@ -1012,6 +1500,7 @@ impl<'a> PreInstance<'a> {
         };
 
         info.clauses_rmed += 1;
+        self.instance.log_def_clauses(pred, &[clause_to_rm])?;
         self.instance.forget_clause(clause_to_rm)?;
 
         // Update lhs clauses.
@@ -1029,6 +1518,10 @@ impl<'a> PreInstance<'a> {
                 self.instance[clause].to_string_info(self.instance.preds()).unwrap()
             }
 
+            let mut parents = self.instance.logged_clauses(Some(clause))?;
+            parents.extend(self.instance.def_clauses(pred));
+            self.instance.record_clause(clause, false)?;
+
             let argss = if let Some(argss) = self.instance.clauses[clause].drop_lhs_pred(pred) {
                 argss
             } else {
@@ -1071,6 +1564,8 @@ impl<'a> PreInstance<'a> {
               ).unwrap()
             }
 
+            self.instance.record_rewritten_clause(clause, parents)?;
+
             debug_assert! { self.instance[clause].preds_changed() }
         }
 
@@ -1198,7 +1693,12 @@ impl<'a> PreInstance<'a> {
     pub fn force_dnf_left(&mut self, pred: PrdIdx, def: Dnf) -> Res<RedInfo> {
         let def: Vec<_> = def
             .into_iter()
-            .map(|(qvars, conj)| (Quant::exists(qvars), conj))
+            .map(|(mut qvars, mut conj)| {
+                if conf.preproc.qe {
+                    term::qe::exists_tterms(&mut qvars, &mut conj);
+                }
+                (Quant::exists(qvars), conj)
+            })
             .collect();
 
         if def.is_empty() {
@@ -1236,12 +1736,16 @@ impl<'a> PreInstance<'a> {
             .sort_unstable_by(|c_1, c_2| c_2.cmp(c_1));
 
         let mut nu_clauses = vec![];
+        let def_clauses = self.instance.def_clauses(pred);
 
         for clause in self.clauses_to_simplify.drain(0..) {
             info.clauses_rmed += 1;
 
             log! { @7 | "working on #{}", clause }
 
+            let mut parents = self.instance.logged_clauses(Some(clause))?;
+            parents.extend(def_clauses.iter().cloned());
+
             let pred_argss: Vec<VarTerms> =
                 if let Some(argss) = self.instance.clauses[clause].drop_lhs_pred(pred) {
                     argss.iter().cloned().collect()
@@ -1298,15 +1802,17 @@ impl<'a> PreInstance<'a> {
                     }
                 }
 
-                nu_clauses.push(clause)
+                nu_clauses.push((clause, parents.clone()))
             }
         }
 
         // Actually force the predicate.
         self.force_pred(pred, TTerms::dnf(def))?;
 
-        for clause in nu_clauses {
-            let is_new = self.instance.push_clause_unchecked(clause);
+        for (clause, parents) in nu_clauses {
+            let prev_parents = self.instance.set_clause_parents(parents);
+            let is_new = self.instance.push_clause_unchecked(clause)?;
+            self.instance.set_clause_parents(prev_parents);
             if is_new {
                 info.clauses_added += 1;
             }
@@ -1392,6 +1898,15 @@ impl<'a> PreInstance<'a> {
             "force pred right on {}...", conf.emph(& self.instance[pred].name)
         }
 
+        // Clause the definition is extracted from.
+        let def_clauses: Vec<_> = self.instance.pred_to_clauses[pred]
+            .0
+            .iter()
+            .cloned()
+            .collect();
+        self.instance.log_def_clauses(pred, &def_clauses)?;
+        let def_clauses = self.instance.def_clauses(pred);
+
         // Update rhs clauses.
         debug_assert! { self.clauses_to_simplify.is_empty() }
         self.instance
@@ -1404,6 +1919,10 @@ impl<'a> PreInstance<'a> {
                 "{}", self.instance[clause].to_string_info(self.instance.preds()).unwrap()
             }
 
+            let mut parents = self.instance.logged_clauses(Some(clause))?;
+            parents.extend(def_clauses.iter().cloned());
+            self.instance.record_clause(clause, false)?;
+
             let rhs = self.instance.clauses[clause].unset_rhs();
 
             if let Some((prd, subst)) = rhs {
@@ -1455,6 +1974,8 @@ impl<'a> PreInstance<'a> {
                         }
                     }
 
+                    self.instance.record_rewritten_clause(clause, parents)?;
+
                     // Explicitely continueing, otherwise the factored error message
                     // below will fire.
                     continue 'clause_iter;
@@ -1507,6 +2028,7 @@ impl<'a> PreInstance<'a> {
         }
 
         for clause in &self.instance.pred_to_clauses[pred].0 {
+            let parents = self.instance.logged_clauses(Some(*clause))?;
             let clause = &self.instance[*clause];
 
             // Negative clause and `pred` is the only application.
@@ -1552,15 +2074,18 @@ impl<'a> PreInstance<'a> {
                         nu_clause.to_string_info(& self.preds).unwrap()
                     }
                     // nu_clause.from_unrolling = true ;
-                    to_add.push(nu_clause)
+                    to_add.push((nu_clause, parents.clone()))
                 }
             }
         }
 
         log! { @debug "adding {} clauses", to_add.len() }
 
-        for clause in to_add {
-            if let Some(index) = self.instance.push_clause(clause)? {
+        for (clause, parents) in to_add {
+            let prev_parents = self.instance.set_clause_parents(parents);
+            let index = self.instance.push_clause(clause)?;
+            self.instance.set_clause_parents(prev_parents);
+            if let Some(index) = index {
                 let mut simplinfo = self.simplify_clause(index)?;
                 if simplinfo.clauses_rmed > 0 {
                     simplinfo.clauses_rmed -= 1
@@ -1594,6 +2119,7 @@ impl<'a> PreInstance<'a> {
         let fls = term::fls();
 
         for clause in &self.instance.pred_to_clauses[pred].1 {
+            let parents = self.instance.logged_clauses(Some(*clause))?;
             let clause = &self.instance[*clause];
 
             // Negative clause and `pred` is the only application.
@@ -1626,16 +2152,19 @@ impl<'a> PreInstance<'a> {
 
                 if !nu_clause.lhs_terms().contains(&fls) {
                     // nu_clause.from_unrolling = true ;
-                    to_add.push(nu_clause)
+                    to_add.push((nu_clause, parents.clone()))
                 }
             }
         }
 
-        for clause in to_add {
+        for (clause, parents) in to_add {
             log! { @4
                 "adding clause {}", clause.to_string_info(& self.preds).unwrap()
             }
-            if let Some(index) = self.instance.push_clause(clause)? {
+            let prev_parents = self.instance.set_clause_parents(parents);
+            let index = self.instance.push_clause(clause)?;
+            self.instance.set_clause_parents(prev_parents);
+            if let Some(index) = index {
                 let mut simplinfo = self.simplify_clause(index)?;
                 if simplinfo.clauses_rmed > 0 {
                     simplinfo.clauses_rmed -= 1
@@ -1651,6 +2180,33 @@ impl<'a> PreInstance<'a> {
         Ok(info)
     }
 
+    /// Adds a clause derived from some other clauses to the instance and simplifies it.
+    ///
+    /// The `parents` are logged in the provenance log, see `logged_clauses`.
+    ///
+    /// # Used by
+    ///
+    /// - `LoopAccel`
+    pub fn add_clause(&mut self, clause: Clause, parents: Vec<LoggedClause>) -> Res<RedInfo> {
+        let mut info = RedInfo::new();
+        log! { @4
+            "adding clause {}", clause.to_string_info(& self.preds).unwrap()
+        }
+        let prev_parents = self.instance.set_clause_parents(parents);
+        let index = self.instance.push_clause(clause)?;
+        self.instance.set_clause_parents(prev_parents);
+        if let Some(index) = index {
+            let mut simplinfo = self.simplify_clause(index)?;
+            if simplinfo.clauses_rmed > 0 {
+                simplinfo.clauses_rmed -= 1
+            } else {
+                simplinfo.clauses_added += 1
+            }
+            info += simplinfo
+        }
+        Ok(info)
+    }
+
     /// Removes some arguments for a predicate.
     ///
     /// Returns `true` if something happened.
@@ -1690,6 +2246,18 @@ impl<'a> PreInstance<'a> {
             }
         }
 
+        if self.instance.provenance.is_active() {
+            let args = self[pred]
+                .sig
+                .index_iter()
+                .filter(|(var, _)| !to_keep.contains(var))
+                .map(|(var, _)| self[pred].original_sig_map()[var])
+                .collect();
+            self.instance
+                .provenance
+                .record(crate::instance::Event::ArgsRemoved { pred, args })
+        }
+
         // Update `preds` with the new signature.
         self.instance.preds[pred].set_sig(nu_sig, var_map);
 
@@ -1784,6 +2352,172 @@ impl<'a> PreInstance<'a> {
         Ok(info)
     }
 
+    /// Largest inductive subset of some candidate argument invariants.
+    ///
+    /// Candidates are terms over the current signature of the predicates. A set of candidates is
+    /// inductive if, for all clauses, the candidates hold for the rhs application when they hold
+    /// for all the lhs applications. Candidates that cannot be proved to hold, including when the
+    /// solver returns `unknown`, are dropped until a fixpoint is reached.
+    pub fn inductive_invariants(
+        &mut self,
+        mut candidates: PrdHMap<Vec<Term>>,
+    ) -> Res<PrdHMap<Vec<Term>>> {
+        let (instance, solver) = (&self.instance, &mut self.solver);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (_idx, clause) in instance.clauses.index_iter() {
+                let (pred, args) = if let Some((pred, args)) = clause.rhs() {
+                    (pred, args)
+                } else {
+                    continue;
+                };
+                let cands = if let Some(cands) = candidates.get(&pred) {
+                    cands
+                } else {
+                    continue;
+                };
+                if cands.is_empty() {
+                    continue;
+                }
+                conf.check_timeout()?;
+                log! { @5 "checking argument invariants of {} on clause #{}", instance[pred], _idx }
+
+                solver.push(1)?;
+                clause.declare(solver)?;
+                for term in clause.lhs_terms() {
+                    solver.assert(&smt::SmtTerm::new(term))?
+                }
+                for (lhs_pred, argss) in clause.lhs_preds() {
+                    if let Some(lhs_cands) = candidates.get(lhs_pred) {
+                        for args in argss {
+                            for cand in lhs_cands {
+                                if let Some((term, _)) = cand.subst_total(args) {
+                                    solver.assert(&smt::SmtTerm::new(&term))?
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let mut nu_cands = Vec::with_capacity(cands.len());
+                for cand in cands {
+                    let holds = if let Some((term, _)) = cand.subst_total(args) {
+                        solver.push(1)?;
+                        solver.assert(&smt::SmtTerm::new(&term::not(term)))?;
+                        let sat = solver.check_sat_or_unk()?;
+                        solver.pop(1)?;
+                        sat == Some(false)
+                    } else {
+                        false
+                    };
+                    if holds {
+                        nu_cands.push(cand.clone())
+                    }
+                }
+                solver.pop(1)?;
+
+                if nu_cands.len() != cands.len() {
+                    changed = true;
+                    candidates.insert(pred, nu_cands);
+                }
+            }
+        }
+
+        Ok(candidates)
+    }
+
+    /// Removes predicate arguments that are defined by argument invariants.
+    ///
+    /// Simplifies before returning.
+    ///
+    /// For each predicate, `invariants` are terms over its current signature that hold for all the
+    /// applications of the predicate in a rhs, assuming they hold for the applications in the lhs.
+    /// The map gives, for each argument to remove, its value as a term over the arguments kept.
+    ///
+    /// The invariants are added to the clauses for each lhs application of the predicate, and
+    /// registered in the predicate information (over the original signature) so that the model
+    /// can be strengthened with them.
+    pub fn rm_args_by_invariants(
+        &mut self,
+        invariants: PrdHMap<(Vec<Term>, VarHMap<Term>)>,
+    ) -> Res<RedInfo> {
+        self.check("rm_args_by_invariants")?;
+
+        let mut info = RedInfo::new();
+
+        for (pred, (invs, defs)) in invariants {
+            if defs.is_empty() {
+                continue;
+            }
+            log! { @4 | "- {} ({} invariant(s))", self[pred], invs.len() }
+
+            let clauses: Vec<ClsIdx> = self.instance.pred_to_clauses[pred]
+                .0
+                .iter()
+                .cloned()
+                .collect();
+            for clause in clauses {
+                let mut nu_terms = vec![];
+                if let Some(argss) = self.instance.clauses[clause].lhs_preds().get(&pred) {
+                    for args in argss {
+                        for inv in &invs {
+                            if let Some((term, _)) = inv.subst_total(args) {
+                                nu_terms.push(term)
+                            } else {
+                                bail!("ill-formed invariant {} for {}", inv, self[pred])
+                            }
+                        }
+                    }
+                }
+                for term in nu_terms {
+                    self.instance.clauses[clause].insert_term(term);
+                }
+                conf.check_timeout()?
+            }
+
+            let to_original: VarMap<Term> = self[pred]
+                .original_sig_map()
+                .iter()
+                .map(|var| term::var(*var, self[pred].original_sig()[*var].clone()))
+                .collect();
+            let invs = invs
+                .into_iter()
+                .map(|inv| inv.subst(&to_original).0)
+                .collect();
+            let mut arg_defs: Vec<_> = defs
+                .iter()
+                .map(|(var, def)| (to_original[*var].clone(), def.subst(&to_original).0))
+                .filter_map(|(var, def)| var.var_idx().map(|var| (var, def)))
+                .collect();
+            arg_defs.sort_unstable_by_key(|(var, _)| *var);
+            self.instance.preds[pred].add_invariants(invs, arg_defs);
+
+            let to_keep: VarSet = self[pred]
+                .sig
+                .index_iter()
+                .filter_map(|(var, _)| {
+                    if defs.contains_key(&var) {
+                        None
+                    } else {
+                        Some(var)
+                    }
+                })
+                .collect();
+            info.args_rmed += self.rm_args_of(pred, &to_keep)?
+        }
+
+        if info.args_rmed > 0 {
+            info += self.simplify_all()?;
+        }
+
+        self.check("after `rm_args_by_invariants`")?;
+
+        Ok(info)
+    }
+
     /// Removes all clauses in which `pred` is in the rhs.
     ///
     /// Does not run simplifications.
@@ -1793,6 +2527,8 @@ impl<'a> PreInstance<'a> {
         let to_rm = self.instance.pred_to_clauses[pred].1.clone();
         // self.instance.unlink_pred_rhs(pred, & mut self.clauses_to_simplify) ;
         info.clauses_rmed += to_rm.len();
+        let def_clauses: Vec<_> = to_rm.iter().cloned().collect();
+        self.instance.log_def_clauses(pred, &def_clauses)?;
         self.instance
             .forget_clauses(&mut to_rm.into_iter().collect())?;
         Ok(info)
@@ -0,0 +1,267 @@
+//! Provenance log of the pre-processing.
+//!
+//! Records what the pre-processing strategies do to the instance: which predicates they create,
+//! define or remove arguments from, and which clauses they create or drop. Clauses are identified
+//! by the original clause they stem from, see `Clause::from`. Created clauses also list the
+//! clauses they were derived from: for instance, the clause a predicate was replaced in and the
+//! clause(s) its definition was extracted from. Clauses rewritten in place are logged as dropped
+//! and created again.
+//!
+//! The log is written in JSON, as an object with a single field `events`, a list of objects of
+//! the form `{ "strategy": <string>, "event": <string>, ... }`. The fields of an event depend on
+//! its kind:
+//!
+//! - `pred_created`: `pred`, the name of the new predicate, and `sig`, its signature;
+//! - `pred_forced`: `pred`, and `def`, its definition;
+//! - `args_removed`: `pred`, and `args`, the indices in the original signature of the arguments
+//!   removed;
+//! - `clause_created` and `clause_dropped`: `clause`, the clause, and `from`, an object with the
+//!   `index` of the original clause it stems from and its `name`, `null` if the original clause
+//!   is not named. Created clauses also have an `info` field describing where they come from,
+//!   and a `parents` field, the list of the clauses they were derived from. Each parent is an
+//!   object with the `clause` at the time of the derivation and its `from` field.
+
+use crate::common::*;
+
+/// A clause as logged: its text and the original clause it stems from.
+///
+/// Clause indices are not stable during the pre-processing, hence the snapshot.
+pub type LoggedClause = (String, ClsIdx);
+
+/// A pre-processing event.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// A predicate was created.
+    PredCreated {
+        /// The new predicate.
+        pred: PrdIdx,
+    },
+    /// A predicate was given a definition.
+    PredForced {
+        /// The predicate.
+        pred: PrdIdx,
+        /// Its definition.
+        def: String,
+    },
+    /// Some arguments of a predicate were removed.
+    ArgsRemoved {
+        /// The predicate.
+        pred: PrdIdx,
+        /// Arguments removed, as indices in the original signature.
+        args: Vec<VarIdx>,
+    },
+    /// A clause was created.
+    ClauseCreated {
+        /// The clause.
+        clause: String,
+        /// Original clause it stems from.
+        from: ClsIdx,
+        /// Where it comes from.
+        info: &'static str,
+        /// Clauses it was derived from.
+        parents: Vec<LoggedClause>,
+    },
+    /// A clause was dropped.
+    ClauseDropped {
+        /// The clause.
+        clause: String,
+        /// Original clause it stems from.
+        from: ClsIdx,
+    },
+}
+
+/// Provenance log.
+#[derive(Clone, Debug)]
+pub struct Provenance {
+    /// True if the log records events.
+    active: bool,
+    /// Strategy currently running, events outside of a strategy are not recorded.
+    strat: Option<&'static str>,
+    /// Events recorded, with the strategy that triggered them.
+    events: Vec<(&'static str, Event)>,
+    /// Parents of the clauses created next.
+    parents: Vec<LoggedClause>,
+    /// Clauses the definition of some predicates were extracted from.
+    ///
+    /// Filled when the clauses are removed, before the predicate is forced.
+    def_clauses: PrdHMap<Vec<LoggedClause>>,
+}
+
+impl Provenance {
+    /// Constructor.
+    pub fn new(active: bool) -> Self {
+        Provenance {
+            active,
+            strat: None,
+            events: vec![],
+            parents: vec![],
+            def_clauses: PrdHMap::new(),
+        }
+    }
+
+    /// True if the log records events.
+    pub fn is_active(&self) -> bool {
+        self.active && self.strat.is_some()
+    }
+
+    /// Sets the strategy currently running.
+    pub fn set_strat(&mut self, strat: Option<&'static str>) {
+        self.strat = strat
+    }
+
+    /// Events recorded, with the strategy that triggered them.
+    pub fn events(&self) -> &[(&'static str, Event)] {
+        &self.events
+    }
+
+    /// Parents of the clauses created next.
+    pub fn parents(&self) -> &[LoggedClause] {
+        &self.parents
+    }
+
+    /// Sets the parents of the clauses created next, returns the previous ones.
+    pub fn set_parents(&mut self, parents: Vec<LoggedClause>) -> Vec<LoggedClause> {
+        ::std::mem::replace(&mut self.parents, parents)
+    }
+
+    /// Clauses the definition of a predicate was extracted from.
+    pub fn def_clauses(&self, pred: PrdIdx) -> &[LoggedClause] {
+        self.def_clauses
+            .get(&pred)
+            .map(|clauses| clauses as &[_])
+            .unwrap_or(&[])
+    }
+
+    /// Adds some clauses the definition of a predicate is extracted from.
+    pub fn add_def_clauses(&mut self, pred: PrdIdx, clauses: Vec<LoggedClause>) {
+        self.def_clauses
+            .entry(pred)
+            .or_insert_with(Vec::new)
+            .extend(clauses)
+    }
+
+    /// Records an event, if active.
+    pub fn record(&mut self, event: Event) {
+        if let (true, Some(strat)) = (self.active, self.strat) {
+            self.events.push((strat, event))
+        }
+    }
+
+    /// Writes the log in JSON.
+    pub fn write_json<W: Write>(&self, w: &mut W, instance: &Instance) -> Res<()> {
+        let from = |from: ClsIdx| -> String {
+            format!(
+                "{{ \"index\": {}, \"name\": {} }}",
+                from,
+                instance
+                    .name_of_old_clause(from)
+                    .map(|name| json_str(name))
+                    .unwrap_or_else(|| "null".into())
+            )
+        };
+
+        let parents = |parents: &[LoggedClause]| -> String {
+            let mut res = "[".to_string();
+            for (idx, (clause, from_clause)) in parents.iter().enumerate() {
+                let sep = if idx == 0 { "" } else { ", " };
+                res.push_str(&format!(
+                    "{}{{ \"clause\": {}, \"from\": {} }}",
+                    sep,
+                    json_str(clause),
+                    from(*from_clause)
+                ))
+            }
+            res.push(']');
+            res
+        };
+
+        writeln!(w, "{{")?;
+        write!(w, "  \"events\": [")?;
+        for (count, (strat, event)) in self.events.iter().enumerate() {
+            let sep = if count == 0 { "" } else { "," };
+            write!(w, "{}\n    {{ \"strategy\": {}, ", sep, json_str(strat))?;
+            match event {
+                Event::PredCreated { pred } => {
+                    write!(
+                        w,
+                        "\"event\": \"pred_created\", \"pred\": {}, \"sig\": [",
+                        json_str(&instance[*pred].name)
+                    )?;
+                    for (idx, typ) in instance[*pred].original_sig().iter().enumerate() {
+                        let sep = if idx == 0 { "" } else { ", " };
+                        write!(w, "{}{}", sep, json_str(&typ.to_string()))?
+                    }
+                    write!(w, "]")?
+                }
+                Event::PredForced { pred, def } => write!(
+                    w,
+                    "\"event\": \"pred_forced\", \"pred\": {}, \"def\": {}",
+                    json_str(&instance[*pred].name),
+                    json_str(def)
+                )?,
+                Event::ArgsRemoved { pred, args } => {
+                    write!(
+                        w,
+                        "\"event\": \"args_removed\", \"pred\": {}, \"args\": [",
+                        json_str(&instance[*pred].name),
+                    )?;
+                    for (idx, arg) in args.iter().enumerate() {
+                        let sep = if idx == 0 { "" } else { ", " };
+                        write!(w, "{}{}", sep, arg)?
+                    }
+                    write!(w, "]")?
+                }
+                Event::ClauseCreated {
+                    clause,
+                    from: from_clause,
+                    info,
+                    parents: clause_parents,
+                } => write!(
+                    w,
+                    "\"event\": \"clause_created\", \"clause\": {}, \"from\": {}, \"info\": {}, \
+                     \"parents\": {}",
+                    json_str(clause),
+                    from(*from_clause),
+                    json_str(info),
+                    parents(clause_parents)
+                )?,
+                Event::ClauseDropped {
+                    clause,
+                    from: from_clause,
+                } => write!(
+                    w,
+                    "\"event\": \"clause_dropped\", \"clause\": {}, \"from\": {}",
+                    json_str(clause),
+                    from(*from_clause)
+                )?,
+            }
+            write!(w, " }}")?
+        }
+        if !self.events.is_empty() {
+            writeln!(w)?;
+            write!(w, "  ")?
+        }
+        writeln!(w, "]")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+}
+
+/// Quotes and escapes a string for JSON.
+pub fn json_str(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
@@ -72,7 +72,7 @@ mod ptterms;
 pub use self::ptterms::*;
 
 /// Result yielded by the parser.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Parsed {
     /// Check-sat.
     CheckSat,
@@ -82,6 +82,12 @@ pub enum Parsed {
     GetUnsatCore,
     /// Get unsat proof.
     GetProof,
+    /// Get-info.
+    GetInfo(InfoFlag),
+    /// Get-option, with the option's keyword.
+    GetOption(String),
+    /// Get-value on predicate applications, with their arguments in the original signature.
+    GetValue(Vec<(PrdIdx, VarMap<Val>)>),
     /// Exit.
     Exit,
     /// Only parsed some item(s), no query.
@@ -97,6 +103,19 @@ mylib::impl_fmt! {
     }
 }
 
+/// Flags of a get-info.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoFlag {
+    /// Name of the solver.
+    Name,
+    /// Version of the solver.
+    Version,
+    /// Reason for the last unknown result.
+    ReasonUnknown,
+    /// Statistics.
+    AllStatistics,
+}
+
 lazy_static! {
   /// Set of legal special characters in identifiers.
   static ref id_special_chars: HashSet<& 'static str> = {
@@ -774,13 +793,15 @@ impl<'cxt, 's> Parser<'cxt, 's> {
         let key = self.ident()?.1;
         self.ws_cmt();
         let val = if self.tag_opt("|") {
-            if let Some(res) = self.get_until('|', true) {
+            if let Some(res) = self.get_until('|', false) {
+                self.tag("|")?;
                 res
             } else {
                 bail!(self.error_here("could not find closing `|` opened"))
             }
         } else if self.tag_opt("\"") {
-            if let Some(res) = self.get_until('"', true) {
+            if let Some(res) = self.get_until('"', false) {
+                self.tag("\"")?;
                 res
             } else {
                 bail!(self.error_here("could not find closing `\"` opened"))
@@ -3406,8 +3427,12 @@ impl<'cxt, 's> Parser<'cxt, 's> {
             let (_, ident) = self
                 .ident()
                 .chain_err(|| "expected identifier after `:named` tag")?;
-            if let Some(idx) = idx.into_option() {
-                instance.set_old_clause_name(idx, ident.into())?
+            // An assertion can yield several clauses, they all get the name of the assertion.
+            if let Some(mut idx) = idx.into_option() {
+                while idx < instance.next_clause_index() {
+                    instance.set_old_clause_name(idx, ident.to_string())?;
+                    idx.inc()
+                }
             }
             self.ws_cmt();
             self.tag(")")?;
@@ -3438,6 +3463,110 @@ impl<'cxt, 's> Parser<'cxt, 's> {
         self.word_opt(keywords::cmd::get_proof)
     }
 
+    /// Parses a get-info.
+    fn get_info(&mut self) -> Res<Option<InfoFlag>> {
+        if !self.word_opt(keywords::cmd::get_info) {
+            return Ok(None);
+        }
+        self.ws_cmt();
+        let start_pos = self.pos();
+        self.tag(":")?;
+        let flag = match self.ident()?.1 {
+            "name" => InfoFlag::Name,
+            "version" => InfoFlag::Version,
+            "reason-unknown" => InfoFlag::ReasonUnknown,
+            "all-statistics" => InfoFlag::AllStatistics,
+            flag => {
+                self.backtrack_to(start_pos);
+                bail!(self.error_here(format!("unsupported get-info flag `:{}`", flag)))
+            }
+        };
+        Ok(Some(flag))
+    }
+
+    /// Parses a get-option.
+    fn get_option(&mut self) -> Res<Option<String>> {
+        if !self.word_opt(keywords::cmd::get_option) {
+            return Ok(None);
+        }
+        self.ws_cmt();
+        self.tag(":")?;
+        let key = self.ident()?.1;
+        Ok(Some(key.into()))
+    }
+
+    /// Parses a get-value on predicate applications.
+    ///
+    /// The arguments of the applications must be constants.
+    fn get_value(&mut self, instance: &Instance) -> Res<Option<Vec<(PrdIdx, VarMap<Val>)>>> {
+        if !self.word_opt(keywords::cmd::get_value) {
+            return Ok(None);
+        }
+        self.ws_cmt();
+        self.tag("(")?;
+        self.ws_cmt();
+
+        let (var_map, map) = (VarInfos::new(), BTreeMap::new());
+        let mut res = vec![];
+
+        while self.tag_opt("(") {
+            self.ws_cmt();
+            let (pred_pos, name) = self.ident()?;
+            let pred = if let Some(pred) = instance.preds().iter().find(|pred| pred.name == name) {
+                pred
+            } else {
+                bail!(self.error(pred_pos, format!("unknown predicate {}", conf.emph(name))))
+            };
+            self.ws_cmt();
+
+            let mut args = VarMap::with_capacity(pred.original_sig().len());
+            for typ in pred.original_sig().iter() {
+                let pos = self.pos();
+                let mut term = if let Some(term) = self.term_opt(&var_map, &map, instance)? {
+                    term
+                } else {
+                    bail!(self.error_here(format!("expected a constant of sort {}", typ)))
+                };
+                let found = term.typ();
+                if *typ != found {
+                    if let Some(nu) = typ.merge(&found) {
+                        if let Some(nu_term) = term.force_dtyp(nu) {
+                            term = nu_term
+                        }
+                    } else {
+                        bail!(self.error(
+                            pos,
+                            format!("expected an expression of sort {}, found {}", typ, found)
+                        ))
+                    }
+                }
+                if let Some(val) = term.val() {
+                    args.push(val)
+                } else {
+                    bail!(self.error(pos, "expected a constant"))
+                }
+                self.ws_cmt()
+            }
+
+            self.tag_err(
+                ")",
+                format!(
+                    "expected `)` closing this application of {}, which takes {} arguments",
+                    conf.emph(name),
+                    pred.original_sig().len()
+                ),
+            )?;
+            self.ws_cmt();
+            res.push((pred.idx, args))
+        }
+
+        self.tag(")")?;
+        if res.is_empty() {
+            bail!(self.error_here("expected at least one predicate application"))
+        }
+        Ok(Some(res))
+    }
+
     /// Parses an exit command.
     fn exit(&mut self) -> bool {
         self.word_opt(keywords::cmd::exit)
@@ -3448,6 +3577,149 @@ impl<'cxt, 's> Parser<'cxt, 's> {
         self.word_opt(keywords::cmd::reset)
     }
 
+    /// Consumes everything up to (and including) the parenthesis closing the current item.
+    fn close_item(&mut self) -> Res<()> {
+        let start_pos = self.pos();
+        let mut depth = 1;
+        while let Some(char) = self.next() {
+            match char {
+                "(" => depth += 1,
+                ")" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                "|" | "\"" => {
+                    if !self.eat_until(char.chars().next().expect("non-empty char"), true) {
+                        break;
+                    }
+                }
+                ";" => {
+                    self.move_back(1);
+                    self.ws_cmt()
+                }
+                _ => (),
+            }
+        }
+        bail!(self.error(start_pos, "could not find the `)` closing this item"))
+    }
+
+    /// Consumes a balanced s-expression.
+    fn skip_sexpr(&mut self) -> Res<()> {
+        self.ws_cmt();
+        if self.tag_opt("(") {
+            return self.close_item();
+        }
+        let start_pos = self.pos();
+        while let Some(char) = self.next() {
+            match char {
+                "(" | ")" | ";" => {
+                    self.move_back(1);
+                    break;
+                }
+                "|" | "\"" => {
+                    if !self.eat_until(char.chars().next().expect("non-empty char"), true) {
+                        bail!(self.error(start_pos, "unclosed quoted symbol or string"))
+                    }
+                }
+                _ if char.chars().all(char::is_whitespace) => {
+                    self.move_back(1);
+                    break;
+                }
+                _ => (),
+            }
+        }
+        if self.pos() == start_pos {
+            bail!(self.error_here("expected an s-expression"))
+        }
+        Ok(())
+    }
+
+    /// Parses a model, as written by [`Instance::write_model`].
+    ///
+    /// Only retrieves the definitions of the predicates of `instance` that are not defined yet,
+    /// and whose definition is a plain term. The definitions are over the **original** signature
+    /// of the predicates. Definitions of functions, of other predicates, and mutually recursive
+    /// definitions are ignored. The model can be preceeded by `sat`.
+    ///
+    /// [`Instance::write_model`]: ../instance/struct.Instance.html#method.write_model
+    /// (write_model function)
+    pub fn model(&mut self, instance: &Instance) -> Res<PrdHMap<Term>> {
+        let mut res = PrdHMap::new();
+
+        self.ws_cmt();
+        if self.word_opt("sat") {
+            self.ws_cmt()
+        }
+        self.tag("(")?;
+        self.ws_cmt();
+        self.word("model")?;
+        self.ws_cmt();
+
+        while self.tag_opt("(") {
+            self.ws_cmt();
+
+            if self.word_opt(keywords::cmd::def_fun) {
+                self.ws_cmt();
+                let (_, name) = self.ident()?;
+                self.ws_cmt();
+
+                let pred = instance
+                    .preds()
+                    .iter()
+                    .find(|pred| pred.name == name && !pred.is_defined());
+
+                if let Some(pred) = pred {
+                    let mut var_info = VarInfos::new();
+                    let mut map = BTreeMap::new();
+                    self.args(&mut var_info, &mut map)?;
+                    self.ws_cmt();
+                    self.word("Bool")?;
+                    self.ws_cmt();
+
+                    let sig_okay = var_info.len() == pred.original_sig().len()
+                        && var_info
+                            .iter()
+                            .zip(pred.original_sig().iter())
+                            .all(|(info, typ)| info.typ == *typ);
+
+                    if !sig_okay {
+                        warn!(
+                            "ignoring definition of {} in model: signature mismatch",
+                            conf.emph(name)
+                        )
+                    } else {
+                        let body_pos = self.pos();
+                        match self.term_opt(&var_info, &map, instance) {
+                            Ok(Some(ref term)) if term.typ().is_bool() => {
+                                let _ = res.insert(pred.idx, term.clone());
+                            }
+                            _ => {
+                                warn!(
+                                    "ignoring definition of {} in model: illegal body",
+                                    conf.emph(name)
+                                );
+                                // Skip the whole body, the term parser can stop anywhere in it.
+                                self.cxt.term_stack.clear();
+                                self.backtrack_to(body_pos);
+                                self.skip_sexpr()?
+                            }
+                        }
+                    }
+                }
+            }
+
+            self.close_item()?;
+            self.ws_cmt()
+        }
+
+        self.tag(")")?;
+        self.ws_cmt();
+
+        Ok(res)
+    }
+
     /// Parses items, returns true if it found a check-sat.
     pub fn parse(mut self, instance: &mut Instance) -> Res<Parsed> {
         self.ws_cmt();
@@ -3490,6 +3762,12 @@ impl<'cxt, 's> Parser<'cxt, 's> {
                 Parsed::GetUnsatCore
             } else if self.get_proof() {
                 Parsed::GetProof
+            } else if let Some(flag) = self.get_info()? {
+                Parsed::GetInfo(flag)
+            } else if let Some(key) = self.get_option()? {
+                Parsed::GetOption(key)
+            } else if let Some(apps) = self.get_value(instance)? {
+                Parsed::GetValue(apps)
             } else if self.exit() {
                 Parsed::Exit
             } else if self.reset() {
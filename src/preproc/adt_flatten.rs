@@ -0,0 +1,237 @@
+//! Flattening of non-recursive datatypes.
+
+use crate::{
+    common::*,
+    preproc::{PreInstance, RedStrat},
+};
+
+/// Maximum number of arguments a predicate argument can be flattened into.
+const MAX_FIELDS: usize = 32;
+
+/// Replaces predicate arguments of non-recursive datatypes by their fields.
+///
+/// An argument `v` of a datatype with constructors `C_1`, ..., `C_n` is replaced by
+///
+/// - an integer *tag* in `[0, n[`, which is `i` when `v` is `C_(i+1)`, if `n > 1`, followed by
+/// - the fields of all the constructors, in order.
+///
+/// So records become their fields, and enumerations become integers. Fields that are themselves
+/// of a non-recursive datatype are flattened recursively. The range of the tags is the
+/// strengthening term of the new predicate.
+///
+/// The predicate is replaced by a new predicate `<pred>_flat` with the flattened signature, and
+/// its applications are rewritten using testers and selectors. Its definition in the final model
+/// is the definition of `<pred>_flat`, applied to the testers and selectors of the original
+/// arguments.
+///
+/// Predicates that are defined or have a strengthening term are ignored.
+///
+/// # Examples
+///
+/// ```
+/// # use hoice::{ common::*, parse, preproc::{ PreInstance, RedStrat, AdtFlatten } };
+/// let mut instance = parse::instance("
+///   (declare-datatypes ( (Pos 0) ) ( ( (pos (x Int) (y Int)) ) ))
+///   (declare-fun p ( Pos ) Bool)
+///   (assert
+///     (forall ( (n Int) ) (=> (= n 0) (p (pos n n))))
+///   )
+///   (assert
+///     (forall ( (s Pos) ) (=> (and (p s) (< (x s) 10)) (p (pos (+ (x s) 1) (y s)))))
+///   )
+///   (assert
+///     (forall ( (s Pos) ) (=> (and (p s) (< (x s) (y s))) false))
+///   )
+/// ");
+///
+/// let mut adt_flatten = AdtFlatten::new(& instance);
+/// let mut instance = PreInstance::new(& mut instance).unwrap();
+/// let info = adt_flatten.apply(& mut instance).unwrap();
+/// assert_eq! { info.preds, 1 }
+///
+/// let (p, p_flat): (PrdIdx, PrdIdx) = (0.into(), 1.into());
+/// assert! { instance[p].is_defined() }
+/// let sig: VarMap<Typ> = vec![ typ::int(), typ::int() ].into();
+/// assert_eq! { instance[p_flat].sig(), &sig }
+/// ```
+pub struct AdtFlatten;
+
+impl AdtFlatten {
+    /// True if a type mentions a datatype.
+    fn has_dtyp(typ: &Typ) -> bool {
+        if let Some((src, tgt)) = typ.array_inspect() {
+            Self::has_dtyp(src) || Self::has_dtyp(tgt)
+        } else {
+            typ.dtyp_inspect().is_some()
+        }
+    }
+
+    /// Fields of the constructors of a non-recursive datatype.
+    ///
+    /// Returns `None` if the type is not a datatype or is recursive. `stack` contains the
+    /// datatypes currently being inspected.
+    fn fields(typ: &Typ, stack: &mut Vec<String>) -> Option<Vec<(String, Vec<(String, Typ)>)>> {
+        let (dtyp, prms) = typ.dtyp_inspect()?;
+        if stack.contains(&dtyp.name) {
+            return None;
+        }
+        stack.push(dtyp.name.clone());
+
+        let mut res = Vec::with_capacity(dtyp.news.len());
+        let mut okay = true;
+        'news: for (constructor, args) in &dtyp.news {
+            let mut fields = Vec::with_capacity(args.len());
+            for (selector, ptyp) in args {
+                let field_typ = if let Ok(field_typ) = ptyp.to_type(Some(prms)) {
+                    field_typ
+                } else {
+                    okay = false;
+                    break 'news;
+                };
+                let legal = if field_typ.dtyp_inspect().is_some() {
+                    Self::fields(&field_typ, stack).is_some()
+                } else {
+                    !Self::has_dtyp(&field_typ)
+                };
+                if !legal {
+                    okay = false;
+                    break 'news;
+                }
+                fields.push((selector.clone(), field_typ))
+            }
+            res.push((constructor.clone(), fields))
+        }
+
+        stack.pop();
+        if okay {
+            Some(res)
+        } else {
+            None
+        }
+    }
+
+    /// Number of arguments a value of a non-recursive datatype is flattened into.
+    fn flat_len(typ: &Typ) -> usize {
+        let news = Self::fields(typ, &mut vec![]).unwrap_or_else(Vec::new);
+        let mut len = if news.len() > 1 { 1 } else { 0 };
+        for (_, fields) in news {
+            for (_, field_typ) in fields {
+                len += if field_typ.dtyp_inspect().is_some() {
+                    Self::flat_len(&field_typ)
+                } else {
+                    1
+                }
+            }
+        }
+        len
+    }
+
+    /// Flattens a term of a non-recursive datatype.
+    ///
+    /// Pushes the arguments it is flattened into on `args`, their types on `sig`, and the range
+    /// constraints of the tags on `ranges`.
+    fn flatten_term(
+        term: Term,
+        typ: &Typ,
+        sig: &mut Sig,
+        args: &mut VarMap<Term>,
+        ranges: &mut Vec<Term>,
+    ) {
+        let news = Self::fields(typ, &mut vec![]).unwrap_or_else(Vec::new);
+
+        if news.len() > 1 {
+            let mut tag = term::int(news.len() - 1);
+            for (idx, (constructor, _)) in news.iter().enumerate().rev().skip(1) {
+                tag = term::ite(
+                    term::dtyp_tst(constructor.clone(), term.clone()),
+                    term::int(idx),
+                    tag,
+                )
+            }
+            let tag_var = term::var(sig.next_index(), typ::int());
+            ranges.push(term::ge(tag_var.clone(), term::int_zero()));
+            ranges.push(term::lt(tag_var, term::int(news.len())));
+            sig.push(typ::int());
+            args.push(tag)
+        }
+
+        for (_, fields) in news {
+            for (selector, field_typ) in fields {
+                let field = term::dtyp_slc(field_typ.clone(), selector, term.clone());
+                if field_typ.dtyp_inspect().is_some() {
+                    Self::flatten_term(field, &field_typ, sig, args, ranges)
+                } else {
+                    args.push(field);
+                    sig.push(field_typ)
+                }
+            }
+        }
+    }
+
+    /// Flattened signature of a predicate, the arguments of the flattened predicate in terms of
+    /// the variables of the predicate, and the range constraints of the tags.
+    ///
+    /// Returns `None` if no argument can be flattened.
+    fn flatten_sig(sig: &Sig) -> Option<(Sig, VarMap<Term>, Vec<Term>)> {
+        let mut nu_sig = VarMap::with_capacity(sig.len());
+        let mut args = VarMap::with_capacity(sig.len());
+        let mut ranges = vec![];
+        let mut flattened = false;
+
+        for (var, typ) in sig.index_iter() {
+            let v = term::var(var, typ.clone());
+            if Self::fields(typ, &mut vec![]).is_some() && Self::flat_len(typ) <= MAX_FIELDS {
+                flattened = true;
+                Self::flatten_term(v, typ, &mut nu_sig, &mut args, &mut ranges)
+            } else {
+                nu_sig.push(typ.clone());
+                args.push(v)
+            }
+        }
+
+        if flattened {
+            Some((nu_sig, args, ranges))
+        } else {
+            None
+        }
+    }
+}
+
+impl RedStrat for AdtFlatten {
+    fn name(&self) -> &'static str {
+        "adt_flatten"
+    }
+
+    fn new(_: &Instance) -> Self {
+        AdtFlatten
+    }
+
+    fn apply(&mut self, instance: &mut PreInstance) -> Res<RedInfo> {
+        let mut info = RedInfo::new();
+
+        // Predicates created in a split instance do not exist in the original instance.
+        if instance.split().is_some() || !dtyp::one_or_more()? {
+            return Ok(info);
+        }
+
+        for pred in instance.pred_indices() {
+            if instance[pred].is_defined() || instance[pred].strength().is_some() {
+                continue;
+            }
+            conf.check_timeout()?;
+
+            if let Some((sig, args, ranges)) = Self::flatten_sig(instance[pred].sig()) {
+                log! { @verb "flattening the datatype arguments of {}", instance[pred] }
+                let (flat_info, flat) =
+                    instance.flatten_pred(pred, "flat", sig, var_to::terms::new(args))?;
+                if !ranges.is_empty() {
+                    instance.set_strength(flat, term::and(ranges))?
+                }
+                info.preds += 1;
+                info += flat_info
+            }
+        }
+
+        Ok(info)
+    }
+}
@@ -0,0 +1,247 @@
+//! Constant and equality propagation across predicate arguments.
+
+use crate::{
+    common::*,
+    preproc::{PreInstance, RedStrat},
+};
+
+/// Maximum arity of the predicates for which equalities between arguments are considered.
+const MAX_EQ_ARITY: usize = 32;
+
+/// Removes predicate arguments that are always equal to a constant or to another argument.
+///
+/// Infers argument invariants of the form `v_i = c`, `v_j = v_i` and `v_j = v_i + c` with `i < j`.
+/// Candidate invariants are guessed from one of the rhs applications of each predicate, and the
+/// ones that are not inductive are dropped until a fixpoint is reached, see
+/// [`inductive_invariants`]. An argument `v_j` with an invariant is removed from the signature of
+/// the predicate, and the invariants are added for each lhs application of the predicate.
+///
+/// The invariants are conjoined to the definition of the predicate when extending the model, and
+/// the removed arguments are restored from their invariant when printing applications of the
+/// predicate.
+///
+/// Predicates that are defined, have a strengthening term or appear in the definition of a
+/// predicate are ignored.
+///
+/// # Examples
+///
+/// ```
+/// # use hoice::{ common::PrdIdx, parse, preproc::{ PreInstance, RedStrat, ArgProp } };
+/// let mut instance = parse::instance("
+///   (declare-fun p ( Int Int Int ) Bool)
+///   (assert
+///     (forall ( (n Int) ) (=> (= n 0) (p n 7 (+ n 1))))
+///   )
+///   (assert
+///     (forall ( (n Int) (m Int) (k Int) ) (=> (and (p n m k) (< n 10)) (p (+ n 1) m (+ k 1))))
+///   )
+///   (assert
+///     (forall ( (n Int) (m Int) (k Int) ) (=> (and (p n m k) (> n k)) false))
+///   )
+/// ");
+///
+/// let mut arg_prop = ArgProp::new(& instance);
+/// let mut instance = PreInstance::new(& mut instance).unwrap();
+/// let info = arg_prop.apply(& mut instance).unwrap();
+/// assert_eq! { info.args_rmed, 2 }
+///
+/// let p: PrdIdx = 0.into();
+/// assert_eq! { instance[p].sig().len(), 1 }
+/// assert_eq! { instance[p].invariants().len(), 2 }
+/// ```
+///
+/// [`inductive_invariants`]: ../../instance/struct.PreInstance.html#method.inductive_invariants
+/// (inductive_invariants function)
+pub struct ArgProp {
+    /// Predicates already analyzed, with the clauses they appeared in the rhs of.
+    ///
+    /// A predicate is only analyzed again if these clauses change.
+    known: PrdHMap<Vec<ClauseContent>>,
+}
+
+/// Lhs terms, lhs applications and rhs of a clause, used to detect changes in a clause.
+type ClauseContent = (TermSet, PredApps, Option<PredApp>);
+
+impl ArgProp {
+    /// True if `pred` appears in the definition of a predicate.
+    fn in_defs(instance: &Instance, pred: PrdIdx) -> bool {
+        instance.preds().iter().any(|info| {
+            info.def()
+                .map(|def| def.preds().contains(&pred))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Rhs arguments to guess candidate invariants from.
+    ///
+    /// Favors positive clauses, which usually give the initial values of the arguments.
+    fn guess_args(instance: &Instance, pred: PrdIdx) -> Option<VarTerms> {
+        let mut res = None;
+        for clause in instance.rhs_clauses_of(pred) {
+            let clause = &instance[*clause];
+            if let Some((_, args)) = clause.rhs() {
+                if clause.lhs_preds().is_empty() {
+                    return Some(args.clone());
+                } else if res.is_none() {
+                    res = Some(args.clone())
+                }
+            }
+        }
+        res
+    }
+
+    /// Candidate invariants for `pred`, as pairs of an argument and its candidate value.
+    fn candidates(instance: &Instance, pred: PrdIdx, args: &VarTerms) -> Vec<(VarIdx, Term)> {
+        let sig = instance[pred].sig();
+        let mut cands = vec![];
+
+        for (var, arg) in args.index_iter() {
+            if let Some(val) = arg.val() {
+                if val.is_known() {
+                    cands.push((var, term::cst(val)))
+                }
+            }
+        }
+
+        if sig.len() > MAX_EQ_ARITY {
+            return cands;
+        }
+
+        for (j, arg_j) in args.index_iter() {
+            for (i, arg_i) in args.index_iter() {
+                if i >= j {
+                    break;
+                }
+                if sig[i] != sig[j] {
+                    continue;
+                }
+                let var_i = term::var(i, sig[i].clone());
+                let def = if sig[i].is_arith() {
+                    match term::sub(vec![arg_j.clone(), arg_i.clone()]).val() {
+                        Some(ref offset) if offset.is_zero() => var_i,
+                        Some(offset) => term::add(vec![var_i, term::cst(offset)]),
+                        None => continue,
+                    }
+                } else if arg_i == arg_j {
+                    var_i
+                } else {
+                    continue;
+                };
+                cands.push((j, def))
+            }
+        }
+
+        cands
+    }
+
+    /// Definitions of the arguments to remove, given some candidates that are invariants.
+    ///
+    /// Candidates only mention arguments lower than the one they define. Definitions are resolved
+    /// so that they only mention arguments that are not removed.
+    fn arg_defs(cands: Vec<(VarIdx, Term)>) -> VarHMap<Term> {
+        let mut defs: VarHMap<Term> = VarHMap::new();
+        for (var, def) in cands {
+            defs.entry(var).or_insert(def);
+        }
+        let mut vars: Vec<VarIdx> = defs.keys().cloned().collect();
+        vars.sort_unstable();
+
+        let mut res = VarHMap::new();
+        for var in vars {
+            let (def, _) = defs[&var].subst(&res);
+            res.insert(var, def);
+        }
+        res
+    }
+
+    /// Remembers the rhs clauses of a predicate, returns `false` if they did not change since
+    /// the last time.
+    fn update_known(&mut self, instance: &Instance, pred: PrdIdx) -> bool {
+        let mut clauses: Vec<_> = instance.rhs_clauses_of(pred).iter().cloned().collect();
+        clauses.sort_unstable();
+        let content: Vec<ClauseContent> = clauses
+            .into_iter()
+            .map(|clause| {
+                let clause = &instance[clause];
+                (
+                    clause.lhs_terms().clone(),
+                    clause.lhs_preds().clone(),
+                    clause.rhs().map(|(pred, args)| (pred, args.clone())),
+                )
+            })
+            .collect();
+        self.known.insert(pred, content.clone()) != Some(content)
+    }
+}
+
+impl RedStrat for ArgProp {
+    fn name(&self) -> &'static str {
+        "arg_prop"
+    }
+
+    fn new(_: &Instance) -> Self {
+        ArgProp {
+            known: PrdHMap::new(),
+        }
+    }
+
+    fn apply(&mut self, instance: &mut PreInstance) -> Res<RedInfo> {
+        // Invariants found in a split instance are unknown to the original instance.
+        if instance.split().is_some() {
+            return Ok(RedInfo::new());
+        }
+
+        let mut candidates = PrdHMap::new();
+        let mut cand_defs = PrdHMap::new();
+        for pred in instance.pred_indices() {
+            if instance[pred].is_defined()
+                || instance[pred].strength().is_some()
+                || Self::in_defs(instance, pred)
+            {
+                continue;
+            }
+            if !self.update_known(instance, pred) {
+                continue;
+            }
+            if let Some(args) = Self::guess_args(instance, pred) {
+                let cands = Self::candidates(instance, pred, &args);
+                if !cands.is_empty() {
+                    let sig = instance[pred].sig();
+                    let invs = cands
+                        .iter()
+                        .map(|(var, def)| term::eq(term::var(*var, sig[*var].clone()), def.clone()))
+                        .collect();
+                    candidates.insert(pred, invs);
+                    cand_defs.insert(pred, cands);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return Ok(RedInfo::new());
+        }
+
+        let mut to_rm = PrdHMap::new();
+        for (pred, invariants) in instance.inductive_invariants(candidates.clone())? {
+            let cands = cand_defs.remove(&pred).unwrap_or_else(Vec::new);
+            let cands = cands
+                .into_iter()
+                .zip(candidates[&pred].iter())
+                .filter_map(|(cand, inv)| {
+                    if invariants.contains(inv) {
+                        Some(cand)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            let defs = Self::arg_defs(cands);
+            if !defs.is_empty() {
+                log! { @verb "removing {} argument(s) of {}", defs.len(), instance[pred] }
+                to_rm.insert(pred, (invariants, defs));
+            }
+        }
+
+        instance.rm_args_by_invariants(to_rm)
+    }
+}
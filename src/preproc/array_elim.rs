@@ -0,0 +1,363 @@
+//! Elimination of arrays only accessed at fixed indices.
+
+use crate::{
+    common::*,
+    preproc::{PreInstance, RedStrat},
+};
+
+/// Maximum number of arguments an array argument can be replaced by.
+const MAX_CELLS: usize = 32;
+
+/// Replaces predicate arguments of array type by the cells of the array that are accessed.
+///
+/// An array argument `a` of a predicate `p` can be eliminated if, in all the clauses where `p`
+/// appears in the lhs as `(p x A)` with `A` a variable,
+///
+/// - `A` only appears as `(select A i)`, where `i` is a constant or one of the `x`, or
+/// - in the rhs as `(p y B)` with `B` a sequence of `store`s over `A`, and the indices read in `B`
+///   are read in `A` or stored in `B`.
+///
+/// The array argument is then replaced by one argument per index, the value of the array at this
+/// index. The model for `p` reads these cells with `select`s on the original array argument, and
+/// samples of the new predicate are turned back into arrays as `store`s over constant arrays.
+///
+/// The predicate is replaced by a new predicate `<pred>_cells`, see [`flatten_pred`]. Predicates
+/// that are defined or have a strengthening term are ignored.
+///
+/// # Examples
+///
+/// ```
+/// # use hoice::{ common::*, parse, preproc::{ PreInstance, RedStrat, ArrayElim } };
+/// let mut instance = parse::instance("
+///   (declare-fun p ( Int (Array Int Int) ) Bool)
+///   (assert
+///     (forall ( (n Int) (a (Array Int Int)) ) (=> (and (= n 0) (= (select a 0) 0)) (p n a)))
+///   )
+///   (assert
+///     (forall ( (n Int) (a (Array Int Int)) )
+///       (=> (and (p n a) (< n 10)) (p (+ n 1) (store a 0 (+ (select a 0) 1))))
+///     )
+///   )
+///   (assert
+///     (forall ( (n Int) (a (Array Int Int)) ) (=> (and (p n a) (> (select a 0) n)) false))
+///   )
+/// ");
+///
+/// let mut array_elim = ArrayElim::new(& instance);
+/// let mut instance = PreInstance::new(& mut instance).unwrap();
+/// let info = array_elim.apply(& mut instance).unwrap();
+/// assert_eq! { info.preds, 1 }
+///
+/// let (p, p_cells): (PrdIdx, PrdIdx) = (0.into(), 1.into());
+/// assert! { instance[p].is_defined() }
+/// let sig: VarMap<Typ> = vec![ typ::int(), typ::int() ].into();
+/// assert_eq! { instance[p_cells].sig(), &sig }
+/// ```
+///
+/// [`flatten_pred`]: ../../instance/struct.PreInstance.html#method.flatten_pred
+/// (flatten_pred function)
+pub struct ArrayElim;
+
+impl ArrayElim {
+    /// Array arguments of a predicate that can be eliminated, if any.
+    fn array_args(sig: &Sig) -> VarHMap<Vec<Term>> {
+        let mut res = VarHMap::new();
+        for (var, typ) in sig.index_iter() {
+            if let Some((src, tgt)) = typ.array_inspect() {
+                if src.array_inspect().is_none() && tgt.array_inspect().is_none() {
+                    res.insert(var, vec![]);
+                }
+            }
+        }
+        res
+    }
+
+    /// Collects the indices at which some array variables are read in a term.
+    ///
+    /// Inserts in `bad` the arrays that appear in the term other than as the array of a `select`.
+    fn reads(
+        term: &Term,
+        arrays: &VarHMap<(VarIdx, VarTerms)>,
+        reads: &mut VarHMap<Vec<Term>>,
+        bad: &mut VarSet,
+    ) {
+        let mut to_do = vec![term];
+
+        while let Some(term) = to_do.pop() {
+            if let Some((Op::Select, args)) = term.app_inspect() {
+                if let Some(array) = args[0].var_idx() {
+                    if arrays.contains_key(&array) {
+                        reads
+                            .entry(array)
+                            .or_insert_with(Vec::new)
+                            .push(args[1].clone());
+                        to_do.push(&args[1]);
+                        continue;
+                    }
+                }
+            }
+
+            match term.get() {
+                RTerm::Var(_, var) => {
+                    if arrays.contains_key(var) {
+                        bad.insert(*var);
+                    }
+                }
+                RTerm::Cst(_) => (),
+
+                RTerm::App { args, .. } | RTerm::Fun { args, .. } | RTerm::DTypNew { args, .. } => {
+                    for arg in args {
+                        to_do.push(arg)
+                    }
+                }
+
+                RTerm::CArray { term, .. }
+                | RTerm::DTypSlc { term, .. }
+                | RTerm::DTypTst { term, .. } => to_do.push(term),
+            }
+        }
+    }
+
+    /// Instantiates some index specifications.
+    fn instantiate(specs: &[Term], args: &VarTerms) -> Vec<Term> {
+        specs
+            .iter()
+            .filter_map(|spec| spec.subst_total(args).map(|(idx, _)| idx))
+            .collect()
+    }
+
+    /// Index specification for an index read in a clause, if any.
+    ///
+    /// The index is either a constant, or one of the non-array arguments `args` of the
+    /// predicate, in which case the specification is the corresponding variable of the predicate.
+    fn spec_of(idx: &Term, sig: &Sig, args: &VarTerms) -> Option<Term> {
+        if idx.val().is_some() {
+            return Some(idx.clone());
+        }
+        let typ = idx.typ();
+        for (var, arg) in args.index_iter() {
+            if arg == idx && sig[var] == typ {
+                return Some(term::var(var, typ));
+            }
+        }
+        None
+    }
+
+    /// Checks the accesses to the array arguments in the clauses where `pred` appears in the lhs.
+    ///
+    /// Removes the arguments of `specs` that cannot be eliminated, and adds the index
+    /// specifications the others need. Returns true if `specs` changed.
+    fn check_clauses(instance: &Instance, pred: PrdIdx, specs: &mut VarHMap<Vec<Term>>) -> bool {
+        let sig = instance[pred].sig();
+        let mut changed = false;
+
+        for clause in instance.lhs_clauses_of(pred) {
+            let clause = &instance[*clause];
+
+            // Array variables passed to `pred` in the lhs, with their position and the arguments
+            // of the application.
+            let mut arrays: VarHMap<(VarIdx, VarTerms)> = VarHMap::new();
+            let mut illegal = VarSet::new();
+            if let Some(argss) = clause.lhs_preds().get(&pred) {
+                for args in argss {
+                    for var in specs.keys() {
+                        if let Some(array) = args[*var].var_idx() {
+                            if let Some((prev, _)) = arrays.insert(array, (*var, args.clone())) {
+                                illegal.insert(prev);
+                                illegal.insert(*var);
+                            }
+                        } else {
+                            illegal.insert(*var);
+                        }
+                    }
+                }
+            }
+            if !illegal.is_empty() {
+                for var in illegal {
+                    specs.remove(&var);
+                }
+                return true;
+            }
+
+            let mut reads = VarHMap::new();
+            let mut bad = VarSet::new();
+
+            for term in clause.lhs_terms() {
+                Self::reads(term, &arrays, &mut reads, &mut bad)
+            }
+            for (p, argss) in clause.lhs_preds() {
+                for args in argss {
+                    for (var, arg) in args.index_iter() {
+                        if *p == pred {
+                            let own_arg = arg
+                                .var_idx()
+                                .and_then(|array| arrays.get(&array))
+                                .map(|(pos, pos_args)| *pos == var && pos_args == args)
+                                .unwrap_or(false);
+                            if own_arg {
+                                continue;
+                            }
+                        }
+                        Self::reads(arg, &arrays, &mut reads, &mut bad)
+                    }
+                }
+            }
+
+            if let Some((p, args)) = clause.rhs() {
+                for (var, arg) in args.index_iter() {
+                    let var_specs = match specs.get(&var) {
+                        Some(var_specs) if p == pred => var_specs,
+                        _ => {
+                            Self::reads(arg, &arrays, &mut reads, &mut bad);
+                            continue;
+                        }
+                    };
+
+                    let mut base = arg;
+                    let mut stored = vec![];
+                    while let Some((Op::Store, store_args)) = base.app_inspect() {
+                        Self::reads(&store_args[1], &arrays, &mut reads, &mut bad);
+                        Self::reads(&store_args[2], &arrays, &mut reads, &mut bad);
+                        stored.push(store_args[1].clone());
+                        base = &store_args[0]
+                    }
+
+                    match base.var_idx() {
+                        Some(array) if arrays.contains_key(&array) => {
+                            let array_reads = reads.entry(array).or_insert_with(Vec::new);
+                            for idx in Self::instantiate(var_specs, args) {
+                                if !stored.contains(&idx) {
+                                    array_reads.push(idx)
+                                }
+                            }
+                        }
+                        _ => Self::reads(base, &arrays, &mut reads, &mut bad),
+                    }
+                }
+            }
+
+            let no_reads = vec![];
+            for (array, (var, args)) in &arrays {
+                if bad.contains(array) {
+                    specs.remove(var);
+                    changed = true;
+                    continue;
+                }
+                let known = if let Some(var_specs) = specs.get(var) {
+                    Self::instantiate(var_specs, args)
+                } else {
+                    continue;
+                };
+                let mut nu_specs = vec![];
+                let mut okay = true;
+                for idx in reads.get(array).unwrap_or(&no_reads) {
+                    if known.contains(idx) {
+                        continue;
+                    }
+                    match Self::spec_of(idx, sig, args) {
+                        Some(spec) => {
+                            if !nu_specs.contains(&spec) {
+                                nu_specs.push(spec)
+                            }
+                        }
+                        None => {
+                            okay = false;
+                            break;
+                        }
+                    }
+                }
+
+                if !okay || known.len() + nu_specs.len() > MAX_CELLS {
+                    specs.remove(var);
+                    changed = true
+                } else if !nu_specs.is_empty() {
+                    if let Some(var_specs) = specs.get_mut(var) {
+                        var_specs.extend(nu_specs)
+                    }
+                    changed = true
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// Signature of the predicate replacing `pred`, and its arguments in terms of the variables
+    /// of `pred`.
+    ///
+    /// Returns `None` if no array argument can be eliminated.
+    fn eliminate(instance: &Instance, pred: PrdIdx) -> Option<(Sig, VarMap<Term>)> {
+        let sig = instance[pred].sig();
+        let mut specs = Self::array_args(sig);
+        if specs.is_empty() {
+            return None;
+        }
+
+        // Specifications only grow, until they are illegal or too many.
+        while Self::check_clauses(instance, pred, &mut specs) {}
+        if specs.is_empty() {
+            return None;
+        }
+
+        let mut nu_sig = VarMap::with_capacity(sig.len());
+        let mut args = VarMap::with_capacity(sig.len());
+        for (var, typ) in sig.index_iter() {
+            if !specs.contains_key(&var) {
+                nu_sig.push(typ.clone());
+                args.push(term::var(var, typ.clone()))
+            }
+        }
+
+        let mut arrays: Vec<VarIdx> = specs.keys().cloned().collect();
+        arrays.sort_unstable();
+        for var in arrays {
+            let tgt = if let Some((_, tgt)) = sig[var].array_inspect() {
+                tgt.clone()
+            } else {
+                continue;
+            };
+            for spec in &specs[&var] {
+                nu_sig.push(tgt.clone());
+                args.push(term::select(term::var(var, sig[var].clone()), spec.clone()))
+            }
+        }
+
+        Some((nu_sig, args))
+    }
+}
+
+impl RedStrat for ArrayElim {
+    fn name(&self) -> &'static str {
+        "array_elim"
+    }
+
+    fn new(_: &Instance) -> Self {
+        ArrayElim
+    }
+
+    fn apply(&mut self, instance: &mut PreInstance) -> Res<RedInfo> {
+        let mut info = RedInfo::new();
+
+        // Predicates created in a split instance do not exist in the original instance.
+        if instance.split().is_some() {
+            return Ok(info);
+        }
+
+        for pred in instance.pred_indices() {
+            if instance[pred].is_defined() || instance[pred].strength().is_some() {
+                continue;
+            }
+            conf.check_timeout()?;
+
+            if let Some((sig, args)) = Self::eliminate(instance, pred) {
+                log! { @verb "eliminating the array arguments of {}", instance[pred] }
+                let (elim_info, _) =
+                    instance.flatten_pred(pred, "cells", sig, var_to::terms::new(args))?;
+                info.preds += 1;
+                info += elim_info
+            }
+        }
+
+        Ok(info)
+    }
+}
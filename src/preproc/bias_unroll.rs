@@ -583,6 +583,8 @@ impl BiasedUnroll {
                 }
             }
 
+            let parents = instance.logged_clauses(Some(rhs_clause))?;
+            let prev_parents = instance.set_clause_parents(parents);
             for mut clause in nu_clauses {
                 clause.from_unrolling = true;
                 if let Some(index) = instance.push_clause(clause)? {
@@ -591,6 +593,7 @@ impl BiasedUnroll {
                     info.clauses_added += 1
                 }
             }
+            instance.set_clause_parents(prev_parents);
         }
 
         Ok(info)
@@ -833,6 +836,8 @@ impl BiasedUnroll {
 
             self.generate_neg_clause_for(pred, instance, lhs_clause, &mut nu_clauses)?;
 
+            let parents = instance.logged_clauses(Some(lhs_clause))?;
+            let prev_parents = instance.set_clause_parents(parents);
             for mut clause in nu_clauses {
                 log! { @6
                     "new clause: {}",
@@ -846,6 +851,7 @@ impl BiasedUnroll {
                     info.clauses_added += 1
                 }
             }
+            instance.set_clause_parents(prev_parents);
         }
 
         Ok(info)
@@ -0,0 +1,146 @@
+//! Case-splitting on boolean arguments.
+
+use crate::{
+    common::*,
+    preproc::{PreInstance, RedStrat},
+};
+
+/// Splits predicates on their boolean arguments.
+///
+/// A predicate `p(b, x, y)` such that all its rhs applications have a constant for `b` is
+/// replaced by two predicates `p_true(x, y)` and `p_false(x, y)`. Clauses where `p` appears in
+/// the lhs with a non-constant `b` are case-split on `b`. The definition of `p` in the final model
+/// is `(ite b p_true(x, y) p_false(x, y))`, where the definitions of `p_true` and `p_false` are
+/// inlined.
+///
+/// Case-splitting clauses can make the instance explode, so this strategy has a budget: the total
+/// number of clauses and predicates it can create is the size of the instance it was constructed
+/// with.
+///
+/// # Examples
+///
+/// ```
+/// # use hoice::{ common::PrdIdx, parse, preproc::{ PreInstance, RedStrat, BoolSplit } };
+/// let mut instance = parse::instance("
+///   (declare-fun p ( Bool Int ) Bool)
+///   (assert
+///     (forall ( (n Int) ) (=> (= n 0) (p true n)))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (and (p true n) (< n 10)) (p false (+ n 1))))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (and (p false n) (< n 10)) (p true (+ n 1))))
+///   )
+///   (assert
+///     (forall ( (b Bool) (n Int) ) (=> (and (p b n) (< n 0)) false))
+///   )
+/// ");
+///
+/// let mut bool_split = BoolSplit::new(& instance);
+/// let mut instance = PreInstance::new(& mut instance).unwrap();
+/// let info = bool_split.apply(& mut instance).unwrap();
+/// assert_eq! { info.preds, 1 }
+///
+/// let p: PrdIdx = 0.into();
+/// assert! { instance[p].is_defined() }
+/// assert_eq! { instance.preds().len(), 3 }
+/// ```
+pub struct BoolSplit {
+    /// Number of clauses and predicates the strategy can still create.
+    budget: usize,
+}
+
+impl BoolSplit {
+    /// True if all the rhs applications of `pred` have a constant for `var`.
+    fn rhs_constant(instance: &Instance, pred: PrdIdx, var: VarIdx) -> bool {
+        instance.rhs_clauses_of(pred).iter().all(|clause| {
+            if let Some((_, args)) = instance[*clause].rhs() {
+                args[var].bool().is_some()
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Number of clauses created by case-splitting when splitting `pred` on `var`.
+    ///
+    /// Returns `None` if it is too big to be computed.
+    fn cost(instance: &Instance, pred: PrdIdx, var: VarIdx) -> Option<usize> {
+        let mut cost: usize = 0;
+        for clause in instance.lhs_clauses_of(pred) {
+            let mut conds = TermSet::new();
+            if let Some(argss) = instance[*clause].lhs_preds().get(&pred) {
+                for args in argss {
+                    if args[var].bool().is_none() {
+                        conds.insert(args[var].clone());
+                    }
+                }
+            }
+            if let Some((rhs_pred, args)) = instance[*clause].rhs() {
+                if rhs_pred == pred && args[var].bool().is_none() {
+                    conds.insert(args[var].clone());
+                }
+            }
+            if conds.len() >= 16 {
+                return None;
+            }
+            cost = cost.checked_add((1 << conds.len()) - 1)?
+        }
+        Some(cost)
+    }
+}
+
+impl RedStrat for BoolSplit {
+    fn name(&self) -> &'static str {
+        "bool_split"
+    }
+
+    fn new(instance: &Instance) -> Self {
+        BoolSplit {
+            budget: instance.clauses().len() + instance.preds().len(),
+        }
+    }
+
+    fn apply(&mut self, instance: &mut PreInstance) -> Res<RedInfo> {
+        let mut info = RedInfo::new();
+
+        // Predicates created in a split instance do not exist in the original instance.
+        if instance.split().is_some() {
+            return Ok(info);
+        }
+
+        for pred in instance.pred_indices() {
+            if instance[pred].is_defined() || instance[pred].strength().is_some() {
+                continue;
+            }
+            conf.check_timeout()?;
+
+            let mut best: Option<(VarIdx, usize)> = None;
+            for (var, typ) in instance[pred].sig.index_iter() {
+                if !typ.is_bool() || !Self::rhs_constant(instance, pred, var) {
+                    continue;
+                }
+                // One more for the predicate created.
+                let cost = if let Some(cost) = Self::cost(instance, pred, var) {
+                    cost + 1
+                } else {
+                    continue;
+                };
+                if cost <= self.budget && best.map(|(_, best)| cost < best).unwrap_or(true) {
+                    best = Some((var, cost))
+                }
+            }
+
+            if let Some((var, cost)) = best {
+                log! { @verb "splitting {} on v_{}", instance[pred], var }
+                self.budget -= cost;
+                let (split_info, _, _) = instance.split_pred_on_bool(pred, var)?;
+                info.preds += 1;
+                info += split_info
+            }
+        }
+
+        Ok(info)
+    }
+}
@@ -73,8 +73,15 @@ impl Graph {
     }
 
     /// Clears itself and sets everything up for the input instance.
+    ///
+    /// Reallocates the graph if the number of predicates changed since its creation, since some
+    /// strategies create predicates.
     pub fn setup(&mut self, instance: &Instance) {
-        self.reset();
+        if self.pos.len() != instance.preds().len() {
+            *self = Self::new(instance)
+        } else {
+            self.reset()
+        }
 
         for clause in instance.clauses() {
             if let Some((tgt, _)) = clause.rhs() {
@@ -94,6 +101,47 @@ impl Graph {
         }
     }
 
+    /// Predicates reachable from the positive clauses.
+    ///
+    /// A predicate is reachable if it appears in the rhs of a positive clause, or in the rhs of a
+    /// clause mentioning a reachable predicate in its lhs. Needs the graph to be [`setup`].
+    ///
+    /// [`setup`]: #method.setup (setup function)
+    pub fn forward_reachable(&self) -> PrdSet {
+        Self::reachable(&self.pos, &self.forward)
+    }
+
+    /// Predicates from which a negative clause is reachable.
+    ///
+    /// A predicate can reach a negative clause if it appears in the lhs of a negative clause, or
+    /// in the lhs of a clause with a predicate that can reach a negative clause in its rhs. Needs
+    /// the graph to be [`setup`].
+    ///
+    /// [`setup`]: #method.setup (setup function)
+    pub fn backward_reachable(&self) -> PrdSet {
+        Self::reachable(&self.neg, &self.bakward)
+    }
+
+    /// Predicates reachable from some start predicates by following some dependencies.
+    fn reachable(start: &PrdMap<usize>, dep: &Dep) -> PrdSet {
+        let mut reached = PrdSet::new();
+        let mut to_do: Vec<PrdIdx> = start
+            .index_iter()
+            .filter_map(|(pred, count)| if *count > 0 { Some(pred) } else { None })
+            .collect();
+        while let Some(pred) = to_do.pop() {
+            let is_new = reached.insert(pred);
+            if is_new {
+                for (tgt, count) in dep[pred].index_iter() {
+                    if *count > 0 && !reached.contains(&tgt) {
+                        to_do.push(tgt)
+                    }
+                }
+            }
+        }
+        reached
+    }
+
     /// Dumps a graph in dot format.
     pub fn dot_write<W>(&self, w: &mut W, instance: &Instance, hi_lite: &PrdSet) -> Res<()>
     where
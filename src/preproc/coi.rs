@@ -0,0 +1,99 @@
+//! Cone-of-influence slicing.
+
+use crate::{
+    common::*,
+    preproc::{cfg_red::Graph, PreInstance, RedStrat},
+};
+
+/// Removes the predicates outside of the cone of influence of the clauses.
+///
+/// Uses the [dependency graph] of the predicates to compute
+///
+/// - the predicates reachable from the positive clauses: the others can never be derived, they
+///   are forced to `false`;
+/// - the predicates from which a negative clause is reachable: the others cannot influence the
+///   result, they are forced to `true`.
+///
+/// A predicate that is in neither is forced to `false`. Forcing predicates removes the clauses
+/// that become trivial, and the definitions of the forced predicates are part of the final model.
+///
+/// # Examples
+///
+/// ```
+/// # use hoice::{ common::PrdIdx, parse, preproc::{ PreInstance, RedStrat, Coi } };
+/// let mut instance = parse::instance("
+///   (declare-fun p ( Int ) Bool)
+///   (declare-fun q ( Int ) Bool)
+///   (declare-fun r ( Int ) Bool)
+///   (assert
+///     (forall ( (n Int) ) (=> (> n 0) (p n)))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (and (p n) (< n 0)) false))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (q n) (p (+ n 1))))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (p n) (r (- n 1))))
+///   )
+/// ");
+///
+/// let mut coi = Coi::new(& instance);
+/// let mut instance = PreInstance::new(& mut instance).unwrap();
+/// let info = coi.apply(& mut instance).unwrap();
+/// assert_eq! { info.preds, 2 }
+///
+/// let (p, q, r): (PrdIdx, PrdIdx, PrdIdx) = (0.into(), 1.into(), 2.into());
+/// assert! { !instance[p].is_defined() }
+/// assert! { instance[q].is_defined() }
+/// assert! { instance[r].is_defined() }
+/// assert_eq! { instance.clauses().len(), 2 }
+/// ```
+///
+/// [dependency graph]: ../cfg_red/struct.Graph.html (Graph struct)
+pub struct Coi {
+    /// Graph, factored to avoid reallocation.
+    graph: Graph,
+}
+
+impl RedStrat for Coi {
+    fn name(&self) -> &'static str {
+        "coi"
+    }
+
+    fn new(instance: &Instance) -> Self {
+        Coi {
+            graph: Graph::new(instance),
+        }
+    }
+
+    fn apply(&mut self, instance: &mut PreInstance) -> Res<RedInfo> {
+        let mut info = RedInfo::new();
+
+        self.graph.setup(instance);
+        self.graph.check(&instance)?;
+
+        let forward = self.graph.forward_reachable();
+        let backward = self.graph.backward_reachable();
+
+        for pred in instance.pred_indices() {
+            if instance[pred].is_defined() {
+                continue;
+            }
+            conf.check_timeout()?;
+
+            if !forward.contains(&pred) {
+                log! { @verb "forcing {} to false (unreachable from facts)", instance[pred] }
+                info.preds += 1;
+                info += instance.force_false(pred)?
+            } else if !backward.contains(&pred) {
+                log! { @verb "forcing {} to true (cannot reach a negative clause)", instance[pred] }
+                info.preds += 1;
+                info += instance.force_true(pred)?
+            }
+        }
+
+        Ok(info)
+    }
+}
@@ -1004,6 +1004,7 @@ impl FunPreds {
         };
 
         info.clauses_rmed += to_rm.len();
+        instance.log_def_clauses(pred, &to_rm)?;
         instance.forget_clauses(&mut to_rm)?;
 
         let mut args = Vec::with_capacity(args_len);
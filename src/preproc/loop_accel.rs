@@ -0,0 +1,252 @@
+//! Acceleration of self-loops with constant increments.
+
+use crate::{
+    common::*,
+    info::VarInfo,
+    preproc::{PreInstance, RedStrat},
+};
+
+/// Info of the clauses created by loop acceleration.
+const INFO: &str = "loop_accel";
+
+/// Adds the transitive closure of simple self-loops as new clauses.
+///
+/// A self-loop `(p x) /\ g(x) => (p x + d)` is accelerated if
+///
+/// - `x` are distinct variables, and `p` is the only predicate application in the lhs,
+/// - `d` is a vector of integer constants, and the arguments that are not integers are unchanged,
+/// - the guard `g` is a conjunction of linear (in)equalities over `x`.
+///
+/// Since the guard is convex, it holds at all the iterations of the loop iff it holds at the
+/// first and the last one. The clause
+///
+/// ```text
+/// (p x) /\ k >= 1 /\ g(x) /\ g(x + (k - 1) d) => (p x + k d)
+/// ```
+///
+/// is a consequence of the self-loop, and is added to the instance. It lets the learner relate
+/// the arguments across any number of iterations at once. The original self-loop is kept, so
+/// the strategy does not change the predicates and is compatible with the strengthenings
+/// `strict_neg` computes afterwards. The strategy remembers the self-loops it accelerated so
+/// that it does not accelerate them again, other self-loops are accelerated even if they stem
+/// from the same original clause.
+///
+/// # Examples
+///
+/// ```
+/// # use hoice::{ common::PrdIdx, parse, preproc::{ PreInstance, RedStrat, LoopAccel } };
+/// let mut instance = parse::instance("
+///   (declare-fun p ( Int Int ) Bool)
+///   (assert
+///     (forall ( (x Int) (y Int) ) (=> (and (= x 0) (= y 0)) (p x y)))
+///   )
+///   (assert
+///     (forall ( (x Int) (y Int) ) (=> (and (p x y) (< x 10)) (p (+ x 1) (+ y 2))))
+///   )
+///   (assert
+///     (forall ( (x Int) (y Int) ) (=> (and (p x y) (> y (* 2 x))) false))
+///   )
+/// ");
+///
+/// let mut loop_accel = LoopAccel::new(& instance);
+/// let mut instance = PreInstance::new(& mut instance).unwrap();
+/// let info = loop_accel.apply(& mut instance).unwrap();
+/// assert_eq! { info.clauses_added, 1 }
+///
+/// let p: PrdIdx = 0.into();
+/// assert_eq! { instance.rhs_clauses_of(p).len(), 3 }
+///
+/// // Accelerating again does nothing.
+/// let info = loop_accel.apply(& mut instance).unwrap();
+/// assert_eq! { info.clauses_added, 0 }
+/// ```
+pub struct LoopAccel {
+    /// Self-loops already accelerated.
+    accelerated: Vec<Clause>,
+}
+
+impl LoopAccel {
+    /// True if a term is a linear integer term.
+    fn is_linear(term: &Term) -> bool {
+        if term.typ() != typ::int() {
+            return false;
+        }
+        if term.var_idx().is_some() || term.val().is_some() {
+            return true;
+        }
+        match term.app_inspect() {
+            Some((Op::Add, args)) | Some((Op::Sub, args)) | Some((Op::CMul, args)) => {
+                args.iter().all(Self::is_linear)
+            }
+            Some((Op::Mul, args)) => {
+                args.iter().filter(|arg| arg.val().is_none()).count() <= 1
+                    && args.iter().all(Self::is_linear)
+            }
+            _ => false,
+        }
+    }
+
+    /// True if a term is a linear (in)equality over integers.
+    fn is_convex(term: &Term) -> bool {
+        match term.app_inspect() {
+            Some((Op::Gt, args))
+            | Some((Op::Ge, args))
+            | Some((Op::Le, args))
+            | Some((Op::Lt, args))
+            | Some((Op::Eql, args)) => args.iter().all(Self::is_linear),
+            _ => false,
+        }
+    }
+
+    /// Increments of a self-loop, if it can be accelerated.
+    ///
+    /// Returns the increment of each argument, zero for the arguments that do not change.
+    fn increments(instance: &Instance, pred: PrdIdx, clause: &Clause) -> Option<VarMap<Int>> {
+        let (rhs_pred, rhs_args) = clause.rhs()?;
+        if rhs_pred != pred || clause.lhs_preds().len() != 1 {
+            return None;
+        }
+        let argss = clause.lhs_preds().get(&pred)?;
+        if argss.len() != 1 {
+            return None;
+        }
+        let args = argss.iter().next()?;
+
+        let mut vars = VarSet::new();
+        for arg in args.iter() {
+            if !vars.insert(arg.var_idx()?) {
+                return None;
+            }
+        }
+        for term in clause.lhs_terms() {
+            if !Self::is_convex(term) || !term::vars(term).is_subset(&vars) {
+                return None;
+            }
+        }
+
+        let mut increments = VarMap::with_capacity(args.len());
+        let mut moves = false;
+        for (var, arg) in args.index_iter() {
+            let rhs_arg = &rhs_args[var];
+            let increment = if instance[pred].sig()[var] == typ::int() {
+                let diff = term::sub(vec![rhs_arg.clone(), arg.clone()]);
+                diff.int_val()?.clone()
+            } else if rhs_arg == arg {
+                Int::zero()
+            } else {
+                return None;
+            };
+            moves = moves || !increment.is_zero();
+            increments.push(increment)
+        }
+
+        if moves {
+            Some(increments)
+        } else {
+            None
+        }
+    }
+
+    /// Accelerated version of a self-loop.
+    fn accelerate(clause: &Clause, pred: PrdIdx, increments: &VarMap<Int>) -> Res<Clause> {
+        let args = if let Some(args) = clause
+            .lhs_preds()
+            .get(&pred)
+            .and_then(|argss| argss.iter().next())
+        {
+            args
+        } else {
+            bail!("inconsistent clause during loop acceleration")
+        };
+
+        let mut nu_clause = clause.clone_with_rhs(None, INFO);
+        let k = nu_clause.vars.next_index();
+        nu_clause
+            .vars
+            .push(VarInfo::new(format!("hoice_accel_k@{}", k), typ::int(), k));
+        let k = term::var(k, typ::int());
+
+        // Arguments after `k - 1` and `k` iterations.
+        let mut last = VarHMap::new();
+        let mut nu_args = VarMap::with_capacity(args.len());
+        for (var, arg) in args.index_iter() {
+            let increment = &increments[var];
+            if increment.is_zero() {
+                nu_args.push(arg.clone());
+                continue;
+            }
+            if let Some(arg_var) = arg.var_idx() {
+                last.insert(
+                    arg_var,
+                    term::add(vec![
+                        arg.clone(),
+                        term::mul(vec![
+                            term::int(increment.clone()),
+                            term::sub(vec![k.clone(), term::int(1)]),
+                        ]),
+                    ]),
+                );
+            }
+            nu_args.push(term::add(vec![
+                arg.clone(),
+                term::mul(vec![term::int(increment.clone()), k.clone()]),
+            ]))
+        }
+
+        for term in clause.lhs_terms() {
+            nu_clause.insert_term(term.subst(&last).0);
+        }
+        nu_clause.insert_term(term::ge(k, term::int(1)));
+        nu_clause.set_rhs(pred, var_to::terms::new(nu_args))?;
+
+        Ok(nu_clause)
+    }
+}
+
+impl RedStrat for LoopAccel {
+    fn name(&self) -> &'static str {
+        "loop_accel"
+    }
+
+    fn new(_: &Instance) -> Self {
+        LoopAccel {
+            accelerated: vec![],
+        }
+    }
+
+    fn apply(&mut self, instance: &mut PreInstance) -> Res<RedInfo> {
+        let mut nu_clauses = vec![];
+
+        for pred in instance.pred_indices() {
+            if instance[pred].is_defined() {
+                continue;
+            }
+            conf.check_timeout()?;
+
+            let rhs_clauses = instance.rhs_clauses_of(pred);
+            for clause_idx in rhs_clauses {
+                let clause = &instance[*clause_idx];
+                if clause.info == INFO {
+                    continue;
+                }
+                // Skip self-loops that were already accelerated.
+                if self.accelerated.iter().any(|done| done.same_as(clause)) {
+                    continue;
+                }
+
+                if let Some(increments) = Self::increments(instance, pred, clause) {
+                    log! { @verb "accelerating a self-loop of {}", instance[pred] }
+                    let parents = instance.logged_clauses(Some(*clause_idx))?;
+                    nu_clauses.push((Self::accelerate(clause, pred, &increments)?, parents));
+                    self.accelerated.push(clause.clone())
+                }
+            }
+        }
+
+        let mut info = RedInfo::new();
+        for (clause, parents) in nu_clauses {
+            info += instance.add_clause(clause, parents)?
+        }
+        Ok(info)
+    }
+}
@@ -16,18 +16,40 @@ use crate::{common::*, instance::*, preproc::utils::ExtractRes};
 
 pub mod utils;
 
+pub mod adt_flatten;
+pub mod arg_prop;
 pub mod arg_red;
+pub mod array_elim;
 pub mod bias_unroll;
+pub mod bool_split;
 pub mod cfg_red;
+pub mod coi;
 pub mod fun_preds;
+pub mod loop_accel;
 pub mod one_lhs;
 pub mod one_rhs;
+pub mod pred_merge;
+pub mod script;
 pub mod strict_neg_clauses;
 pub mod unroll;
 
 pub use self::{
-    arg_red::ArgRed, bias_unroll::BiasedUnroll, cfg_red::CfgRed, fun_preds::FunPreds,
-    one_lhs::OneLhs, one_rhs::OneRhs, strict_neg_clauses::StrictNeg, unroll::RUnroll,
+    adt_flatten::AdtFlatten,
+    arg_prop::ArgProp,
+    arg_red::ArgRed,
+    array_elim::ArrayElim,
+    bias_unroll::BiasedUnroll,
+    bool_split::BoolSplit,
+    cfg_red::CfgRed,
+    coi::Coi,
+    fun_preds::FunPreds,
+    loop_accel::LoopAccel,
+    one_lhs::OneLhs,
+    one_rhs::OneRhs,
+    pred_merge::PredMerge,
+    script::{Script, Step, Strat},
+    strict_neg_clauses::StrictNeg,
+    unroll::RUnroll,
 };
 pub use crate::instance::PreInstance;
 
@@ -83,6 +105,13 @@ fn finalize(res: Res<()>, instance: &mut Instance, _profiler: &Profiler) -> Res<
         } "finalizing"
     )?;
 
+    if let Some(mut file) = conf.preproc.provenance_file(instance)? {
+        instance
+            .provenance()
+            .write_json(&mut file, instance)
+            .chain_err(|| "while writing the provenance log")?
+    }
+
     profile! {
         |_profiler|
         "clauses |        positive" => add instance.pos_clauses().len()
@@ -274,6 +303,20 @@ pub struct Reductor<'a> {
     one_lhs: Option<OneLhs>,
     /// Optional cfg pre-processor.
     cfg_red: Option<CfgRed>,
+    /// Optional cone-of-influence slicer.
+    coi: Option<Coi>,
+    /// Optional equivalent predicate merger.
+    pred_merge: Option<PredMerge>,
+    /// Optional boolean argument splitter.
+    bool_split: Option<BoolSplit>,
+    /// Optional predicate argument propagation.
+    arg_prop: Option<ArgProp>,
+    /// Optional datatype flattener.
+    adt_flatten: Option<AdtFlatten>,
+    /// Optional array eliminator.
+    array_elim: Option<ArrayElim>,
+    /// Optional self-loop accelerator.
+    loop_accel: Option<LoopAccel>,
     /// Optional biased unroller.
     biased_unroll: Option<BiasedUnroll>,
     /// Optional reverse unroller.
@@ -282,13 +325,28 @@ pub struct Reductor<'a> {
     strict_neg: Option<StrictNeg>,
     /// Optional predicate-to-function reduction.
     fun_preds: Option<FunPreds>,
+    /// Optional pre-processing script, replaces the built-in pipeline.
+    script: Option<Script>,
 }
 impl<'a> Reductor<'a> {
     /// Constructor.
     ///
-    /// Checks the configuration to initialize the pre-processors.
+    /// Checks the configuration to initialize the pre-processors. If there is a pre-processing
+    /// script, the pre-processors it mentions are initialized regardless of their own flag.
     pub fn new(instance: PreInstance<'a>) -> Res<Self> {
+        let script = instance
+            .preproc_script()
+            .cloned()
+            .or_else(|| conf.preproc.script.clone());
+
         macro_rules! some_new {
+            ($red:ident or $strat:ident in script if $($tail:tt)*) => (
+                if let Some(script) = script.as_ref() {
+                    some_new! { $red |if| conf.preproc.active && script.mentions(Strat::$strat) }
+                } else {
+                    some_new! { $red if $($tail)* }
+                }
+            ) ;
             ($red:ident if $flag:ident $(and $flags:ident )*) => (
                 some_new! { $red |if| conf.preproc.$flag $( && conf.preproc.$flags )* }
             ) ;
@@ -311,30 +369,39 @@ impl<'a> Reductor<'a> {
         }
 
         let simplify = Some(Simplify::new(&instance));
-        let arg_red = some_new! { ArgRed if active and arg_red };
+        let arg_red = some_new! { ArgRed or ArgRed in script if active and arg_red };
 
         let one_rhs = some_new! {
-          OneRhs if active and one_rhs
+          OneRhs or OneRhs in script if active and one_rhs
         };
         let one_lhs = some_new! {
-          OneLhs if active and one_lhs
+          OneLhs or OneLhs in script if active and one_lhs
         };
 
-        let cfg_red = some_new! { CfgRed if active and cfg_red };
+        let cfg_red = some_new! { CfgRed or CfgRed in script if active and cfg_red };
+        let coi = some_new! { Coi or Coi in script if active and coi };
+        let pred_merge = some_new! { PredMerge or PredMerge in script if active and pred_merge };
+        let bool_split = some_new! { BoolSplit or BoolSplit in script if active and bool_split };
+        let arg_prop = some_new! { ArgProp or ArgProp in script if active and arg_prop };
+        let adt_flatten =
+            some_new! { AdtFlatten or AdtFlatten in script if active and adt_flatten };
+        let array_elim = some_new! { ArrayElim or ArrayElim in script if active and array_elim };
+        let loop_accel = some_new! { LoopAccel or LoopAccel in script if active and loop_accel };
 
         let biased_unroll = some_new! {
-          BiasedUnroll if active and (conf.preproc.pos_unroll || conf.preproc.neg_unroll)
+          BiasedUnroll or BiasedUnroll in script
+          if active and (conf.preproc.pos_unroll || conf.preproc.neg_unroll)
         };
         let runroll = some_new! {
-          RUnroll if active and neg_unroll
+          RUnroll or RUnroll in script if active and neg_unroll
         };
         let strict_neg = some_new! {
-          StrictNeg if active and strict_neg
+          StrictNeg or StrictNeg in script if active and strict_neg
         };
         let fun_preds = if !dtyp::one_or_more()? {
             None
         } else {
-            some_new! { FunPreds if active and fun_preds }
+            some_new! { FunPreds or FunPreds in script if active and fun_preds }
         };
 
         Ok(Reductor {
@@ -344,10 +411,18 @@ impl<'a> Reductor<'a> {
             one_rhs,
             one_lhs,
             cfg_red,
+            coi,
+            pred_merge,
+            bool_split,
+            arg_prop,
+            adt_flatten,
+            array_elim,
+            loop_accel,
             biased_unroll,
             runroll,
             strict_neg,
             fun_preds,
+            script,
         })
     }
 
@@ -403,6 +478,15 @@ impl<'a> Reductor<'a> {
             return Ok(());
         }
 
+        if let Some(mut script) = self.script.take() {
+            log! { @verb "running pre-processing script {}", conf.emph(script.to_string()) }
+            let res = self.run_steps(&mut script.steps, _profiler, &mut count)?;
+            if res.is_some() {
+                utils::register_final_stats(&self.instance, _profiler)?
+            }
+            return Ok(());
+        }
+
         // Used to avoid running cfg reduction if nothing has changed since the
         // last run.
         let mut changed_since_cfg_red = true;
@@ -413,6 +497,12 @@ impl<'a> Reductor<'a> {
             }
             conf.check_timeout()?;
 
+            run! { coi };
+            run! { adt_flatten };
+            run! { array_elim };
+            run! { pred_merge };
+            run! { bool_split };
+            run! { arg_prop };
             run! { arg_red };
 
             let changed = false;
@@ -475,12 +565,104 @@ impl<'a> Reductor<'a> {
             }
         }
 
+        run! { loop_accel };
         run! { strict_neg };
 
         utils::register_final_stats(&self.instance, _profiler)?;
 
         Ok(())
     }
+
+    /// Runs a strategy from a script.
+    ///
+    /// Returns `None` if the instance is solved. Strategies that are not active do nothing.
+    fn run_strat(
+        &mut self,
+        strat: Strat,
+        _profiler: &Profiler,
+        count: &mut usize,
+    ) -> Res<Option<RedInfo>> {
+        macro_rules! run {
+            ($preproc:ident) => {
+                if let Some(preproc) = self.$preproc.as_mut() {
+                    utils::run_preproc(&mut self.instance, _profiler, preproc, count)
+                } else {
+                    Ok(Some(RedInfo::new()))
+                }
+            };
+        }
+
+        match strat {
+            Strat::Simplify => run!(simplify),
+            Strat::ArgRed => run!(arg_red),
+            Strat::OneRhs => run!(one_rhs),
+            Strat::OneLhs => run!(one_lhs),
+            Strat::FunPreds => run!(fun_preds),
+            Strat::CfgRed => run!(cfg_red),
+            Strat::Coi => run!(coi),
+            Strat::PredMerge => run!(pred_merge),
+            Strat::BoolSplit => run!(bool_split),
+            Strat::ArgProp => run!(arg_prop),
+            Strat::AdtFlatten => run!(adt_flatten),
+            Strat::ArrayElim => run!(array_elim),
+            Strat::LoopAccel => run!(loop_accel),
+            Strat::StrictNeg => run!(strict_neg),
+            Strat::RUnroll => run!(runroll),
+            Strat::BiasedUnroll => run!(biased_unroll),
+        }
+    }
+
+    /// Runs some steps of a script.
+    ///
+    /// Returns `None` if the instance is solved, and whether the steps changed the instance
+    /// otherwise. Budgets are updated as steps run, fixpoints without a budget stop after
+    /// [`script::MAX_FIXPOINT_ITERATIONS`] iterations.
+    ///
+    /// [`script::MAX_FIXPOINT_ITERATIONS`]: script/constant.MAX_FIXPOINT_ITERATIONS.html
+    /// (constant)
+    fn run_steps(
+        &mut self,
+        steps: &mut [Step],
+        _profiler: &Profiler,
+        count: &mut usize,
+    ) -> Res<Option<bool>> {
+        let mut changed = false;
+
+        for step in steps {
+            conf.check_timeout()?;
+
+            match step {
+                Step::Strat { strat, budget } => {
+                    if let Some(budget) = budget.as_mut() {
+                        if *budget == 0 {
+                            continue;
+                        }
+                        *budget -= 1
+                    }
+                    if let Some(info) = self.run_strat(*strat, _profiler, count)? {
+                        changed = info.non_zero() || changed
+                    } else {
+                        return Ok(None);
+                    }
+                }
+
+                Step::Fixpoint { steps, budget } => {
+                    let max = budget.unwrap_or(script::MAX_FIXPOINT_ITERATIONS);
+                    let mut iterations = 0;
+                    while iterations < max {
+                        iterations += 1;
+                        match self.run_steps(steps, _profiler, count)? {
+                            None => return Ok(None),
+                            Some(true) => changed = true,
+                            Some(false) => break,
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Some(changed))
+    }
 }
 
 /// Reduction strategy trait.
@@ -197,6 +197,15 @@ impl OneLhs {
                 log! { @4 "=> trivial" }
                 instance.force_true(pred)?
             }
+            Success((_, ref pred_app, ref tterms))
+                if !instance[pred].invariants().is_empty()
+                    && (pred_app.is_some() || !tterms.preds().is_empty()) =>
+            {
+                // The definition would be a disjunction of predicate applications, which cannot
+                // be strengthened with the argument invariants of the predicate.
+                log! { @4 "=> not forced, {} has argument invariants", instance[pred] }
+                return Ok(None);
+            }
             Success((qualfed, pred_app, tterms)) => {
                 if pred_app.is_none() && tterms.is_empty() {
                     log! { @4 "=> false" }
@@ -0,0 +1,242 @@
+//! Merges equivalent predicates.
+
+use crate::{
+    common::*,
+    preproc::{PreInstance, RedStrat},
+};
+
+/// Merges predicates defined by isomorphic clauses.
+///
+/// Two predicates are *equivalent* if they have the same signature, and the clauses they appear
+/// in the rhs of are the same modulo variable renaming and renaming of equivalent predicates.
+/// Equivalent predicates have the same least model, so all the predicates of an equivalence class
+/// can be replaced by one of them. The definition of the predicates merged is an application of
+/// the predicate they were merged into, and is restored when extending the model.
+///
+/// Equivalence classes are computed by partition refinement, starting from the classes of
+/// predicates with the same signature. Predicates that are already defined or that have a
+/// strengthening term are never merged.
+///
+/// Clauses are compared syntactically after renaming their variables in order of appearance, so
+/// this strategy does not detect all isomorphic clauses.
+///
+/// # Examples
+///
+/// ```
+/// # use hoice::{ common::PrdIdx, parse, preproc::{ PreInstance, RedStrat, PredMerge } };
+/// let mut instance = parse::instance("
+///   (declare-fun p ( Int ) Bool)
+///   (declare-fun q ( Int ) Bool)
+///   (assert
+///     (forall ( (n Int) ) (=> (= n 0) (p n)))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (and (p n) (< n 10)) (p (+ n 1))))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (= n 0) (q n)))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (and (q n) (< n 10)) (q (+ n 1))))
+///   )
+///   (assert
+///     (forall ( (n Int) ) (=> (and (p n) (q n) (> n 10)) false))
+///   )
+/// ");
+///
+/// let mut pred_merge = PredMerge::new(& instance);
+/// let mut instance = PreInstance::new(& mut instance).unwrap();
+/// let info = pred_merge.apply(& mut instance).unwrap();
+/// assert_eq! { info.preds, 1 }
+///
+/// let (p, q): (PrdIdx, PrdIdx) = (0.into(), 1.into());
+/// assert! { !instance[p].is_defined() }
+/// assert! { instance[q].is_defined() }
+/// ```
+pub struct PredMerge {
+    /// Equivalence class of each predicate.
+    class: PrdMap<usize>,
+}
+
+impl PredMerge {
+    /// Key of a clause, used to compare the clauses predicates appear in the rhs of.
+    ///
+    /// Renames the variables in order of appearance in the rhs arguments, the lhs applications
+    /// and then the lhs terms. Predicates are replaced by their class.
+    fn clause_key(&self, instance: &Instance, clause: ClsIdx) -> String {
+        let clause = &instance[clause];
+
+        let mut map = VarHMap::new();
+        let mut types = String::new();
+
+        let mut rename = |term: &Term, map: &mut VarHMap<Term>| {
+            let mut vars: Vec<VarIdx> = term::vars(term).into_iter().collect();
+            vars.sort_unstable();
+            for var in vars {
+                if !map.contains_key(&var) {
+                    let typ = clause.vars()[var].typ.clone();
+                    types.push_str(&format!(" {}", typ));
+                    let nu_var = term::var(map.len(), typ);
+                    let _ = map.insert(var, nu_var);
+                }
+            }
+        };
+
+        if let Some((_, args)) = clause.rhs() {
+            for arg in args.iter() {
+                rename(arg, &mut map)
+            }
+        }
+
+        let mut apps: Vec<(usize, String, &VarTerms)> = vec![];
+        for (pred, argss) in clause.lhs_preds() {
+            for args in argss {
+                apps.push((self.class[*pred], format!("{}", args), args))
+            }
+        }
+        apps.sort_unstable_by(|(c_1, s_1, _), (c_2, s_2, _)| (c_1, s_1).cmp(&(c_2, s_2)));
+        for (_, _, args) in &apps {
+            for arg in args.iter() {
+                rename(arg, &mut map)
+            }
+        }
+
+        let mut terms: Vec<&Term> = clause.lhs_terms().iter().collect();
+        terms.sort_unstable_by_key(|term| format!("{}", term));
+        for term in &terms {
+            rename(term, &mut map)
+        }
+
+        let mut lhs: Vec<String> = apps
+            .iter()
+            .map(|(class, _, args)| {
+                let mut s = format!("(#{}", class);
+                for arg in args.iter() {
+                    s.push_str(&format!(" {}", arg.subst(&map).0))
+                }
+                s.push_str(")");
+                s
+            })
+            .collect();
+        lhs.extend(terms.iter().map(|term| format!("{}", term.subst(&map).0)));
+        lhs.sort_unstable();
+
+        let mut key = format!("({} ) (", types);
+        for elem in &lhs {
+            key.push_str(" ");
+            key.push_str(elem)
+        }
+        key.push_str(" ) =>");
+        if let Some((_, args)) = clause.rhs() {
+            for arg in args.iter() {
+                key.push_str(&format!(" {}", arg.subst(&map).0))
+            }
+        }
+        key
+    }
+
+    /// Computes the equivalence classes of the predicates.
+    ///
+    /// Returns the classes with at least two predicates.
+    fn classes(&mut self, instance: &Instance) -> Vec<Vec<PrdIdx>> {
+        self.class = PrdMap::with_capacity(instance.preds().len());
+
+        let mut mergeable = PrdSet::new();
+        let mut count = 0;
+        {
+            let mut sig_classes = HashMap::new();
+            for pred in instance.preds() {
+                if pred.is_defined() || pred.strength().is_some() {
+                    self.class.push(count);
+                    count += 1
+                } else {
+                    mergeable.insert(pred.idx);
+                    let sig: Vec<String> = pred.sig.iter().map(|typ| typ.to_string()).collect();
+                    let class = *sig_classes.entry(sig).or_insert_with(|| {
+                        count += 1;
+                        count - 1
+                    });
+                    self.class.push(class)
+                }
+            }
+        }
+
+        loop {
+            let mut keys = HashMap::new();
+            let mut nu_class = PrdMap::with_capacity(self.class.len());
+            let mut nu_count = 0;
+
+            for pred in instance.pred_indices() {
+                let key = if mergeable.contains(&pred) {
+                    let mut clauses: Vec<String> = instance
+                        .rhs_clauses_of(pred)
+                        .iter()
+                        .map(|clause| self.clause_key(instance, *clause))
+                        .collect();
+                    clauses.sort_unstable();
+                    clauses.dedup();
+                    Some(clauses)
+                } else {
+                    None
+                };
+                let class = *keys.entry((self.class[pred], key)).or_insert_with(|| {
+                    nu_count += 1;
+                    nu_count - 1
+                });
+                nu_class.push(class)
+            }
+
+            self.class = nu_class;
+            if nu_count == count {
+                break;
+            }
+            count = nu_count
+        }
+
+        let mut classes: Vec<Vec<PrdIdx>> = vec![vec![]; count];
+        for pred in mergeable {
+            classes[self.class[pred]].push(pred)
+        }
+        classes.retain(|class| class.len() > 1);
+        for class in &mut classes {
+            class.sort_unstable()
+        }
+        classes
+    }
+}
+
+impl RedStrat for PredMerge {
+    fn name(&self) -> &'static str {
+        "pred_merge"
+    }
+
+    fn new(instance: &Instance) -> Self {
+        PredMerge {
+            class: PrdMap::with_capacity(instance.preds().len()),
+        }
+    }
+
+    fn apply(&mut self, instance: &mut PreInstance) -> Res<RedInfo> {
+        let mut info = RedInfo::new();
+
+        for class in self.classes(instance) {
+            let mut class = class.into_iter();
+            let into = if let Some(pred) = class.next() {
+                pred
+            } else {
+                continue;
+            };
+            for pred in class {
+                conf.check_timeout()?;
+                if instance[pred].is_defined() || instance[into].is_defined() {
+                    continue;
+                }
+                log! { @verb "merging {} into {}", instance[pred], instance[into] }
+                info.preds += 1;
+                info += instance.merge_pred(pred, into)?
+            }
+        }
+
+        Ok(info)
+    }
+}
@@ -0,0 +1,358 @@
+//! Pre-processing scripts.
+//!
+//! A script describes the order in which the [`Reductor`] runs the reduction strategies. It is a
+//! `;`-separated sequence of steps, where a step is either
+//!
+//! - the name of a strategy: `simplify`, `arg_red`, `one_rhs`, `one_lhs`, `fun_preds`, `cfg_red`,
+//!   `coi`, `pred_merge`, `bool_split`, `arg_prop`, `adt_flatten`, `array_elim`, `loop_accel`,
+//!   `strict_neg`, `runroll` or `biased_unroll`, or
+//! - `fixpoint(<script>)`, which runs `<script>` until none of its steps changes the instance.
+//!
+//! Any step can be followed by `@<int>`, its *budget*. The budget of a strategy is the maximum
+//! number of times it can run, which is relevant when it appears in a fixpoint. The budget of a
+//! fixpoint is the maximum number of iterations, [`MAX_FIXPOINT_ITERATIONS`] if none is given.
+//!
+//! Scripts are given with `--preproc_script` or `(set-option :preproc-script "<script>")`, the
+//! latter having priority. Without a script, the reductor uses its built-in pipeline.
+//!
+//! # Examples
+//!
+//! ```
+//! # use hoice::preproc::script::{ Script, Strat };
+//! let script: Script =
+//!     "simplify ; fixpoint@10( arg_red ; one_rhs ; one_lhs@3 ) ; cfg_red ; strict_neg"
+//!         .parse().unwrap();
+//! assert! { script.mentions(Strat::OneLhs) }
+//! assert! { !script.mentions(Strat::RUnroll) }
+//! assert_eq! {
+//!     &format!("{}", script),
+//!     "simplify; fixpoint@10(arg_red; one_rhs; one_lhs@3); cfg_red; strict_neg"
+//! }
+//!
+//! assert! { "simplify; unknown_strat".parse::<Script>().is_err() }
+//! assert! { "fixpoint(arg_red".parse::<Script>().is_err() }
+//! ```
+//!
+//! [`Reductor`]: ../struct.Reductor.html (Reductor struct)
+//! [`MAX_FIXPOINT_ITERATIONS`]: constant.MAX_FIXPOINT_ITERATIONS.html (constant)
+
+use crate::common::*;
+
+/// Maximum number of iterations of a fixpoint without a budget.
+///
+/// Some strategies create predicates and clauses, a fixpoint over them might not terminate.
+pub const MAX_FIXPOINT_ITERATIONS: usize = 100;
+
+/// Reduction strategies that can appear in a script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strat {
+    /// Clause simplification.
+    Simplify,
+    /// Argument reduction.
+    ArgRed,
+    /// One rhs reduction.
+    OneRhs,
+    /// One lhs reduction.
+    OneLhs,
+    /// Predicate-to-function reduction.
+    FunPreds,
+    /// Control flow graph reduction.
+    CfgRed,
+    /// Cone-of-influence slicing.
+    Coi,
+    /// Equivalent predicate merging.
+    PredMerge,
+    /// Case-splitting on boolean arguments.
+    BoolSplit,
+    /// Constant and equality propagation across predicate arguments.
+    ArgProp,
+    /// Flattening of non-recursive datatypes.
+    AdtFlatten,
+    /// Elimination of arrays only accessed at fixed indices.
+    ArrayElim,
+    /// Acceleration of self-loops with constant increments.
+    LoopAccel,
+    /// Strengthening by strict negative clauses.
+    StrictNeg,
+    /// Reverse unrolling.
+    RUnroll,
+    /// Biased unrolling.
+    BiasedUnroll,
+}
+impl Strat {
+    /// All the strategies.
+    pub const ALL: [Strat; 16] = [
+        Strat::Simplify,
+        Strat::ArgRed,
+        Strat::OneRhs,
+        Strat::OneLhs,
+        Strat::FunPreds,
+        Strat::CfgRed,
+        Strat::Coi,
+        Strat::PredMerge,
+        Strat::BoolSplit,
+        Strat::ArgProp,
+        Strat::AdtFlatten,
+        Strat::ArrayElim,
+        Strat::LoopAccel,
+        Strat::StrictNeg,
+        Strat::RUnroll,
+        Strat::BiasedUnroll,
+    ];
+
+    /// Name of a strategy in scripts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Strat::Simplify => "simplify",
+            Strat::ArgRed => "arg_red",
+            Strat::OneRhs => "one_rhs",
+            Strat::OneLhs => "one_lhs",
+            Strat::FunPreds => "fun_preds",
+            Strat::CfgRed => "cfg_red",
+            Strat::Coi => "coi",
+            Strat::PredMerge => "pred_merge",
+            Strat::BoolSplit => "bool_split",
+            Strat::ArgProp => "arg_prop",
+            Strat::AdtFlatten => "adt_flatten",
+            Strat::ArrayElim => "array_elim",
+            Strat::LoopAccel => "loop_accel",
+            Strat::StrictNeg => "strict_neg",
+            Strat::RUnroll => "runroll",
+            Strat::BiasedUnroll => "biased_unroll",
+        }
+    }
+
+    /// Strategy corresponding to a name.
+    pub fn of_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|strat| strat.name() == name).cloned()
+    }
+}
+mylib::impl_fmt! {
+    Strat(self, fmt) {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+/// A step in a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Runs a strategy.
+    Strat {
+        /// The strategy.
+        strat: Strat,
+        /// Remaining number of runs.
+        budget: Option<usize>,
+    },
+    /// Runs some steps until they do not change the instance.
+    Fixpoint {
+        /// Steps of the fixpoint.
+        steps: Vec<Step>,
+        /// Maximum number of iterations, [`MAX_FIXPOINT_ITERATIONS`] if `None`.
+        ///
+        /// [`MAX_FIXPOINT_ITERATIONS`]: constant.MAX_FIXPOINT_ITERATIONS.html (constant)
+        budget: Option<usize>,
+    },
+}
+impl Step {
+    /// True if a strategy appears in the step.
+    pub fn mentions(&self, strat: Strat) -> bool {
+        match self {
+            Step::Strat { strat: s, .. } => *s == strat,
+            Step::Fixpoint { steps, .. } => steps.iter().any(|step| step.mentions(strat)),
+        }
+    }
+}
+
+/// Writes a budget, if any.
+fn write_budget(fmt: &mut ::std::fmt::Formatter, budget: Option<usize>) -> ::std::fmt::Result {
+    if let Some(budget) = budget {
+        write!(fmt, "@{}", budget)
+    } else {
+        Ok(())
+    }
+}
+/// Writes a sequence of steps.
+fn write_steps(fmt: &mut ::std::fmt::Formatter, steps: &[Step]) -> ::std::fmt::Result {
+    for (idx, step) in steps.iter().enumerate() {
+        if idx > 0 {
+            write!(fmt, "; ")?
+        }
+        write!(fmt, "{}", step)?
+    }
+    Ok(())
+}
+
+mylib::impl_fmt! {
+    Step(self, fmt) {
+        match self {
+            Step::Strat { strat, budget } => {
+                write!(fmt, "{}", strat)?;
+                write_budget(fmt, *budget)
+            }
+            Step::Fixpoint { steps, budget } => {
+                write!(fmt, "fixpoint")?;
+                write_budget(fmt, *budget)?;
+                write!(fmt, "(")?;
+                write_steps(fmt, steps)?;
+                write!(fmt, ")")
+            }
+        }
+    }
+}
+
+/// A pre-processing script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    /// Steps of the script.
+    pub steps: Vec<Step>,
+}
+mylib::impl_fmt! {
+    Script(self, fmt) {
+        write_steps(fmt, &self.steps)
+    }
+}
+
+impl ::std::str::FromStr for Script {
+    type Err = Error;
+    fn from_str(s: &str) -> Res<Self> {
+        let mut parser = ScriptParser { s, cursor: 0 };
+        let steps = parser
+            .steps()
+            .chain_err(|| format!("while parsing pre-processing script `{}`", s))?;
+        parser.ws();
+        if parser.cursor < s.len() {
+            bail!(
+                "while parsing pre-processing script `{}`: unexpected `{}`",
+                s,
+                &s[parser.cursor..]
+            )
+        }
+        Ok(Script { steps })
+    }
+}
+
+impl Script {
+    /// True if a strategy appears in the script.
+    pub fn mentions(&self, strat: Strat) -> bool {
+        self.steps.iter().any(|step| step.mentions(strat))
+    }
+}
+
+/// Script parser.
+struct ScriptParser<'a> {
+    /// String to parse.
+    s: &'a str,
+    /// Current position.
+    cursor: usize,
+}
+impl<'a> ScriptParser<'a> {
+    /// Rest of the string.
+    fn rest(&self) -> &'a str {
+        &self.s[self.cursor..]
+    }
+
+    /// Skips whitespaces.
+    fn ws(&mut self) {
+        let rest = self.rest();
+        self.cursor += rest.len() - rest.trim_start().len()
+    }
+
+    /// Parses a character if it's next.
+    fn char_opt(&mut self, c: char) -> bool {
+        self.ws();
+        if self.rest().starts_with(c) {
+            self.cursor += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses an identifier.
+    fn ident(&mut self) -> Res<&'a str> {
+        self.ws();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| rest.len());
+        if len == 0 {
+            if rest.is_empty() {
+                bail!("expected step, found end of script")
+            } else {
+                bail!("expected step, found `{}`", rest)
+            }
+        }
+        self.cursor += len;
+        Ok(&rest[0..len])
+    }
+
+    /// Parses an optional budget.
+    fn budget(&mut self) -> Res<Option<usize>> {
+        use std::str::FromStr;
+        if !self.char_opt('@') {
+            return Ok(None);
+        }
+        self.ws();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_numeric())
+            .unwrap_or_else(|| rest.len());
+        if let Ok(budget) = usize::from_str(&rest[0..len]) {
+            self.cursor += len;
+            Ok(Some(budget))
+        } else {
+            bail!("expected integer budget after `@`")
+        }
+    }
+
+    /// Parses a step.
+    fn step(&mut self) -> Res<Step> {
+        let ident = self.ident()?;
+        let budget = self.budget()?;
+        if ident == "fixpoint" {
+            if !self.char_opt('(') {
+                bail!("expected `(` after `fixpoint`")
+            }
+            let steps = self.steps()?;
+            if !self.char_opt(')') {
+                bail!("expected `)` closing `fixpoint(`")
+            }
+            Ok(Step::Fixpoint { steps, budget })
+        } else if let Some(strat) = Strat::of_name(ident) {
+            Ok(Step::Strat { strat, budget })
+        } else {
+            bail!("unknown pre-processing strategy `{}`", ident)
+        }
+    }
+
+    /// Parses a sequence of steps, separated by `;`.
+    fn steps(&mut self) -> Res<Vec<Step>> {
+        let mut steps = vec![self.step()?];
+        while self.char_opt(';') {
+            self.ws();
+            if self.rest().is_empty() || self.rest().starts_with(')') {
+                break;
+            }
+            steps.push(self.step()?)
+        }
+        Ok(steps)
+    }
+}
+
+/// Validates a pre-processing script, used by clap.
+///
+/// Also accepts `default`, which stands for the built-in pipeline.
+#[cfg_attr(feature = "cargo-clippy", allow(needless_pass_by_value))]
+pub fn script_validator(s: String) -> Result<(), String> {
+    if s == "default" {
+        return Ok(());
+    }
+    match s.parse::<Script>() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join(": ")),
+    }
+}
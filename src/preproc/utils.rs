@@ -771,9 +771,11 @@ pub fn run_preproc<Strat: RedStrat>(
     log! { @verb
       "running {}", conf.emph( preproc.name() )
     }
+    instance.set_provenance_strat(Some(preproc.name()));
     let red_info = preproc
         .apply(instance)
         .chain_err(|| format!("while running preprocessor {}", conf.bad(preproc.name())));
+    instance.set_provenance_strat(None);
     profile! {
       |_profiler| mark "preproc", preproc.name()
     }
@@ -8,7 +8,7 @@ use std::time::Duration;
 use crate::{
     common::{
         msg::*,
-        smt::{FullParser as Parser, SmtTerm},
+        smt::{FPVar, FullParser as Parser, SmtActPredDef, SmtTerm},
         *,
     },
     data::Data,
@@ -21,6 +21,9 @@ use self::assistant::Assistant;
 
 pub use self::cex_bias::CexBias;
 
+#[cfg(test)]
+mod test;
+
 /// Starts the teaching process.
 ///
 /// The partial model stores conjunction of top terms for some of the top
@@ -53,6 +56,8 @@ pub fn start_class(
             _ => {
                 if let Err(tmo) = conf.check_timeout() {
                     Err(tmo)
+                } else if e.is_unknown() {
+                    Err(e).chain_err(|| teacher.unknown_report())
                 } else {
                     Err(e)
                 }
@@ -164,6 +169,13 @@ pub struct Teacher<'a> {
     fls_preds: PrdSet,
     /// Clauses that are trivially verified in the current candidate.
     clauses_to_ignore: ClsSet,
+    /// Clauses known to be valid, with the candidates they were checked against.
+    ///
+    /// Maps a clause to the candidates of the predicates it mentions at the time it was proved
+    /// valid. Only populated when `cex_cache` is true.
+    valid_cache: ClsHMap<PrdHMap<Option<Term>>>,
+    /// True if clauses known to be valid are not checked again, see `--cex_cache`.
+    cex_cache: bool,
 
     /// Helper for cex bias.
     bias: CexBias,
@@ -181,6 +193,25 @@ pub struct Teacher<'a> {
     using_rec_funs: bool,
     /// Forces to restart the solver after each check.
     restart_on_cex: bool,
+    /// True if predicates are declared once and their candidates guarded by actlits.
+    ///
+    /// Never true if `restart_on_cex` is.
+    incremental: bool,
+    /// Candidates currently active in incremental mode, with the actlit guarding them.
+    act_defs: PrdHMap<(Term, Actlit)>,
+    /// Maximum number of counterexamples extracted for a clause.
+    cexs_per_clause: usize,
+    /// True if diverse counterexamples must have different sign patterns.
+    cex_signs: bool,
+
+    /// Last candidates checked, for unknown reports.
+    last_candidates: Option<Candidates>,
+    /// Clauses falsified by the last candidates checked.
+    falsified_clauses: ClsSet,
+    /// Clauses the solver could not check for the last candidates checked.
+    unknown_clauses: ClsSet,
+    /// Learners that gave up, with their reasons.
+    gave_up: Vec<(String, Vec<String>)>,
 }
 
 impl<'a> Teacher<'a> {
@@ -241,8 +272,9 @@ impl<'a> Teacher<'a> {
 
         let restart_on_cex =
             conf.teacher.restart_on_cex || !dtyp::get_all().is_empty() || using_rec_funs;
+        let incremental = conf.teacher.incremental && !restart_on_cex;
 
-        Ok(Teacher {
+        let mut teacher = Teacher {
             solver,
             instance,
             data,
@@ -256,10 +288,26 @@ impl<'a> Teacher<'a> {
             tru_preds: PrdSet::new(),
             fls_preds: PrdSet::new(),
             clauses_to_ignore: ClsSet::new(),
+            valid_cache: ClsHMap::new(),
+            cex_cache: conf.teacher.cex_cache,
             bias: CexBias::new(),
             using_rec_funs,
             restart_on_cex,
-        })
+            incremental,
+            act_defs: PrdHMap::new(),
+            cexs_per_clause: conf.teacher.cexs_per_clause,
+            cex_signs: conf.teacher.cex_signs,
+            last_candidates: None,
+            falsified_clauses: ClsSet::new(),
+            unknown_clauses: ClsSet::new(),
+            gave_up: vec![],
+        };
+
+        if teacher.incremental {
+            teacher.declare_preds()?
+        }
+
+        Ok(teacher)
     }
 
     /// Model from some candidates.
@@ -308,11 +356,59 @@ impl<'a> Teacher<'a> {
         if !nu_stuff {
             bail! { "translation of initial cexs to data generated no new data" }
         }
+
+        if let Some(file_path) = conf.load_data_file() {
+            if self.instance.persist_data() {
+                self.load_data(file_path)?
+            }
+        }
+
         self.run_assistant()?;
 
         Ok(None)
     }
 
+    /// Seeds the learning data with a dump from a previous run.
+    ///
+    /// Only the facts that can be justified on the current instance are kept, see
+    /// [`Dump::seed`].
+    ///
+    /// [`Dump::seed`]: ../data/persist/struct.Dump.html#method.seed (seed function)
+    fn load_data(&mut self, file_path: &str) -> Res<()> {
+        use std::fs::OpenOptions;
+        log_debug! { "loading learning data from `{}`...", file_path }
+        profile! { self tick "load data" }
+        let file = OpenOptions::new()
+            .read(true)
+            .open(file_path)
+            .chain_err(|| format!("while opening data file `{}`", conf.emph(file_path)))?;
+        let dump = crate::data::persist::load(file, &self.instance)
+            .chain_err(|| format!("while loading data file `{}`", conf.emph(file_path)))?;
+        let _discarded = dump.discarded();
+        let _seeded = dump.seed(&mut self.data)?;
+        profile! { self mark "load data" }
+        profile! { self "loaded facts" => add _seeded }
+        profile! { self "discarded facts" => add _discarded }
+        Ok(())
+    }
+
+    /// Dumps the learning data at the end of a file.
+    ///
+    /// The file is the temporary dump file, see `Config::dump_data_tmp_file`. Each `check-sat` of
+    /// a script runs its own teacher, hence appending: the dump gathers the data of all of them.
+    /// The temporary file does not exist when the script starts, and replaces the dump file when
+    /// the script is done.
+    fn dump_data(&self, file_path: &str) -> Res<()> {
+        use std::fs::OpenOptions;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)
+            .chain_err(|| format!("while opening data file `{}`", conf.emph(file_path)))?;
+        crate::data::persist::dump(&self.data, &mut file)
+            .chain_err(|| format!("while dumping learning data to `{}`", conf.emph(file_path)))
+    }
+
     /// Runs the assistant (if any) on the current data.
     pub fn run_assistant(&mut self) -> Res<()> {
         if let Some(assistant) = self.assistant.as_mut() {
@@ -361,6 +457,12 @@ impl<'a> Teacher<'a> {
         log_debug! { "draining messages" }
         while let Ok(_) = self.get_candidates(true) {}
 
+        if let Some(file_path) = conf.dump_data_tmp_file() {
+            if self.instance.persist_data() {
+                self.dump_data(&file_path)?
+            }
+        }
+
         if conf.stats {
             self._profiler.add_sub("data", self.data.destroy())
         }
@@ -651,6 +753,11 @@ impl<'a> Teacher<'a> {
                       } ;
                       log! { @verb "received `{}` from {}", conf.bad("unknown"), id }
                     }
+                    let name = match id {
+                        Id::Learner(idx) => self.learners[idx].1.clone(),
+                        Id::Assistant => "assistant".into(),
+                    };
+                    self.gave_up.push((name, e.reasons()));
 
                     // Are we unsat?
                     if self
@@ -698,6 +805,65 @@ impl<'a> Teacher<'a> {
             .chain_err(|| "while getting unsat proof in teacher")
     }
 
+    /// Report explaining why the teacher could not conclude.
+    ///
+    /// Lists the learners that gave up, the clauses the solver could not check, the predicates
+    /// appearing in clauses falsified or not checked, and the last candidates checked.
+    pub fn unknown_report(&self) -> String {
+        let instance = &self.instance;
+        let clause_str = |clause: &ClsIdx| {
+            let from = instance[*clause].from();
+            if let Some(name) = instance.name_of_old_clause(from) {
+                format!("#{} (from {})", clause, name)
+            } else {
+                format!("#{} (from #{})", clause, from)
+            }
+        };
+
+        let mut report = format!("teacher gave up after checking {} candidate(s)", self.count);
+
+        for (learner, reasons) in &self.gave_up {
+            report.push_str(&format!("\nlearner {} gave up", learner));
+            for reason in reasons {
+                report.push_str(&format!("\n  {}", reason))
+            }
+        }
+
+        if !self.unknown_clauses.is_empty() {
+            report.push_str("\nsolver returned unknown when checking clause(s)");
+            for clause in &self.unknown_clauses {
+                report.push_str(&format!("\n  {}", clause_str(clause)))
+            }
+        }
+
+        let mut preds = PrdSet::new();
+        for clause in self.falsified_clauses.iter().chain(&self.unknown_clauses) {
+            let (lhs, rhs) = instance.preds_of_clause(*clause);
+            preds.extend(lhs.keys().cloned().chain(rhs))
+        }
+        if !preds.is_empty() {
+            let mut names: Vec<_> = preds.iter().map(|pred| &instance[*pred].name).collect();
+            names.sort();
+            report.push_str("\npredicates without a consistent candidate:");
+            for name in names {
+                report.push_str(&format!(" {}", name))
+            }
+        }
+
+        if let Some(cands) = self.last_candidates.as_ref() {
+            report.push_str("\nlast candidates:");
+            for (pred, cand) in cands.index_iter() {
+                if let Some(cand) = cand.as_ref() {
+                    report.push_str(&format!("\n  {}: {}", instance[pred], cand))
+                }
+            }
+        } else {
+            report.push_str("\nno candidates were checked")
+        }
+
+        report
+    }
+
     /// Initial check, where all candidates are `true`.
     ///
     /// Drops the copy of the `Sender` end of the channel used to communicate
@@ -707,29 +873,19 @@ impl<'a> Teacher<'a> {
         // Drop `to_teacher` sender so that we know when all kids are dead.
         self.to_teacher = None;
 
+        let mut warm_start = match conf.warm_start_file() {
+            Some(file_path) if self.instance.persist_data() => {
+                self.warm_start_candidates(file_path)?
+            }
+            _ => PrdHMap::new(),
+        };
+
         let mut cands = PrdMap::with_capacity(self.instance.preds().len());
         for pred in self.instance.pred_indices() {
             if self.instance[pred].is_defined() {
                 cands.push(None)
-
-            // } else if let Some(dnf) = partial_candidate.get(& pred) {
-            //   let mut cand_dnf = vec![] ;
-            //   for conj in dnf {
-            //     let mut cand_conj = vec![] ;
-            //     for tterms in conj {
-            //       if let Some(term) = tterms.to_term() {
-            //         term.subst()
-            //         cand_conj.push(term)
-            //       } else {
-            //         cand_conj.clear() ;
-            //         cand_dnf.clear() ;
-            //         cands.push( Some(term::tru()) ) ;
-            //         continue 'all_preds
-            //       }
-            //     }
-            //     cand_dnf.push( term::and(cand_conj) )
-            //   }
-            //   cands.push( Some( term::or(cand_dnf) ) )
+            } else if let Some(cand) = warm_start.remove(&pred) {
+                cands.push(Some(cand))
             } else {
                 cands.push(Some(term::tru()))
             }
@@ -751,6 +907,141 @@ impl<'a> Teacher<'a> {
         self.get_cexs(&cands).map(|res| (res, cands))
     }
 
+    /// Retrieves candidates from a model file.
+    ///
+    /// The definitions are translated to the current signature of the predicates. Definitions
+    /// mentioning arguments removed by pre-processing are ignored.
+    fn warm_start_candidates(&self, file_path: &str) -> Res<PrdHMap<Term>> {
+        use std::fs::OpenOptions;
+        log_debug! { "loading warm start model from `{}`...", file_path }
+        let mut content = String::new();
+        OpenOptions::new()
+            .read(true)
+            .open(file_path)
+            .chain_err(|| format!("while opening model file `{}`", conf.emph(file_path)))?
+            .read_to_string(&mut content)
+            .chain_err(|| format!("while reading model file `{}`", conf.emph(file_path)))?;
+
+        let mut cxt = crate::parse::ParserCxt::new();
+        let defs = cxt
+            .parser(&content, 0, self._profiler)
+            .model(&self.instance)
+            .chain_err(|| format!("while parsing model file `{}`", conf.emph(file_path)))?;
+
+        let mut cands = PrdHMap::with_capacity(defs.len());
+        for (pred, def) in defs {
+            let map = self.instance.map_from_original_sig_of(pred);
+            if let Some((cand, _)) = def.subst_total(&map) {
+                profile! { self "warm start candidates" => add 1 }
+                let _ = cands.insert(pred, cand);
+            } else {
+                log_debug! {
+                    "ignoring warm start definition of {}: it mentions removed arguments",
+                    self.instance[pred]
+                }
+            }
+        }
+
+        Ok(cands)
+    }
+
+    /// Declares the predicates, used in incremental mode.
+    ///
+    /// Predicates that are already defined in the instance are ignored.
+    fn declare_preds(&mut self) -> Res<()> {
+        for pred in self.instance.preds() {
+            if !pred.is_defined() {
+                let sig: Vec<_> = pred.sig.iter().map(|typ| typ.get()).collect();
+                self.solver.declare_fun(&pred.name, &sig, "Bool")?
+            }
+        }
+        Ok(())
+    }
+
+    /// Resets the solver.
+    ///
+    /// In incremental mode, forgets the candidates asserted and declares the predicates again.
+    fn reset_solver(&mut self) -> Res<()> {
+        smt::reset(&mut self.solver, &self.instance)?;
+        if self.incremental {
+            self.act_defs.clear();
+            self.declare_preds()?
+        }
+        Ok(())
+    }
+
+    /// Sets the number of counterexamples extracted for a clause, and whether they must have
+    /// different sign patterns, see `--cexs_per_clause` and `--cex_signs`.
+    pub fn set_diversity(&mut self, cexs_per_clause: usize, signs: bool) {
+        self.cexs_per_clause = cexs_per_clause;
+        self.cex_signs = signs
+    }
+
+    /// (De)activates the cache of valid clauses, see `--cex_cache`.
+    pub fn set_cex_cache(&mut self, cex_cache: bool) {
+        if !cex_cache {
+            self.valid_cache.clear()
+        }
+        self.cex_cache = cex_cache
+    }
+
+    /// (De)activates incremental mode, see `--incremental`.
+    ///
+    /// Incremental mode stays inactive if the solver is restarted after each check. Resets the
+    /// solver if the mode changes.
+    pub fn set_incremental(&mut self, incremental: bool) -> Res<()> {
+        let incremental = incremental && !self.restart_on_cex;
+        if incremental != self.incremental {
+            self.act_defs.clear();
+            self.incremental = incremental;
+            self.reset_solver()?
+        }
+        Ok(())
+    }
+
+    /// Activates some candidates, used in incremental mode.
+    ///
+    /// Candidates that did not change since the last call keep their actlit, and are not asserted
+    /// again. New candidates get a fresh actlit guarding their definition, asserted at top level,
+    /// and the actlit of the candidate they replace is disabled. Only considers predicates that
+    /// are neither trivially true or false.
+    pub fn activate_preds(&mut self, cands: &Candidates) -> Res<()> {
+        for (pred, cand) in cands.index_iter() {
+            let term = match *cand {
+                Some(ref term) if term.bool().is_none() => Some(term),
+                _ => None,
+            };
+
+            if let Some(&(ref prev, _)) = self.act_defs.get(&pred) {
+                if Some(prev) == term {
+                    continue;
+                }
+            }
+            if let Some((_, actlit)) = self.act_defs.remove(&pred) {
+                self.solver.de_actlit(actlit)?
+            }
+
+            if let Some(term) = term {
+                profile! { self "incremental definitions" => add 1 }
+                let actlit = self.solver.get_actlit()?;
+                self.solver
+                    .assert(&SmtActPredDef::new(&actlit, &self.instance[pred], term))?;
+                self.act_defs.insert(pred, (term.clone(), actlit));
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepares the solver for the checks on some candidates.
+    fn prepare_solver(&mut self, cands: &Candidates) -> Res<()> {
+        if self.incremental {
+            self.activate_preds(cands)
+        } else {
+            self.solver.push(1)?;
+            self.define_preds(cands)
+        }
+    }
+
     /// Defines the predicates given some candidates.
     ///
     /// Only defines predicates that are neither trivially true or false.
@@ -812,14 +1103,15 @@ impl<'a> Teacher<'a> {
     /// Looks for falsifiable clauses given some candidates.
     pub fn get_cexs(&mut self, cands: &Candidates) -> Res<Cexs> {
         self.count += 1;
+        self.last_candidates = Some(cands.clone());
+        self.unknown_clauses.clear();
 
         self.register_trivial(cands);
 
         let mut map = ClsHMap::with_capacity(self.instance.clauses().len());
 
         if !self.restart_on_cex {
-            self.solver.push(1)?;
-            self.define_preds(cands)?
+            self.prepare_solver(cands)?
         }
 
         let instance = self.instance.clone();
@@ -827,14 +1119,14 @@ impl<'a> Teacher<'a> {
         let mut got_unknown = false;
 
         macro_rules! handle_clause_res {
-            ($e:expr) => {
+            ($clause:expr, $e:expr) => {
                 match $e {
                     Ok(()) => Ok(()),
                     Err(e) => {
                         if e.is_unknown() {
-                            smt::reset(&mut self.solver, &self.instance)?;
-                            self.solver.push(1)?;
-                            self.define_preds(cands)?;
+                            self.reset_solver()?;
+                            self.prepare_solver(cands)?;
+                            self.unknown_clauses.insert($clause);
                             got_unknown = true;
                             Ok(())
                         } else {
@@ -853,7 +1145,10 @@ impl<'a> Teacher<'a> {
             instance.pos_clauses().len()
         }
         for clause in instance.pos_clauses() {
-            handle_clause_res!(self.get_cexs_of_clause(cands, *clause, &mut map, false))?
+            handle_clause_res!(
+                *clause,
+                self.get_cexs_of_clause(cands, *clause, &mut map, false)
+            )?
         }
 
         log! { @verb |
@@ -861,7 +1156,10 @@ impl<'a> Teacher<'a> {
             instance.strict_neg_clauses().len()
         }
         for clause in instance.strict_neg_clauses() {
-            handle_clause_res!(self.get_cexs_of_clause(cands, *clause, &mut map, false))?
+            handle_clause_res!(
+                *clause,
+                self.get_cexs_of_clause(cands, *clause, &mut map, false)
+            )?
         }
 
         // got_pos_neg_samples = ! map.is_empty() ;
@@ -872,12 +1170,10 @@ impl<'a> Teacher<'a> {
                 instance.non_strict_neg_clauses().len()
             }
             for clause in instance.non_strict_neg_clauses() {
-                handle_clause_res!(self.get_cexs_of_clause(
-                    cands,
+                handle_clause_res!(
                     *clause,
-                    &mut map,
-                    conf.teacher.bias_cexs
-                ))?
+                    self.get_cexs_of_clause(cands, *clause, &mut map, conf.teacher.bias_cexs)
+                )?
             }
         }
 
@@ -888,15 +1184,15 @@ impl<'a> Teacher<'a> {
             }
 
             for clause in instance.imp_clauses() {
-                handle_clause_res!(self.get_cexs_of_clause(
-                    cands,
+                handle_clause_res!(
                     *clause,
-                    &mut map,
-                    conf.teacher.bias_cexs
-                ))?
+                    self.get_cexs_of_clause(cands, *clause, &mut map, conf.teacher.bias_cexs)
+                )?
             }
         }
 
+        self.falsified_clauses = map.keys().cloned().collect();
+
         if map.is_empty() && got_unknown {
             bail!(ErrorKind::SmtError(::rsmt2::errors::ErrorKind::Unknown))
         }
@@ -919,15 +1215,39 @@ impl<'a> Teacher<'a> {
         // }
 
         if self.count % 100 == 0 || self.restart_on_cex {
-            smt::reset(&mut self.solver, &self.instance)?;
-        } else {
+            self.reset_solver()?;
+        } else if !self.incremental {
             self.solver.pop(1)?
         }
 
         Ok(map)
     }
 
+    /// Candidates of the predicates appearing in a clause.
+    fn candidates_of_clause(&self, cands: &Candidates, clause: ClsIdx) -> PrdHMap<Option<Term>> {
+        let (lhs, rhs) = self.instance.preds_of_clause(clause);
+        let mut res = PrdHMap::with_capacity(lhs.len() + 1);
+        for pred in lhs.keys().cloned().chain(rhs) {
+            let prev = res.insert(pred, cands[pred].clone());
+            debug_assert! { prev.is_none() || prev == Some(cands[pred].clone()) }
+        }
+        res
+    }
+
+    /// True if a clause was proved valid for the same candidates as the ones for its predicates
+    /// in `cands`.
+    fn is_known_valid(&self, cands: &Candidates, clause: ClsIdx) -> bool {
+        if let Some(prev) = self.valid_cache.get(&clause) {
+            prev.iter().all(|(pred, cand)| cands[*pred] == *cand)
+        } else {
+            false
+        }
+    }
+
     /// Retrieves counterexamples for a clause.
+    ///
+    /// Skips the clause if it is trivially verified, or if the cache is active and it was
+    /// already proved valid for the same candidates.
     pub fn get_cexs_of_clause(
         &mut self,
         cands: &Candidates,
@@ -935,10 +1255,18 @@ impl<'a> Teacher<'a> {
         map: &mut ClsHMap<Vec<BCex>>,
         bias: bool,
     ) -> Res<()> {
+        if self.cex_cache && self.is_known_valid(cands, clause) {
+            profile! { self "cex checks skipped" => add 1 }
+            return Ok(());
+        }
+
         if !self.clauses_to_ignore.contains(&clause) {
+            profile! { self "cex checks" => add 1 }
             if self.restart_on_cex {
                 self.define_preds(cands)?
             } else {
+                // Only scopes the declarations and assertions of the clause, in incremental mode
+                // the definitions of the candidates live at top level.
                 self.solver.push(1)?
             }
 
@@ -951,8 +1279,12 @@ impl<'a> Teacher<'a> {
             }
 
             if !cexs.is_empty() {
+                self.valid_cache.remove(&clause);
                 let prev = map.insert(clause, cexs);
                 debug_assert_eq!(prev, None)
+            } else if self.cex_cache {
+                let clause_cands = self.candidates_of_clause(cands, clause);
+                self.valid_cache.insert(clause, clause_cands);
             }
         }
 
@@ -966,7 +1298,15 @@ impl<'a> Teacher<'a> {
         }
     }
     fn get_bias_cex_inner(&mut self, clause: ClsIdx, bias: &Bias) -> Res<Cex> {
-        let model = self.solver.get_model()?;
+        let mut model = self.solver.get_model()?;
+        if self.incremental {
+            // Predicates are declared in incremental mode, drop their interpretation.
+            let preds = self.instance.preds();
+            model.retain(|&(ref var, _, _, _)| match *var {
+                FPVar::Sym(ref name) => preds.iter().all(|pred| pred.name != *name),
+                FPVar::Var(_) => true,
+            })
+        }
         let model = Parser.fix_model(model)?;
         Cex::of_model(
             self.instance[clause].vars(),
@@ -991,7 +1331,11 @@ impl<'a> Teacher<'a> {
                 bias.to_string(&self.instance)
             ))?;
             profile! { self tick "cexs", "biased check-sat" }
-            let sat = { self.solver.check_sat_act(Some(&actlit))? };
+            let sat = {
+                let def_actlits = self.act_defs.values().map(|&(_, ref actlit)| actlit);
+                self.solver
+                    .check_sat_act(Some(&actlit).into_iter().chain(def_actlits))?
+            };
 
             if sat {
                 log! { @debug | "  sat, getting cex" }
@@ -1039,6 +1383,13 @@ impl<'a> Teacher<'a> {
                         // } else {
                         //     res
                         // }
+                    } else if self.incremental {
+                        let def_actlits = self.act_defs.values().map(
+                            |&(_, ref actlit)| actlit
+                        );
+                        self.solver.check_sat_act(def_actlits).map_err(
+                            |e| e.into()
+                        )
                     } else {
                         self.solver.check_sat().map_err(
                             |e| e.into()
@@ -1177,6 +1528,15 @@ impl<'a> Teacher<'a> {
 
         get_cex!();
 
+        let mut diverse = vec![];
+        if self.cexs_per_clause > 1 && !self.using_rec_funs {
+            if let Some(first) = cexs.last() {
+                diverse = profile! {
+                    self wrap { self.get_diverse_cexs(clause_idx, first) } "cexs", "diversity"
+                }?
+            }
+        }
+
         if bias {
             let unbiased_cex = cexs.pop();
 
@@ -1201,11 +1561,101 @@ impl<'a> Teacher<'a> {
             // Add the unbiased cex back if bias checks yielded nothing.
             if !conf.teacher.max_bias || cexs.is_empty() {
                 if let Some(unbiased_cex) = unbiased_cex {
-                    cexs.push(unbiased_cex)
+                    cexs.push(unbiased_cex);
+                    cexs.extend(diverse)
                 }
             }
+        } else {
+            cexs.extend(diverse)
         }
 
         Ok(cexs)
     }
+
+    /// Term excluding the counterexamples similar to some counterexample for a clause.
+    ///
+    /// If sign patterns are active (see `set_diversity`), arithmetic variables are constrained to have a different sign
+    /// than in `cex`. Other variables are constrained to have a different value. Unknown values
+    /// are ignored.
+    ///
+    /// Returns `None` if there is nothing to block.
+    fn blocking_term(&self, clause: ClsIdx, cex: &Cex) -> Option<Term> {
+        use std::cmp::Ordering::*;
+        let mut conj = vec![];
+
+        for info in self.instance[clause].vars() {
+            if !info.active {
+                continue;
+            }
+            let val = &cex[info.idx];
+            let var = term::var(info.idx, info.typ.clone());
+
+            let sign = if self.cex_signs {
+                match val.get() {
+                    val::RVal::I(ref i) => Some(i.cmp(&Int::zero())),
+                    val::RVal::R(ref r) => Some(r.cmp(&Rat::zero())),
+                    _ => None,
+                }
+            } else {
+                None
+            };
+
+            if let Some(sign) = sign {
+                let zero = info.typ.default_term();
+                conj.push(match sign {
+                    Less => term::lt(var, zero),
+                    Equal => term::eq(var, zero),
+                    Greater => term::gt(var, zero),
+                })
+            } else if let Some(val) = val.to_term() {
+                conj.push(term::eq(var, val))
+            }
+        }
+
+        if conj.is_empty() {
+            None
+        } else {
+            Some(term::not(term::and(conj)))
+        }
+    }
+
+    /// Extracts more counterexamples for a clause, different from a first one.
+    ///
+    /// Assumes the clause is already asserted. Each new counterexample has the same bias as the
+    /// first one, and is required to differ from all the previous ones (see `blocking_term`).
+    /// Stops when `cexs_per_clause` counterexamples have been extracted (including the first
+    /// one), or when there are no more counterexamples.
+    fn get_diverse_cexs(&mut self, clause: ClsIdx, first: &BCex) -> Res<Vec<BCex>> {
+        let (ref first, ref bias) = *first;
+        let mut res: Vec<BCex> = vec![];
+
+        let actlit = self.solver.get_actlit()?;
+        let mut last = first.clone();
+
+        while res.len() + 1 < self.cexs_per_clause {
+            if let Some(blocking) = self.blocking_term(clause, &last) {
+                self.solver.assert_act(&actlit, &SmtTerm::new(&blocking))?
+            } else {
+                break;
+            }
+
+            let sat = {
+                let def_actlits = self.act_defs.values().map(|&(_, ref actlit)| actlit);
+                self.solver
+                    .check_sat_act_or_unk(Some(&actlit).into_iter().chain(def_actlits))?
+            };
+            if sat != Some(true) {
+                break;
+            }
+
+            let cex = self.get_bias_cex(clause, bias)?;
+            log! { @debug | "  diverse cex {}", cex }
+            profile! { self "diverse cexs" => add 1 }
+            last = cex.clone();
+            res.push((cex, bias.clone()))
+        }
+
+        self.solver.de_actlit(actlit)?;
+        Ok(res)
+    }
 }
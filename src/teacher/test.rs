@@ -0,0 +1,131 @@
+//! Tests for the teacher.
+
+use crate::{common::*, parse, teacher::Teacher};
+
+/// Teacher for `rsc/sat/counter.smt2`.
+///
+/// The only predicate is `p`, its only argument is `x`. The clauses are `x = 0 => (p x)`,
+/// `(p x) /\ x < 10 => (p (+ x 1))` and `(p x) /\ x > 10 => false`.
+fn counter_teacher(profiler: &Profiler) -> Teacher {
+    let mut instance = parse::instance(
+        &::std::fs::read_to_string("rsc/sat/counter.smt2").expect("while reading counter.smt2"),
+    );
+    instance.finalize().unwrap();
+    Teacher::new(Arc::new(instance), profiler, &ConjCandidates::new()).unwrap()
+}
+
+/// Candidates for the counter instance.
+fn cands(cand: Term) -> Candidates {
+    vec![Some(cand)].into()
+}
+
+/// The `x` variable of `p`.
+fn x() -> Term {
+    term::var(0, typ::int())
+}
+
+/// `0 <= x <= 10`, the strongest invariant of the counter instance.
+fn inv() -> Term {
+    term::and(vec![
+        term::ge(x(), term::int(0)),
+        term::le(x(), term::int(10)),
+    ])
+}
+
+/// Value of a statistic of a profiler.
+fn stat(profiler: &Profiler, name: &str) -> usize {
+    profiler.stats().get(name).cloned().unwrap_or(0)
+}
+
+#[test]
+fn incremental() {
+    let profiler = Profiler::new();
+    let mut teacher = counter_teacher(&profiler);
+    teacher.set_incremental(true).unwrap();
+
+    assert! { teacher.get_cexs(&cands(inv())).unwrap().is_empty() }
+    assert_eq! { stat(&profiler, "incremental definitions"), 1 }
+
+    // Same candidate, the definition is not asserted again.
+    assert! { teacher.get_cexs(&cands(inv())).unwrap().is_empty() }
+    assert_eq! { stat(&profiler, "incremental definitions"), 1 }
+
+    // The actlit of the previous candidate is disabled, its definition must not interfere.
+    assert_eq! { teacher.get_cexs(&cands(term::ge(x(), term::int(1)))).unwrap().len(), 2 }
+    assert_eq! { stat(&profiler, "incremental definitions"), 2 }
+    assert_eq! { teacher.get_cexs(&cands(term::le(x(), term::int(20)))).unwrap().len(), 1 }
+    assert_eq! { stat(&profiler, "incremental definitions"), 3 }
+}
+
+#[test]
+fn cex_cache() {
+    let profiler = Profiler::new();
+    let mut teacher = counter_teacher(&profiler);
+    teacher.set_cex_cache(true);
+
+    assert! { teacher.get_cexs(&cands(inv())).unwrap().is_empty() }
+    assert_eq! { stat(&profiler, "cex checks skipped"), 0 }
+    // All the clauses are known to be valid for these candidates.
+    assert! { teacher.get_cexs(&cands(inv())).unwrap().is_empty() }
+    assert_eq! { stat(&profiler, "cex checks skipped"), 3 }
+
+    // The candidate changed, the clauses must be checked again.
+    assert_eq! { teacher.get_cexs(&cands(term::ge(x(), term::int(1)))).unwrap().len(), 2 }
+    assert_eq! { stat(&profiler, "cex checks skipped"), 3 }
+}
+
+#[test]
+fn no_cex_cache() {
+    let profiler = Profiler::new();
+    let mut teacher = counter_teacher(&profiler);
+    teacher.set_cex_cache(false);
+
+    assert! { teacher.get_cexs(&cands(inv())).unwrap().is_empty() }
+    assert! { teacher.get_cexs(&cands(inv())).unwrap().is_empty() }
+    assert_eq! { stat(&profiler, "cex checks skipped"), 0 }
+}
+
+#[test]
+fn diverse_cexs() {
+    let profiler = Profiler::new();
+    let mut teacher = counter_teacher(&profiler);
+
+    // Only falsifies the last clause, for all `x > 10`.
+    let cands = cands(term::ge(x(), term::int(0)));
+
+    teacher.set_diversity(3, false);
+    let cexs = teacher.get_cexs(&cands).unwrap();
+    assert_eq! { cexs.len(), 1 }
+    let cexs: Vec<_> = cexs
+        .values()
+        .next()
+        .unwrap()
+        .iter()
+        .map(|(cex, _)| cex)
+        .collect();
+    assert_eq! { cexs.len(), 3 }
+    assert! { cexs[0] != cexs[1] && cexs[0] != cexs[2] && cexs[1] != cexs[2] }
+
+    // All the counterexamples are positive, there is no other sign pattern.
+    teacher.set_diversity(3, true);
+    let cexs = teacher.get_cexs(&cands).unwrap();
+    assert_eq! { cexs.values().next().unwrap().len(), 1 }
+}
+
+#[test]
+fn unknown_report() {
+    let profiler = Profiler::new();
+    let mut teacher = counter_teacher(&profiler);
+
+    let report = teacher.unknown_report();
+    assert! { report.contains("after checking 0 candidate(s)") }
+    assert! { report.contains("no candidates were checked") }
+
+    assert_eq! { teacher.get_cexs(&cands(term::ge(x(), term::int(1)))).unwrap().len(), 2 }
+
+    let report = teacher.unknown_report();
+    assert! { report.contains("after checking 1 candidate(s)") }
+    assert! { report.contains("predicates without a consistent candidate: p") }
+    assert! { report.contains("last candidates:\n  p: ") }
+    assert! { !report.contains("no candidates were checked") }
+}
@@ -65,6 +65,7 @@ pub mod bindings;
 mod eval;
 mod factory;
 mod leaf_iter;
+pub mod qe;
 pub mod simplify;
 mod tterms;
 pub mod typ;
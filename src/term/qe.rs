@@ -0,0 +1,508 @@
+//! Quantifier elimination for linear arithmetic.
+//!
+//! Eliminates existentially quantified arithmetic variables from conjunctions of terms. A variable
+//! can only be eliminated if all the terms mentioning it are linear (in)equalities.
+//!
+//! - if the variable appears in an equality, it is substituted away; for integers with a non-unit
+//!   coefficient `c`, the other constraints are scaled by `|c|` and a divisibility constraint is
+//!   added;
+//! - otherwise, real variables are eliminated by Fourier-Motzkin;
+//! - integer variables are eliminated by the exact shadow of the Omega test when all pairs of
+//!   bounds have a unit coefficient on one side, and by a closed form of Cooper's method
+//!   otherwise, which yields a disjunction over the lower (or upper) bounds.
+//!
+//! Elimination is skipped for a variable when the result would be significantly bigger than the
+//! constraints it replaces.
+//!
+//! # Examples
+//!
+//! ```
+//! # use hoice::common::*;
+//! // exists v_0. v_0 >= v_1 /\ v_0 <= v_2
+//! let mut terms = TermSet::new();
+//! terms.insert(term::ge(term::real_var(0), term::real_var(1)));
+//! terms.insert(term::le(term::real_var(0), term::real_var(2)));
+//! let mut qvars = VarHMap::new();
+//! qvars.insert(0.into(), typ::real());
+//!
+//! let eliminated = term::qe::exists(&qvars, &mut terms);
+//! assert! { eliminated.contains(&0.into()) }
+//! assert_eq! { terms.len(), 1 }
+//! assert! { terms.iter().all(|term| !term::vars(term).contains(&0.into())) }
+//!
+//! // exists v_0. 2 * v_0 = v_1 /\ v_0 >= v_2
+//! let mut terms = TermSet::new();
+//! terms.insert(term::eq(term::cmul(2, term::int_var(0)), term::int_var(1)));
+//! terms.insert(term::ge(term::int_var(0), term::int_var(2)));
+//! let mut qvars = VarHMap::new();
+//! qvars.insert(0.into(), typ::int());
+//!
+//! let eliminated = term::qe::exists(&qvars, &mut terms);
+//! assert! { eliminated.contains(&0.into()) }
+//! assert_eq! { terms.len(), 2 }
+//! assert! { terms.iter().all(|term| !term::vars(term).contains(&0.into())) }
+//! ```
+
+use num::Integer;
+
+use crate::common::*;
+
+/// Maximal growth factor of the number of constraints when eliminating a variable.
+const MAX_GROWTH: usize = 2;
+/// Number of constraints an elimination can always produce.
+const MIN_BUDGET: usize = 8;
+
+/// A linear combination of variables plus a constant.
+#[derive(Clone, Debug)]
+struct Lin {
+    /// Coefficients of the variables.
+    coefs: VarHMap<Rat>,
+    /// Constant.
+    cst: Rat,
+}
+impl Lin {
+    /// A constant.
+    fn cst(cst: Rat) -> Self {
+        Lin {
+            coefs: VarHMap::new(),
+            cst,
+        }
+    }
+
+    /// A variable.
+    fn var(var: VarIdx) -> Self {
+        let mut coefs = VarHMap::new();
+        coefs.insert(var, Rat::one());
+        Lin {
+            coefs,
+            cst: Rat::zero(),
+        }
+    }
+
+    /// Adds `coef * other` to `self`.
+    fn add(&mut self, other: &Lin, coef: &Rat) {
+        for (var, c) in &other.coefs {
+            let sum = self.coefs.get(var).cloned().unwrap_or_else(Rat::zero) + c * coef;
+            if sum.is_zero() {
+                self.coefs.remove(var);
+            } else {
+                self.coefs.insert(*var, sum);
+            }
+        }
+        self.cst += &other.cst * coef
+    }
+
+    /// Multiplies `self` by a constant.
+    fn scale(&mut self, coef: &Rat) {
+        if coef.is_zero() {
+            self.coefs.clear()
+        } else {
+            for c in self.coefs.values_mut() {
+                *c *= coef
+            }
+        }
+        self.cst *= coef
+    }
+
+    /// Removes a variable, returns its coefficient.
+    fn remove(&mut self, var: VarIdx) -> Rat {
+        self.coefs.remove(&var).unwrap_or_else(Rat::zero)
+    }
+
+    /// Linear combination corresponding to a term, if any.
+    fn of_term(term: &Term) -> Option<Self> {
+        if let Some(var) = term.var_idx() {
+            return Some(Lin::var(var));
+        }
+        if let Some(val) = term.val() {
+            return val.to_real().ok().and_then(|r| r).map(Lin::cst);
+        }
+
+        let (op, args) = term.app_inspect()?;
+        match op {
+            Op::Add => {
+                let mut res = Lin::cst(Rat::zero());
+                for arg in args {
+                    res.add(&Lin::of_term(arg)?, &Rat::one())
+                }
+                Some(res)
+            }
+            Op::Sub => {
+                let mut args = args.iter();
+                let mut res = Lin::of_term(args.next()?)?;
+                let mut empty = true;
+                for arg in args {
+                    empty = false;
+                    res.add(&Lin::of_term(arg)?, &-Rat::one())
+                }
+                if empty {
+                    res.scale(&-Rat::one())
+                }
+                Some(res)
+            }
+            Op::Mul | Op::CMul => {
+                let mut coef = Rat::one();
+                let mut lin: Option<Lin> = None;
+                for arg in args {
+                    let arg = Lin::of_term(arg)?;
+                    if arg.coefs.is_empty() {
+                        coef *= arg.cst
+                    } else if lin.is_none() {
+                        lin = Some(arg)
+                    } else {
+                        return None;
+                    }
+                }
+                let mut res = lin.unwrap_or_else(|| Lin::cst(Rat::one()));
+                res.scale(&coef);
+                Some(res)
+            }
+            _ => None,
+        }
+    }
+
+    /// Term corresponding to a linear combination.
+    ///
+    /// Coefficients must be integers if `typ` is `Int`.
+    fn to_term(&self, typ: &Typ) -> Term {
+        let cst = |r: &Rat| {
+            if typ.is_int() {
+                debug_assert! { r.is_integer() }
+                term::int(r.to_integer())
+            } else {
+                term::real(r.clone())
+            }
+        };
+        let mut vars: Vec<_> = self.coefs.iter().collect();
+        vars.sort_unstable_by_key(|(var, _)| **var);
+        let mut kids = Vec::with_capacity(vars.len() + 1);
+        for (var, coef) in vars {
+            kids.push(term::mul(vec![cst(coef), term::var(*var, typ.clone())]))
+        }
+        kids.push(cst(&self.cst));
+        term::add(kids)
+    }
+}
+
+/// Relation of an atom to zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rel {
+    /// Equal to zero.
+    Eq,
+    /// Greater than or equal to zero.
+    Ge,
+    /// Greater than zero.
+    Gt,
+}
+
+/// A linear atom `lin rel 0`.
+#[derive(Clone, Debug)]
+struct Atom {
+    /// Linear combination.
+    lin: Lin,
+    /// Relation to zero.
+    rel: Rel,
+}
+impl Atom {
+    /// Atom corresponding to a term, if any.
+    ///
+    /// Strict inequalities are made non-strict over integers.
+    fn of_term(term: &Term, typ: &Typ) -> Option<Self> {
+        if let Some(kid) = term.neg_inspect() {
+            let mut atom = Atom::of_term(kid, typ)?;
+            atom.lin.scale(&-Rat::one());
+            atom.rel = match atom.rel {
+                Rel::Eq => return None,
+                Rel::Ge => Rel::Gt,
+                Rel::Gt => Rel::Ge,
+            };
+            return Some(atom.normalize(typ));
+        }
+
+        let (op, args) = term.app_inspect()?;
+        if args.len() != 2 || args[0].typ() != *typ {
+            return None;
+        }
+        let (lhs, rhs) = (Lin::of_term(&args[0])?, Lin::of_term(&args[1])?);
+        let (mut lin, sub, rel) = match op {
+            Op::Eql => (lhs, rhs, Rel::Eq),
+            Op::Ge => (lhs, rhs, Rel::Ge),
+            Op::Gt => (lhs, rhs, Rel::Gt),
+            Op::Le => (rhs, lhs, Rel::Ge),
+            Op::Lt => (rhs, lhs, Rel::Gt),
+            _ => return None,
+        };
+        lin.add(&sub, &-Rat::one());
+        Some(Atom { lin, rel }.normalize(typ))
+    }
+
+    /// Makes the coefficients integers and the relation non-strict over integers.
+    fn normalize(mut self, typ: &Typ) -> Self {
+        if typ.is_int() {
+            let mut lcm = self.lin.cst.denom().clone();
+            for coef in self.lin.coefs.values() {
+                lcm = lcm.lcm(coef.denom())
+            }
+            if !lcm.is_one() {
+                self.lin.scale(&Rat::from_integer(lcm))
+            }
+            if self.rel == Rel::Gt {
+                self.lin.cst -= Rat::one();
+                self.rel = Rel::Ge
+            }
+        }
+        self
+    }
+
+    /// Term corresponding to an atom.
+    fn to_term(&self, typ: &Typ) -> Term {
+        let (lin, zero) = (self.lin.to_term(typ), Lin::cst(Rat::zero()).to_term(typ));
+        match self.rel {
+            Rel::Eq => term::eq(lin, zero),
+            Rel::Ge => term::ge(lin, zero),
+            Rel::Gt => term::gt(lin, zero),
+        }
+    }
+}
+
+/// Eliminates a variable from a conjunction of atoms.
+///
+/// Returns `None` if the result would blow up.
+fn eliminate(var: VarIdx, typ: &Typ, atoms: Vec<Atom>) -> Option<Vec<Term>> {
+    let budget = ::std::cmp::max(atoms.len() * MAX_GROWTH, MIN_BUDGET);
+
+    let mut res = vec![];
+    let mut bounds = vec![];
+    for mut atom in atoms {
+        let coef = atom.lin.remove(var);
+        if coef.is_zero() {
+            res.push(atom.to_term(typ))
+        } else {
+            bounds.push((coef, atom))
+        }
+    }
+
+    // Substitute equalities away.
+    if let Some(idx) = bounds.iter().position(|(_, atom)| atom.rel == Rel::Eq) {
+        let (c, eq) = bounds.swap_remove(idx);
+        if typ.is_int() && !c.abs().is_one() {
+            // `c * var + r = 0`, scale `a * var + t rel 0` by `|c|`.
+            let (abs, sign) = (c.abs(), c.signum());
+            for (a, mut atom) in bounds {
+                atom.lin.scale(&abs);
+                atom.lin.add(&eq.lin, &-(&sign * a));
+                res.push(atom.to_term(typ))
+            }
+            res.push(term::eq(
+                term::modulo(eq.lin.to_term(typ), term::int(abs.to_integer())),
+                term::int(0),
+            ))
+        } else {
+            // `var = - r / c`.
+            for (a, mut atom) in bounds {
+                atom.lin.add(&eq.lin, &-(a / &c));
+                res.push(atom.to_term(typ))
+            }
+        }
+        return if res.len() <= budget { Some(res) } else { None };
+    }
+
+    let (lowers, uppers): (Vec<_>, Vec<_>) = bounds.into_iter().partition(|(a, _)| a.is_positive());
+
+    // Unbounded on one side, all bounds can be dropped.
+    if lowers.is_empty() || uppers.is_empty() {
+        return Some(res);
+    }
+
+    let exact_shadow = typ.is_real()
+        || lowers
+            .iter()
+            .all(|(a, _)| a.is_one() || uppers.iter().all(|(b, _)| (-b).is_one()));
+
+    if exact_shadow {
+        if res.len() + lowers.len() * uppers.len() > budget {
+            return None;
+        }
+        // `a * var + l rel 0` and `b * var + u rel 0` with `a > 0`, `b < 0`.
+        for (a, lower) in &lowers {
+            for (b, upper) in &uppers {
+                let mut lin = lower.lin.clone();
+                lin.scale(&-b);
+                lin.add(&upper.lin, a);
+                let rel = if lower.rel == Rel::Gt || upper.rel == Rel::Gt {
+                    Rel::Gt
+                } else {
+                    Rel::Ge
+                };
+                res.push(Atom { lin, rel }.to_term(typ))
+            }
+        }
+        return Some(res);
+    }
+
+    // Integer, non-unit coefficients. Let `y = delta * var` where `delta` is the lcm of the
+    // coefficients, the bounds are `y >= l_i` and `y <= u_j`.
+    let delta = lowers
+        .iter()
+        .chain(uppers.iter())
+        .fold(Int::one(), |lcm, (a, _)| lcm.lcm(&a.abs().to_integer()));
+    let delta_rat = Rat::from_integer(delta.clone());
+
+    let lowers: Vec<Lin> = lowers
+        .into_iter()
+        .map(|(a, mut atom)| {
+            atom.lin.scale(&-(&delta_rat / a));
+            atom.lin
+        })
+        .collect();
+    let uppers: Vec<Lin> = uppers
+        .into_iter()
+        .map(|(b, mut atom)| {
+            atom.lin.scale(&(&delta_rat / -b));
+            atom.lin
+        })
+        .collect();
+
+    let pivots = ::std::cmp::min(lowers.len(), uppers.len());
+    if res.len() + pivots * (lowers.len() + uppers.len() - 1) > budget {
+        return None;
+    }
+
+    let zero = term::int(0);
+    let diff = |lhs: &Lin, rhs: &Lin| {
+        let mut lin = lhs.clone();
+        lin.add(rhs, &-Rat::one());
+        lin
+    };
+
+    let mut disj = Vec::with_capacity(pivots);
+    if lowers.len() <= uppers.len() {
+        // Some `l_i` is the greatest lower bound, and the smallest multiple of `delta` above it
+        // is below all the upper bounds.
+        for (idx, lower) in lowers.iter().enumerate() {
+            let mut conj = vec![];
+            for (other_idx, other) in lowers.iter().enumerate() {
+                if other_idx != idx {
+                    conj.push(term::ge(diff(lower, other).to_term(typ), zero.clone()))
+                }
+            }
+            let mut neg_lower = lower.clone();
+            neg_lower.scale(&-Rat::one());
+            let offset = term::modulo(neg_lower.to_term(typ), term::int(delta.clone()));
+            for upper in &uppers {
+                conj.push(term::ge(
+                    term::sub(vec![diff(upper, lower).to_term(typ), offset.clone()]),
+                    zero.clone(),
+                ))
+            }
+            disj.push(term::and(conj))
+        }
+    } else {
+        // Some `u_j` is the least upper bound, and the greatest multiple of `delta` below it is
+        // above all the lower bounds.
+        for (idx, upper) in uppers.iter().enumerate() {
+            let mut conj = vec![];
+            for (other_idx, other) in uppers.iter().enumerate() {
+                if other_idx != idx {
+                    conj.push(term::ge(diff(other, upper).to_term(typ), zero.clone()))
+                }
+            }
+            let offset = term::modulo(upper.to_term(typ), term::int(delta.clone()));
+            for lower in &lowers {
+                conj.push(term::ge(
+                    term::sub(vec![diff(upper, lower).to_term(typ), offset.clone()]),
+                    zero.clone(),
+                ))
+            }
+            disj.push(term::and(conj))
+        }
+    }
+    res.push(term::or(disj));
+
+    Some(res)
+}
+
+/// Eliminates existentially quantified variables from a conjunction of terms.
+///
+/// The variables of `qvars` must not appear anywhere but in `terms`. Variables that cannot be
+/// eliminated are left untouched. Returns the variables eliminated.
+pub fn exists(qvars: &VarHMap<Typ>, terms: &mut TermSet) -> VarSet {
+    let mut eliminated = VarSet::new();
+
+    let mut vars: Vec<_> = qvars.iter().filter(|(_, typ)| typ.is_arith()).collect();
+    vars.sort_unstable_by_key(|(var, _)| **var);
+
+    'vars: for (var, typ) in vars {
+        let mut atoms = vec![];
+        let mut to_rm = vec![];
+        for term in terms.iter() {
+            if !term::vars(term).contains(var) {
+                continue;
+            }
+            if let Some(atom) = Atom::of_term(term, typ) {
+                atoms.push(atom);
+                to_rm.push(term.clone())
+            } else {
+                continue 'vars;
+            }
+        }
+        if atoms.is_empty() {
+            continue 'vars;
+        }
+
+        if let Some(nu_terms) = eliminate(*var, typ, atoms) {
+            log! { @5 "eliminated v_{} ({} terms -> {} terms)", var, to_rm.len(), nu_terms.len() }
+            for term in to_rm {
+                terms.remove(&term);
+            }
+            for term in nu_terms {
+                match term.bool() {
+                    Some(true) => (),
+                    Some(false) => {
+                        terms.clear();
+                        terms.insert(term);
+                        eliminated.extend(qvars.keys().cloned());
+                        return eliminated;
+                    }
+                    None => {
+                        if let Some(kids) = term.conj_inspect() {
+                            terms.extend(kids.iter().cloned())
+                        } else {
+                            terms.insert(term);
+                        }
+                    }
+                }
+            }
+            eliminated.insert(*var);
+        }
+    }
+
+    eliminated
+}
+
+/// Eliminates the existentially quantified variables of a conjunction of top terms.
+///
+/// Variables appearing in predicate applications are not eliminated. Eliminated variables are
+/// removed from `qvars`. Returns the number of variables eliminated.
+pub fn exists_tterms(qvars: &mut Quantfed, tterms: &mut TTermSet) -> usize {
+    let mut in_apps = VarSet::new();
+    for argss in tterms.preds().values() {
+        for args in argss {
+            for arg in args.iter() {
+                in_apps.extend(term::vars(arg))
+            }
+        }
+    }
+    let candidates: VarHMap<Typ> = qvars
+        .iter()
+        .filter(|(var, _)| !in_apps.contains(var))
+        .map(|(var, typ)| (*var, typ.clone()))
+        .collect();
+    if candidates.is_empty() {
+        return 0;
+    }
+
+    let eliminated = exists(&candidates, tterms.terms_mut());
+    qvars.retain(|var, _| !eliminated.contains(var));
+    eliminated.len()
+}
@@ -541,6 +541,165 @@ fn lazy_4() {
     assert_eval!( bool not model => distinct )
 }
 
+/// Evaluates the conjunction of some terms on some values.
+fn qe_eval(terms: &TermSet, model: &VarMap<Val>) -> bool {
+    term::and(terms.iter().cloned().collect())
+        .eval(model)
+        .unwrap()
+        .to_bool()
+        .unwrap()
+        .unwrap()
+}
+
+/// Eliminates `v_0` from some integer terms over `v_0`, `v_1` and `v_2`.
+///
+/// Checks that `v_0` is eliminated, and that the result is equivalent to the original terms on
+/// small values of `v_1` and `v_2`.
+fn qe_int_check(terms: Vec<Term>) -> TermSet {
+    let original: TermSet = terms.into_iter().collect();
+    let mut terms = original.clone();
+    let mut qvars = VarHMap::new();
+    qvars.insert(0.into(), typ::int());
+
+    let eliminated = term::qe::exists(&qvars, &mut terms);
+    assert! { eliminated.contains(&0.into()) }
+    assert! { terms.iter().all(|term| !term::vars(term).contains(&0.into())) }
+
+    for v_1 in -6..7 {
+        for v_2 in -6..7 {
+            let expected = (-40..41).any(|v_0| {
+                qe_eval(
+                    &original,
+                    &model!(val::int(v_0), val::int(v_1), val::int(v_2)),
+                )
+            });
+            let res = qe_eval(&terms, &model!(val::int(0), val::int(v_1), val::int(v_2)));
+            assert_eq! { res, expected, "v_1 = {}, v_2 = {}", v_1, v_2 }
+        }
+    }
+
+    terms
+}
+
+#[test]
+fn qe_eq_subst() {
+    // exists v_0. v_0 = v_1 + 1 /\ v_0 <= v_2
+    qe_int_check(vec![
+        term::eq(
+            term::int_var(0),
+            term::add(vec![term::int_var(1), term::int(1)]),
+        ),
+        term::le(term::int_var(0), term::int_var(2)),
+    ]);
+}
+
+#[test]
+fn qe_eq_subst_divisibility() {
+    // exists v_0. 3 * v_0 = v_1 /\ v_0 >= v_2
+    let terms = qe_int_check(vec![
+        term::eq(term::cmul(3, term::int_var(0)), term::int_var(1)),
+        term::ge(term::int_var(0), term::int_var(2)),
+    ]);
+    assert_eq! { terms.len(), 2 }
+}
+
+#[test]
+fn qe_fourier_motzkin() {
+    // exists v_0. v_0 >= v_1 /\ v_0 < v_2 /\ 2 * v_0 <= v_2 + 4, over the reals
+    let mut terms = TermSet::new();
+    terms.insert(term::ge(term::real_var(0), term::real_var(1)));
+    terms.insert(term::lt(term::real_var(0), term::real_var(2)));
+    let real = |i: i64| term::real(Rat::from_integer(i.into()));
+    terms.insert(term::le(
+        term::mul(vec![real(2), term::real_var(0)]),
+        term::add(vec![term::real_var(2), real(4)]),
+    ));
+    let mut qvars = VarHMap::new();
+    qvars.insert(0.into(), typ::real());
+
+    let eliminated = term::qe::exists(&qvars, &mut terms);
+    assert! { eliminated.contains(&0.into()) }
+    assert! { terms.iter().all(|term| !term::vars(term).contains(&0.into())) }
+
+    // Equivalent to `v_1 < v_2 /\ 2 * v_1 <= v_2 + 4`.
+    for v_1 in -6..7 {
+        for v_2 in -6..7 {
+            let model = model!(
+                val::real(Rat::from_integer(0.into())),
+                val::real(Rat::from_integer(v_1.into())),
+                val::real(Rat::from_integer(v_2.into()))
+            );
+            let expected = v_1 < v_2 && 2 * v_1 <= v_2 + 4;
+            assert_eq! { qe_eval(&terms, &model), expected, "v_1 = {}, v_2 = {}", v_1, v_2 }
+        }
+    }
+}
+
+#[test]
+fn qe_exact_shadow() {
+    // exists v_0. v_0 >= v_1 /\ 2 * v_0 >= v_2 /\ v_0 <= v_2 + 3
+    //
+    // The only upper bound has a unit coefficient.
+    let terms = qe_int_check(vec![
+        term::ge(term::int_var(0), term::int_var(1)),
+        term::ge(term::cmul(2, term::int_var(0)), term::int_var(2)),
+        term::le(
+            term::int_var(0),
+            term::add(vec![term::int_var(2), term::int(3)]),
+        ),
+    ]);
+    assert! { terms.iter().all(|term| term.disj_inspect().is_none()) }
+}
+
+#[test]
+fn qe_cooper_divisibility() {
+    // exists v_0. 2 * v_0 >= v_1 /\ 2 * v_0 >= v_2 - 1 /\ 3 * v_0 <= v_2 /\ 3 * v_0 <= v_1 + 4
+    //
+    // Non-unit coefficients on both sides, the result has divisibility constraints.
+    let terms = qe_int_check(vec![
+        term::ge(term::cmul(2, term::int_var(0)), term::int_var(1)),
+        term::ge(
+            term::cmul(2, term::int_var(0)),
+            term::sub(vec![term::int_var(2), term::int(1)]),
+        ),
+        term::le(term::cmul(3, term::int_var(0)), term::int_var(2)),
+        term::le(
+            term::cmul(3, term::int_var(0)),
+            term::add(vec![term::int_var(1), term::int(4)]),
+        ),
+    ]);
+    assert! { terms.iter().any(|term| format!("{}", term).contains("mod")) }
+}
+
+#[test]
+fn qe_refusal() {
+    let mut qvars = VarHMap::new();
+    qvars.insert(0.into(), typ::int());
+
+    // exists v_0. v_0 * v_1 >= v_2, non-linear.
+    let mut terms = TermSet::new();
+    terms.insert(term::ge(
+        term::mul(vec![term::int_var(0), term::int_var(1)]),
+        term::int_var(2),
+    ));
+    let original = terms.clone();
+    let eliminated = term::qe::exists(&qvars, &mut terms);
+    assert! { eliminated.is_empty() }
+    assert_eq! { terms, original }
+
+    // exists v_0. /\_i v_0 >= v_i /\ /\_j v_0 <= v_j, 5 lower and 5 upper bounds: the exact
+    // shadow has 25 constraints, too many.
+    let mut terms = TermSet::new();
+    for var in 1..6 {
+        terms.insert(term::ge(term::int_var(0), term::int_var(var)));
+        terms.insert(term::le(term::int_var(0), term::int_var(var + 5)));
+    }
+    let original = terms.clone();
+    let eliminated = term::qe::exists(&qvars, &mut terms);
+    assert! { eliminated.is_empty() }
+    assert_eq! { terms, original }
+}
+
 // #[test]
 // fn models() {
 //   let v_1 = term::bool_var(0) ;
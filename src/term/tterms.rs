@@ -1020,6 +1020,45 @@ impl TTerms {
         }
     }
 
+    /// Constructs the conjunction of `self` and a term.
+    ///
+    /// The term must not mention the quantified variables of `self`.
+    ///
+    /// # Error if
+    ///
+    /// - called on a `Disj` mentioning predicates
+    pub fn and(self, term: Term) -> Res<Self> {
+        match self {
+            TTerms::True => Ok(TTerms::of_term(None, term)),
+            TTerms::False => Ok(self),
+            TTerms::Conj { quant, mut tterms } => {
+                tterms.insert_term(term);
+                Ok(TTerms::conj(quant, tterms))
+            }
+            TTerms::Disj {
+                quant,
+                tterms,
+                neg_preds,
+            } => {
+                if !tterms.preds.is_empty() || !neg_preds.is_empty() {
+                    bail!("TTerms: trying to call `and` on a disjunction of predicate applications")
+                }
+                let disj = term::or(tterms.terms().iter().cloned().collect());
+                let mut tterms = TTermSet::of_term(disj);
+                tterms.insert_term(term);
+                Ok(TTerms::conj(quant, tterms))
+            }
+            TTerms::Dnf { disj } => {
+                let mut nu_disj = Vec::with_capacity(disj.len());
+                for (quant, mut tterms) in disj {
+                    tterms.insert_term(term.clone());
+                    nu_disj.push((quant, tterms))
+                }
+                Ok(TTerms::dnf(nu_disj))
+            }
+        }
+    }
+
     /// Simplifies a formula of top terms.
     ///
     /// # TODO
@@ -1244,12 +1283,15 @@ impl TTerms {
     pub fn simplify_pred_apps(self, model: ModelRef, preds: &Preds) -> Self {
         macro_rules! if_defined {
             ($pred:ident then |$def:ident| $stuff:expr) => {
-                if let Some($def) = preds[*$pred].def() {
-                    $stuff
-                } else {
-                    for (ref idx, ref $def) in model {
-                        if idx == $pred {
-                            $stuff
+                // Predicates with argument invariants are not trivial, whatever their definition.
+                if preds[*$pred].invariants().is_empty() {
+                    if let Some($def) = preds[*$pred].def() {
+                        $stuff
+                    } else {
+                        for (ref idx, ref $def) in model {
+                            if idx == $pred {
+                                $stuff
+                            }
                         }
                     }
                 }
@@ -0,0 +1,179 @@
+//! Unsat cores over the named assertions of the input.
+//!
+//! The core is computed at the level of clauses from the unsat proof: it is made of the clauses
+//! of the original instance the [derivation] of the contradiction uses. If no full proof is
+//! available, the clauses of the original instance that cannot take part in a refutation are
+//! pruned instead: a clause is kept if all its lhs predicates are derivable from the facts, and
+//! if it is negative or its rhs predicate is needed by a clause that is kept.
+//!
+//! If `conf.minimize_core` is set, the core is then minimized by removing the assertions one by
+//! one and re-solving the remaining clauses of the core, through pre-processing and splitting.
+//! These runs do not load or dump learning data, see `Instance::persist_data`.
+//!
+//! Clauses are mapped back to the assertions they come from with `Clause::from`. Clauses that do
+//! not stem from a named assertion do not appear in the core.
+//!
+//! [derivation]: ../derivation/index.html (derivation module)
+
+use crate::{common::*, preproc, split, unsat_core::UnsatRes};
+
+/// Clauses of an instance that can take part in a refutation.
+fn relevant_clauses(instance: &Instance) -> ClsSet {
+    // Predicates derivable from the facts.
+    let mut derivable = PrdSet::new();
+    let mut fixed_point = false;
+    while !fixed_point {
+        fixed_point = true;
+        for clause in instance.clauses() {
+            if let Some((pred, _)) = clause.rhs() {
+                if !derivable.contains(&pred)
+                    && clause
+                        .lhs_preds()
+                        .keys()
+                        .all(|pred| derivable.contains(pred))
+                {
+                    derivable.insert(pred);
+                    fixed_point = false
+                }
+            }
+        }
+    }
+
+    // Predicates needed by negative clauses.
+    let mut needed = PrdSet::new();
+    let mut relevant = ClsSet::new();
+    let mut fixed_point = false;
+    while !fixed_point {
+        fixed_point = true;
+        for (idx, clause) in instance.clauses().index_iter() {
+            if relevant.contains(&idx)
+                || !clause
+                    .lhs_preds()
+                    .keys()
+                    .all(|pred| derivable.contains(pred))
+            {
+                continue;
+            }
+            let is_needed = clause
+                .rhs()
+                .map(|(pred, _)| needed.contains(&pred))
+                .unwrap_or(true);
+            if is_needed {
+                relevant.insert(idx);
+                needed.extend(clause.lhs_preds().keys().cloned());
+                fixed_point = false
+            }
+        }
+    }
+
+    relevant
+}
+
+/// Clauses of the original instance a refutation uses.
+///
+/// The clauses of the derivation if the unsat result is a full proof, the clauses that can take
+/// part in a refutation otherwise.
+fn core_clauses(original: &Instance, unsat: &UnsatRes) -> ClsSet {
+    if let UnsatRes::Proof { derivation, .. } = unsat {
+        derivation.nodes.iter().map(|node| node.clause).collect()
+    } else {
+        relevant_clauses(original)
+    }
+}
+
+/// True if an instance is known to be unsat.
+///
+/// Runs pre-processing and splitting on the instance, without proofs and without loading or
+/// dumping learning data. Timeouts and unknown results yield false.
+fn is_unsat(mut instance: Instance, profiler: &Profiler) -> Res<bool> {
+    instance.set_unsat_cores(false);
+    instance.set_proofs(false);
+    instance.set_persist_data(false);
+    match preproc::work(&mut instance, profiler) {
+        Ok(()) => (),
+        Err(ref e) if e.is_unsat() => return Ok(true),
+        Err(ref e) if e.is_timeout() || e.is_unknown() => return Ok(false),
+        Err(e) => bail!(e),
+    }
+    if let Some(maybe_model) = instance.is_trivial_conj()? {
+        return Ok(maybe_model.is_unsat());
+    }
+
+    match split::work(&Arc::new(instance), profiler) {
+        Ok(Some(Either::Right(_))) => Ok(true),
+        Ok(_) => Ok(false),
+        Err(ref e) if e.is_unsat() => Ok(true),
+        Err(ref e) if e.is_timeout() || e.is_unknown() => Ok(false),
+        Err(e) => bail!(e),
+    }
+}
+
+/// True if the original instance restricted to some of its clauses is known to be unsat.
+fn is_unsat_with(original: &Instance, clauses: &ClsSet, profiler: &Profiler) -> Res<bool> {
+    let mut instance = original.clone();
+    let mut to_forget: Vec<ClsIdx> = instance
+        .clauses()
+        .index_iter()
+        .map(|(idx, _)| idx)
+        .filter(|idx| !clauses.contains(idx))
+        .collect();
+    instance.forget_clauses(&mut to_forget)?;
+    is_unsat(instance, profiler)
+}
+
+/// Names of the assertions of an unsat core.
+///
+/// The `original` instance is the instance as it was before pre-processing, `unsat` is its unsat
+/// result. The names are given in the order of the assertions.
+pub fn named_core(original: &Instance, unsat: &UnsatRes, profiler: &Profiler) -> Res<Vec<String>> {
+    let core = core_clauses(original, unsat);
+
+    // Names of the assertions, and the original clauses they yield.
+    let mut names: Vec<(&String, ClsSet)> = vec![];
+    for clause in &core {
+        let from = original[*clause].from();
+        if let Some(name) = original.name_of_old_clause(from) {
+            if let Some((_, clauses)) = names.iter_mut().find(|(n, _)| *n == name) {
+                clauses.insert(*clause);
+            } else {
+                let mut clauses = ClsSet::new();
+                clauses.insert(*clause);
+                names.push((name, clauses))
+            }
+        }
+    }
+    names.sort_by_key(|(_, clauses)| clauses.iter().min().cloned());
+
+    if conf.minimize_core {
+        // Clauses of the current core.
+        let mut core = core;
+        let mut cnt = 0;
+        while cnt < names.len() {
+            // The core is valid as it is, so minimization stops on timeouts and errors.
+            let res = conf.check_timeout().and_then(|()| {
+                let candidate: ClsSet = core.difference(&names[cnt].1).cloned().collect();
+                let is_unsat = is_unsat_with(original, &candidate, profiler)
+                    .chain_err(|| format!("while minimizing core without {}", names[cnt].0))?;
+                Ok(if is_unsat { Some(candidate) } else { None })
+            });
+
+            match res {
+                Ok(Some(candidate)) => {
+                    log! { @verb "dropping {} from the unsat core", names[cnt].0 }
+                    core = candidate;
+                    names.remove(cnt);
+                }
+                Ok(None) => cnt += 1,
+                Err(e) => {
+                    if !e.is_timeout() {
+                        print_err(&e)
+                    }
+                    warn!("unsat core minimization stopped, the core might not be minimal");
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(names.into_iter().map(|(name, _)| name.clone()).collect())
+}
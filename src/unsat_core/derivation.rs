@@ -0,0 +1,481 @@
+//! Derivation trees for unsat proofs.
+//!
+//! A derivation tree explains a refutation in terms of the clauses of the original instance.
+//! Each node is an instance of a clause: the clause, the values of its quantified variables, the
+//! fact it derives, *i.e.* its rhs predicate applied to values, and its children, the nodes
+//! deriving the facts its lhs predicate applications correspond to. The root is an instance of a
+//! clause with no predicate application in its rhs, it is the contradiction.
+//!
+//! Derivations are built from the [entry points] of a proof, reconstructed on the original
+//! instance: facts are derived forward from the entry points until the lhs of a clause with no
+//! predicate application in its rhs holds.
+//!
+//! # Formats
+//!
+//! Nodes are identified by integers, the root is node `0`. In both formats, values are written
+//! in SMT-LIB, and the variables with no value in the derivation are omitted.
+//!
+//! As an S-expression:
+//!
+//! ```text
+//! (derivation
+//!   (node <id>
+//!     (clause <clause index> <clause name>?)
+//!     (values (<var> <value>)*)
+//!     (derives (<pred> <value>*) | false)
+//!     (children <id>*)
+//!   )*
+//! )
+//! ```
+//!
+//! In JSON, as an object with the `root` node and the list of `nodes`:
+//!
+//! ```text
+//! {
+//!   "root": 0,
+//!   "nodes": [
+//!     {
+//!       "id": <id>,
+//!       "clause": { "index": <clause index>, "name": <clause name> | null },
+//!       "values": { <var>: <value>, ... },
+//!       "derives": { "pred": <pred>, "args": [ <value>, ... ] } | null,
+//!       "children": [ <id>, ... ]
+//!     },
+//!     ...
+//!   ]
+//! }
+//! ```
+//!
+//! The clause index is the index of the clause in the input, and its name is the name of the
+//! assertion it comes from, if any. Unsat witnesses (`--witness`) use the S-expression format.
+//!
+//! [entry points]: ../entry_points/index.html (entry_points module)
+
+use crate::{
+    common::{smt::DisjArgs, *},
+    data::sample::Sample,
+    instance::json_str,
+    unsat_core::entry_points::Entry,
+    var_to::vals::VarValsSet,
+};
+
+/// Type of the solver used for derivation.
+type Slvr = Solver<smt::FullParser>;
+
+/// Maximum number of rounds of forward derivation.
+const MAX_ROUNDS: usize = 100;
+
+/// A node of a derivation tree.
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// Clause of the original instance.
+    pub clause: ClsIdx,
+    /// Values of the variables of the clause.
+    pub values: Cex,
+    /// Fact derived, `None` for the root.
+    pub fact: Option<Sample>,
+    /// Nodes deriving the lhs predicate applications of the clause.
+    pub children: Vec<usize>,
+}
+
+/// A derivation tree.
+#[derive(Debug, Clone)]
+pub struct Derivation {
+    /// Nodes of the tree, the first one is the root.
+    pub nodes: Vec<Node>,
+}
+
+impl Derivation {
+    /// Builds a derivation from some entry points for the original instance.
+    pub fn new(original: &Instance, entry: &Entry) -> Res<Self> {
+        let mut solver = conf.solver.spawn("derivation", smt::FullParser, original)?;
+        let res = Builder::new(original, &mut solver).work(entry);
+        solver.reset()?;
+        res
+    }
+
+    /// Writes the derivation as an S-expression.
+    pub fn write_sexpr<W: Write>(&self, w: &mut W, original: &Instance) -> Res<()> {
+        writeln!(w, "(derivation")?;
+        for (id, node) in self.nodes.iter().enumerate() {
+            let clause = &original[node.clause];
+            writeln!(w, "  (node {}", id)?;
+
+            write!(w, "    (clause {}", clause.from())?;
+            if let Some(name) = original.name_of_old_clause(clause.from()) {
+                write!(w, " {}", name)?
+            }
+            writeln!(w, ")")?;
+
+            write!(w, "    (values")?;
+            for (var, val) in node.values.index_iter() {
+                if val.is_known() {
+                    write!(w, " ({} {})", clause.vars[var], val)?
+                }
+            }
+            writeln!(w, ")")?;
+
+            if let Some(fact) = node.fact.as_ref() {
+                write!(w, "    (derives ({}", original[fact.pred])?;
+                for val in fact.args.iter() {
+                    write!(w, " {}", val)?
+                }
+                writeln!(w, "))")?
+            } else {
+                writeln!(w, "    (derives false)")?
+            }
+
+            write!(w, "    (children")?;
+            for child in &node.children {
+                write!(w, " {}", child)?
+            }
+            writeln!(w, ")")?;
+
+            writeln!(w, "  )")?
+        }
+        writeln!(w, ")")?;
+        Ok(())
+    }
+
+    /// Writes the derivation in JSON.
+    pub fn write_json<W: Write>(&self, w: &mut W, original: &Instance) -> Res<()> {
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"root\": 0,")?;
+        write!(w, "  \"nodes\": [")?;
+        for (id, node) in self.nodes.iter().enumerate() {
+            let clause = &original[node.clause];
+            let sep = if id == 0 { "" } else { "," };
+            writeln!(w, "{}", sep)?;
+            writeln!(w, "    {{")?;
+            writeln!(w, "      \"id\": {},", id)?;
+
+            writeln!(
+                w,
+                "      \"clause\": {{ \"index\": {}, \"name\": {} }},",
+                clause.from(),
+                original
+                    .name_of_old_clause(clause.from())
+                    .map(|name| json_str(name))
+                    .unwrap_or_else(|| "null".into())
+            )?;
+
+            write!(w, "      \"values\": {{")?;
+            let mut first = true;
+            for (var, val) in node.values.index_iter() {
+                if val.is_known() {
+                    let sep = if first { " " } else { ", " };
+                    first = false;
+                    write!(
+                        w,
+                        "{}{}: {}",
+                        sep,
+                        json_str(&clause.vars[var].name),
+                        json_str(&val.to_string())
+                    )?
+                }
+            }
+            writeln!(w, "{}}},", if first { "" } else { " " })?;
+
+            if let Some(fact) = node.fact.as_ref() {
+                write!(
+                    w,
+                    "      \"derives\": {{ \"pred\": {}, \"args\": [",
+                    json_str(&original[fact.pred].name)
+                )?;
+                for (idx, val) in fact.args.iter().enumerate() {
+                    let sep = if idx == 0 { "" } else { ", " };
+                    write!(w, "{}{}", sep, json_str(&val.to_string()))?
+                }
+                writeln!(w, "] }},")?
+            } else {
+                writeln!(w, "      \"derives\": null,")?
+            }
+
+            write!(w, "      \"children\": [")?;
+            for (idx, child) in node.children.iter().enumerate() {
+                let sep = if idx == 0 { "" } else { ", " };
+                write!(w, "{}{}", sep, child)?
+            }
+            writeln!(w, "]")?;
+
+            write!(w, "    }}")?
+        }
+        if !self.nodes.is_empty() {
+            writeln!(w)?;
+            write!(w, "  ")?
+        }
+        writeln!(w, "]")?;
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+}
+
+/// Negation of a disjunction of arguments, forces a fact to be new.
+struct NewFact<'a>(DisjArgs<'a>);
+impl<'a> Expr2Smt<()> for NewFact<'a> {
+    fn expr_to_smt2<Writer: Write>(&self, w: &mut Writer, _: ()) -> SmtRes<()> {
+        write!(w, "(not ")?;
+        self.0.expr_to_smt2(w, ())?;
+        write!(w, ")")?;
+        Ok(())
+    }
+}
+
+/// What an instance of a clause should derive.
+enum Target<'a> {
+    /// A specific fact.
+    Fact(&'a VarVals),
+    /// A fact that is not known yet.
+    New,
+    /// A contradiction, for clauses with no rhs.
+    False,
+}
+
+/// Derivation builder.
+struct Builder<'a> {
+    /// Original instance.
+    original: &'a Instance,
+    /// Solver.
+    solver: &'a mut Slvr,
+    /// Nodes, in the order they are created.
+    nodes: Vec<Node>,
+    /// Facts derived so far.
+    facts: PrdHMap<VarValsSet>,
+    /// Maps facts to the node deriving them.
+    fact_nodes: BTreeMap<Sample, usize>,
+}
+
+impl<'a> Builder<'a> {
+    /// Constructor.
+    fn new(original: &'a Instance, solver: &'a mut Slvr) -> Self {
+        Builder {
+            original,
+            solver,
+            nodes: vec![],
+            facts: PrdHMap::new(),
+            fact_nodes: BTreeMap::new(),
+        }
+    }
+
+    /// True if all the lhs predicates of a clause have facts.
+    fn is_usable(&self, clause: &Clause) -> bool {
+        clause
+            .lhs_preds()
+            .keys()
+            .all(|pred| self.facts.get(pred).map(|set| !set.is_empty()) == Some(true))
+    }
+
+    /// Node deriving a fact matching some values.
+    fn node_of(&self, pred: PrdIdx, vals: &VarVals) -> Option<usize> {
+        for (Sample { pred: p, args }, node) in &self.fact_nodes {
+            if *p == pred
+                && args
+                    .iter()
+                    .zip(vals.iter())
+                    .all(|(fact, val)| !fact.is_known() || !val.is_known() || fact == val)
+            {
+                return Some(*node);
+            }
+        }
+        None
+    }
+
+    /// Tries to instantiate a clause so that it derives some target from the facts.
+    ///
+    /// Returns the index of the new node if successful.
+    fn instantiate(&mut self, clause_idx: ClsIdx, target: Target) -> Res<Option<usize>> {
+        let original = self.original;
+        let clause = &original[clause_idx];
+        if !self.is_usable(clause) {
+            return Ok(None);
+        }
+        let no_facts = VarValsSet::new();
+
+        self.solver.push(1)?;
+        clause.declare(self.solver)?;
+        for term in clause.lhs_terms() {
+            self.solver.assert(&smt::SmtTerm::new(term))?
+        }
+        for (pred, argss) in clause.lhs_preds() {
+            for args in argss {
+                if !args.is_empty() {
+                    let facts = self.facts.get(pred).unwrap_or(&no_facts);
+                    self.solver.assert(&DisjArgs::new(args, facts)?)?
+                }
+            }
+        }
+        let mut skip = false;
+        match (target, clause.rhs()) {
+            (Target::Fact(vals), Some((_, args))) => {
+                self.solver.assert(&smt::EqConj::new(args, vals))?
+            }
+            (Target::New, Some((pred, args))) => {
+                let facts = self.facts.get(&pred).unwrap_or(&no_facts);
+                if !facts.is_empty() {
+                    if args.is_empty() {
+                        skip = true
+                    } else {
+                        self.solver.assert(&NewFact(DisjArgs::new(args, facts)?))?
+                    }
+                }
+            }
+            (Target::False, None) => (),
+            _ => skip = true,
+        }
+
+        let model = if !skip && self.solver.check_sat()? {
+            let model = self.solver.get_model()?;
+            Some(smt::FullParser.fix_model(model)?)
+        } else {
+            None
+        };
+        self.solver.pop(1)?;
+
+        let model = if let Some(model) = model {
+            Cex::of_model(clause.vars(), model, true)?
+        } else {
+            return Ok(None);
+        };
+
+        let mut children = vec![];
+        for (pred, argss) in clause.lhs_preds() {
+            for args in argss {
+                let mut vals = VarMap::with_capacity(args.len());
+                for arg in args.iter() {
+                    vals.push(arg.eval(&model)?)
+                }
+                let vals = var_to::vals::new(vals);
+                if let Some(node) = self.node_of(*pred, &vals) {
+                    children.push(node)
+                } else {
+                    bail!(
+                        "derivation: no fact matches ({} {})",
+                        self.original[*pred],
+                        vals
+                    )
+                }
+            }
+        }
+
+        let fact = if let Some((pred, args)) = clause.rhs() {
+            let mut vals = VarMap::with_capacity(args.len());
+            for arg in args.iter() {
+                vals.push(arg.eval(&model)?)
+            }
+            Some(Sample::new(pred, var_to::vals::new(vals)))
+        } else {
+            None
+        };
+
+        let node = self.nodes.len();
+        if let Some(fact) = fact.as_ref() {
+            self.facts
+                .entry(fact.pred)
+                .or_insert_with(VarValsSet::new)
+                .insert(fact.args.clone());
+            self.fact_nodes.insert(fact.clone(), node);
+        }
+        self.nodes.push(Node {
+            clause: clause_idx,
+            values: model,
+            fact,
+            children,
+        });
+
+        Ok(Some(node))
+    }
+
+    /// Derives the entry points, as long as possible.
+    fn derive_entries(&mut self, entry: &Entry) -> Res<()> {
+        let mut to_derive: Vec<&Sample> = entry.samples.iter().collect();
+        let mut fixed_point = false;
+        while !fixed_point {
+            fixed_point = true;
+            let mut cnt = 0;
+            'samples: while cnt < to_derive.len() {
+                let Sample { pred, args } = to_derive[cnt];
+                for clause in self.original.rhs_clauses_of(*pred) {
+                    if self.instantiate(*clause, Target::Fact(args))?.is_some() {
+                        to_derive.swap_remove(cnt);
+                        fixed_point = false;
+                        continue 'samples;
+                    }
+                }
+                cnt += 1
+            }
+        }
+        Ok(())
+    }
+
+    /// Derives new facts, returns false if there was none.
+    fn derive_new(&mut self) -> Res<bool> {
+        let mut new = false;
+        for clause in self.original.clauses().index_iter().map(|(idx, _)| idx) {
+            if self.original[clause].rhs().is_some()
+                && self.instantiate(clause, Target::New)?.is_some()
+            {
+                new = true
+            }
+        }
+        Ok(new)
+    }
+
+    /// Looks for a contradiction.
+    fn contradiction(&mut self) -> Res<Option<usize>> {
+        for clause in self.original.clauses().index_iter().map(|(idx, _)| idx) {
+            if self.original[clause].rhs().is_none() {
+                if let Some(node) = self.instantiate(clause, Target::False)? {
+                    return Ok(Some(node));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Builds the derivation.
+    fn work(mut self, entry: &Entry) -> Res<Derivation> {
+        self.derive_entries(entry)?;
+
+        let mut root = None;
+        for _ in 0..MAX_ROUNDS {
+            conf.check_timeout()?;
+            root = self.contradiction()?;
+            if root.is_some() || !self.derive_new()? {
+                break;
+            }
+        }
+        let root = if let Some(root) = root {
+            root
+        } else {
+            bail!("could not derive a contradiction from the entry points")
+        };
+
+        // Keep the nodes reachable from the root, numbered in depth-first order.
+        let mut ids = vec![None; self.nodes.len()];
+        let mut order = vec![];
+        let mut to_do = vec![root];
+        while let Some(node) = to_do.pop() {
+            if ids[node].is_some() {
+                continue;
+            }
+            ids[node] = Some(order.len());
+            order.push(node);
+            for child in self.nodes[node].children.iter().rev() {
+                to_do.push(*child)
+            }
+        }
+
+        let mut nodes = Vec::with_capacity(order.len());
+        for node in order {
+            let mut node = self.nodes[node].clone();
+            for child in node.children.iter_mut() {
+                if let Some(id) = ids[*child] {
+                    *child = id
+                }
+            }
+            nodes.push(node)
+        }
+
+        Ok(Derivation { nodes })
+    }
+}
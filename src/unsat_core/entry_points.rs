@@ -352,11 +352,16 @@ impl Entry {
     }
 
     /// Rewrites the entry points in terms of the original signatures.
-    fn rewrite(&self, instance: &Instance) -> Vec<Sample> {
+    fn rewrite(&self, instance: &Instance) -> Res<Vec<Sample>> {
         let mut samples = vec![];
 
         for Sample { pred, args } in &self.samples {
-            let pred = *pred;
+            let mut pred = *pred;
+            let mut args: VarMap<Val> = args.iter().cloned().collect::<Vec<_>>().into();
+            while let Some((original, original_args)) = instance.unflatten_sample(pred, &args)? {
+                pred = original;
+                args = original_args
+            }
             let original_sig = instance[pred].original_sig();
             let mut nu_args = VarMap::with_capacity(original_sig.len());
             for typ in original_sig {
@@ -366,16 +371,19 @@ impl Entry {
                 let old_var = instance[pred].original_sig_map()[var];
                 nu_args[old_var] = val.clone()
             }
+            for (var, def) in instance[pred].arg_defs().iter().rev() {
+                nu_args[*var] = def.eval(&nu_args)?
+            }
             let args = var_to::vals::new(nu_args);
             samples.push(Sample { pred, args })
         }
 
-        samples
+        Ok(samples)
     }
 
     /// Reconstructs some entry points given the original instance.
     pub fn reconstruct(&self, instance: &Instance, original: &Instance) -> Res<Self> {
-        let samples = self.rewrite(instance);
+        let samples = self.rewrite(instance)?;
         log! { @2 | "reconstructing {} sample(s)", samples.len() }
         let mut solver = conf
             .solver
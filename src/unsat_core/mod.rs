@@ -1,15 +1,56 @@
 //! Unsat core and proof extraction.
 //!
-//! Right now, only unsat proof in the form of [`entry_points`] is active.
+//! Unsat results carry the [`entry_points`] of the contradiction. When proofs are active, they
+//! are completed on the original instance with a [`derivation`] of the contradiction, and the
+//! full [`UnsatProof`] the [`sample_graph`] extracts from it. Unsat cores are computed over the
+//! named assertions by the [`assertions`] module. See [`ProofFormat`] for the formats proofs can
+//! be printed in.
 //!
 //! [`entry_points`]: entry_points/index.html (entry_points module)
+//! [`assertions`]: assertions/index.html (assertions module)
+//! [`derivation`]: derivation/index.html (derivation module)
+//! [`sample_graph`]: sample_graph/index.html (sample_graph module)
+//! [`UnsatProof`]: sample_graph/struct.UnsatProof.html (UnsatProof struct)
+//! [`ProofFormat`]: enum.ProofFormat.html (ProofFormat enum)
 
 use crate::common::*;
 
+pub mod assertions;
+pub mod derivation;
 pub mod entry_points;
-mod sample_graph;
+pub mod sample_graph;
 
+pub use self::derivation::Derivation;
 pub use self::entry_points::Entry;
+pub use self::sample_graph::{SampleGraph, UnsatProof};
+
+/// Format of the unsat proofs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofFormat {
+    /// Traces of the positive and negative samples of the contradiction.
+    Trace,
+    /// Entry points, the positive samples leading to the contradiction.
+    Entries,
+    /// Derivation tree as an S-expression.
+    Sexpr,
+    /// Derivation tree in JSON.
+    Json,
+}
+impl ::std::str::FromStr for ProofFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "trace" => Ok(ProofFormat::Trace),
+            "entries" => Ok(ProofFormat::Entries),
+            "sexpr" => Ok(ProofFormat::Sexpr),
+            "json" => Ok(ProofFormat::Json),
+            _ => Err(format!(
+                "expected `trace`, `entries`, `sexpr` or `json`, got `{}`",
+                s
+            )),
+        }
+    }
+}
 
 /// An unsat result.
 pub enum UnsatRes {
@@ -17,6 +58,15 @@ pub enum UnsatRes {
     None,
     /// Some entry points.
     Entry(Entry),
+    /// A full proof, in terms of the original instance.
+    Proof {
+        /// Entry points.
+        entry: Entry,
+        /// Derivation of the contradiction from the entry points.
+        derivation: Derivation,
+        /// Traces of the contradicting samples.
+        proof: UnsatProof,
+    },
 }
 impl UnsatRes {
     /// Constructor.
@@ -33,6 +83,17 @@ impl UnsatRes {
         UnsatRes::Entry(Entry::new(entry_points::SampleSet::new()))
     }
 
+    /// Unsat result when no entry points are known.
+    ///
+    /// Empty entry points if proofs are active, none otherwise.
+    pub fn of_instance(instance: &Instance) -> Self {
+        if instance.produces_proofs() {
+            Self::empty_entry()
+        } else {
+            UnsatRes::None
+        }
+    }
+
     /// True if none.
     pub fn is_none(&self) -> bool {
         match self {
@@ -41,14 +102,56 @@ impl UnsatRes {
         }
     }
 
-    /// Tries to retrieve the unsat proof.
-    fn get_proof(&self, instance: &Instance, original: &Instance) -> Res<Option<Entry>> {
-        match self {
-            UnsatRes::None => Ok(None),
-            UnsatRes::Entry(entry) => Ok(Some(entry.reconstruct(instance, original)?)),
+    /// Full proof from some entry points for the original instance.
+    ///
+    /// The derivation is computed by forward solving from the entry points on the original
+    /// instance, and the proof is extracted from the sample graph of this derivation, not from
+    /// the graph maintained by the teacher.
+    fn full_proof(original: &Instance, entry: Entry) -> Res<Self> {
+        let derivation = Derivation::new(original, &entry)?;
+        let proof = SampleGraph::of_derivation(original, &derivation)?.get_proof(original)?;
+        Ok(UnsatRes::Proof {
+            entry,
+            derivation,
+            proof,
+        })
+    }
+
+    /// Completes entry points with a full proof on the original instance.
+    ///
+    /// Keeps the entry points if the proof cannot be built.
+    pub fn complete(self, instance: &Instance, original: &Instance) -> Self {
+        let entry = if let UnsatRes::Entry(entry) = self {
+            entry
+        } else {
+            return self;
+        };
+
+        match entry
+            .reconstruct(instance, original)
+            .and_then(|entry| Self::full_proof(original, entry))
+        {
+            Ok(res) => res,
+            Err(e) => {
+                warn!(
+                    "could not build a full unsat proof, only entry points are available";
+                    "{}", e
+                );
+                UnsatRes::Entry(entry)
+            }
         }
     }
 
+    /// Writes some entry points.
+    fn write_entries<W: Write>(w: &mut W, entry: &Entry, original: &Instance) -> Res<()> {
+        writeln!(w, "(")?;
+        for sample in &entry.samples {
+            writeln!(w, "  ({} {})", original[sample.pred], sample.args)?
+        }
+        writeln!(w, ")")?;
+        Ok(())
+    }
+
     /// Tries to write the unsat proof.
     pub fn write_proof<W: Write>(
         &self,
@@ -56,18 +159,46 @@ impl UnsatRes {
         instance: &Instance,
         original: &Instance,
     ) -> Res<()> {
-        if let Some(entry) = self.get_proof(instance, original)? {
-            writeln!(w, "(")?;
-            for sample in &entry.samples {
-                writeln!(w, "  ({} {})", instance[sample.pred], sample.args)?
-            }
-            writeln!(w, ")")?;
+        // Witnesses are derivation trees.
+        let format = if conf.witness {
+            ProofFormat::Sexpr
         } else {
-            bail!(
+            conf.proof_format
+        };
+        match self {
+            UnsatRes::None => bail!(
                 "cannot produce unsat proof without `{}`",
                 conf.emph("(set-option :produce-unsat-proof true)")
-            )
+            ),
+
+            UnsatRes::Entry(entry) => {
+                let entry = entry.reconstruct(instance, original)?;
+                match format {
+                    ProofFormat::Entries => Self::write_entries(w, &entry, original),
+                    ProofFormat::Sexpr => {
+                        Derivation::new(original, &entry)?.write_sexpr(w, original)
+                    }
+                    ProofFormat::Json => Derivation::new(original, &entry)?.write_json(w, original),
+                    ProofFormat::Trace => {
+                        Self::full_proof(original, entry)?.write_proof(w, instance, original)
+                    }
+                }
+            }
+
+            UnsatRes::Proof {
+                entry,
+                derivation,
+                proof,
+            } => match format {
+                ProofFormat::Entries => Self::write_entries(w, entry, original),
+                ProofFormat::Sexpr => derivation.write_sexpr(w, original),
+                ProofFormat::Json => derivation.write_json(w, original),
+                ProofFormat::Trace => {
+                    proof.write(w, original)?;
+                    writeln!(w)?;
+                    Ok(())
+                }
+            },
         }
-        Ok(())
     }
 }
@@ -6,9 +6,8 @@ use std::borrow::Borrow;
 
 use crate::{
     common::{
-        // smt::FullParser as Parser,
-        // var_to::vals::{ VarValsMap, VarValsSet },
-        var_to::vals::VarValsMap,
+        smt::FullParser as Parser,
+        var_to::vals::{VarValsMap, VarValsSet},
         *,
     },
     unsat_core::*,
@@ -411,9 +410,9 @@ impl TraceFrame {
 
         let original_clause_name =
             if let Some(name) = instance.name_of_old_clause(original_clause_index) {
-                name
+                name.clone()
             } else {
-                return Ok(());
+                format!("clause_{}", original_clause_index)
             };
 
         writeln!(w, "{}({}", pref, original_clause_name)?;
@@ -605,10 +604,12 @@ impl UnsatProof {
 
 /** Stores the graph of dependencies between samples.
 
-This is maintained by the teacher when unsat core production is active and is
-returned in case of an unsat result. The graph allows to retrieve values for
-the original clauses that explain why some sample needs to be both true and
-false at the same time.
+This is built from the derivation of a contradiction on the original instance
+when proofs are active, see [`of_derivation`]. The graph allows to retrieve
+values for the original clauses that explain why some sample needs to be both
+true and false at the same time.
+
+[`of_derivation`]: #method.of_derivation (of_derivation function)
 */
 #[derive(Clone, Debug, Default)]
 pub struct SampleGraph {
@@ -660,6 +661,70 @@ impl SampleGraph {
         }
     }
 
+    /// Builds the sample graph of a derivation on the original instance.
+    ///
+    /// This is not the graph the teacher maintains, it is rebuilt from the derivation. The
+    /// sample of each lhs application of a node is the fact of the child of the node matching
+    /// the predicate and the arguments of the application.
+    pub fn of_derivation(original: &Instance, derivation: &Derivation) -> Res<Self> {
+        let mut graph = Self::new();
+
+        for node in &derivation.nodes {
+            let clause = &original[node.clause];
+            let mut samples = PrdHMap::<TArgMap>::new();
+            for (pred, argss) in clause.lhs_preds() {
+                for fargs in argss {
+                    let mut vals = VarMap::with_capacity(fargs.len());
+                    for arg in fargs.iter() {
+                        vals.push(arg.eval(&node.values)?)
+                    }
+                    let vals = var_to::vals::new(vals);
+
+                    let fact = node
+                        .children
+                        .iter()
+                        .filter_map(|child| derivation.nodes[*child].fact.as_ref())
+                        .find(|fact| {
+                            fact.pred == *pred
+                                && fact.args.iter().zip(vals.iter()).all(|(fact, val)| {
+                                    !fact.is_known() || !val.is_known() || fact == val
+                                })
+                        });
+                    if let Some(fact) = fact {
+                        samples
+                            .entry(*pred)
+                            .or_insert_with(TArgMap::new)
+                            .insert(fargs.clone(), fact.args.clone());
+                    } else {
+                        bail!(
+                            "no child of the derivation node for clause #{} derives ({} {})",
+                            node.clause,
+                            original[*pred],
+                            vals
+                        )
+                    }
+                }
+            }
+
+            match (node.fact.as_ref(), clause.rhs()) {
+                (Some(fact), Some((_, fargs))) => graph.add(
+                    fact.pred,
+                    fargs.clone(),
+                    fact.args.clone(),
+                    node.clause,
+                    samples,
+                ),
+                (None, None) if samples.is_empty() => {
+                    bail!("the contradiction does not involve any predicate")
+                }
+                (None, None) => graph.add_neg(node.clause, samples),
+                _ => bail!("inconsistent derivation node for clause #{}", node.clause),
+            }
+        }
+
+        Ok(graph)
+    }
+
     /// Adds traceability for a sample.
     pub fn add(
         &mut self,
@@ -798,183 +863,159 @@ impl SampleGraph {
         res.map(|(pred, pos, neg)| (pred, pos, neg, known))
     }
 
-    // /// Traces the origin of a sample.
-    // fn trace<'a>(
-    //   & 'a self,
-    //   pred: PrdIdx, args: & VarVals,
-    //   polarity: Polarity,
-    //   known: & KnownSamples,
-    //   solver: & mut Solver<Parser>,
-    //   instance: & Instance,
-    // ) -> Res<Trace> {
-
-    //   // Samples for which we already have an explanation for.
-    //   let mut explained = PrdHMap::<VarValsSet>::new() ;
-
-    //   // Checks whether a sample is explained or inserts a sample in explained
-    //   // samples.
-    //   macro_rules! explained {
-    //     // Checks whether a sample is already explained.
-    //     (contains $pred:expr, $args:expr) => (
-    //       explained.get(& $pred).map(
-    //         |set| set.contains(& $args)
-    //       ).unwrap_or(false)
-    //     ) ;
-
-    //     // Adds a sample as explained.
-    //     (insert $pred:expr, $args:expr) => ({
-    //       let is_new = explained.entry($pred).or_insert_with(
-    //         VarValsSet::new
-    //       ).insert($args) ;
-    //       debug_assert! { is_new }
-    //     }) ;
-    //   }
-
-    //   // Result: full trace of explanation.
-    //   let mut res = vec![] ;
-
-    //   // Stores the samples we need to explain.
-    //   let mut to_explain = vec![ (polarity, pred, args.clone()) ] ;
-
-    //   // Explain all samples.
-    //   'all_samples: while let Some(
-    //     (polarity, pred, args)
-    //   ) = to_explain.pop() {
-
-    //     // Already explained?
-    //     if explained!(contains pred, & args) {
-    //       continue 'all_samples
-    //     }
-
-    //     let (rhs, (clause, lhs)) = if let Some(origin) = known.get(
-    //       polarity, pred, & args
-    //     ) {
-    //       origin
-    //     } else {
-    //       bail!("unable to explain why sample ({} {}) is positive", pred, args)
-    //     } ;
-
-    //     // Take care of the antecedents.
-    //     for (pred, argss) in lhs.iter() {
-    //       for (_, args) in argss {
-    //         to_explain.push(
-    //           (Polarity::pos(), * pred, args.clone())
-    //         )
-    //       }
-    //     }
-
-    //     let mut map = VarHMap::new() ;
-
-    //     {
-    //       use common::smt::{ SmtConj, EqConj } ;
-
-    //       solver.comment(
-    //         & format!("Working on clause #{}", clause)
-    //       ) ? ;
-    //       let clause = & instance[clause] ;
-
-    //       solver.push(1) ? ;
-
-    //       clause.declare(solver) ? ;
-
-    //       let conj = SmtConj::new(clause.lhs_terms()) ;
-
-    //       solver.assert(& conj) ? ;
-
-    //       debug_assert_eq! { clause.lhs_preds().len(), lhs.len() }
-
-    //       debug_assert! {
-    //         lhs.iter().all(
-    //           |(pred, argss)| clause.lhs_preds().iter().any(
-    //             |(p, a)| p == pred && argss.len() == a.len() && argss.iter().all(
-    //               |(args, _)| a.iter().any(
-    //                 |a| a == args
-    //               )
-    //             )
-    //           )
-    //         )
-    //       }
-
-    //       for argss in lhs.values() {
-    //         for (fargs, sample) in argss {
-    //           let eq_conj = EqConj::new(fargs, sample) ;
-    //           solver.assert(& eq_conj) ?
-    //         }
-    //       }
-
-    //       if let Some((pred, ref fargs, ref args)) = rhs {
-    //         debug_assert! {
-    //           if let Some((p, fa)) = clause.rhs() {
-    //             pred == p && fa == fargs
-    //           } else {
-    //             false
-    //           }
-    //         }
-    //         let eq_conj = EqConj::new(fargs, args) ;
-    //         solver.assert(& eq_conj) ?
-    //       }
-
-    //       if ! solver.check_sat() ? {
-    //         bail!("error retrieving unsat core, trace is not feasible")
-    //       }
-
-    //       let model = solver.get_model() ? ;
-    //       let model = Parser.fix_model(model) ? ;
-
-    //       solver.pop(1) ? ;
-
-    //       for (var, _, val) in model {
-    //         let prev = map.insert(var, val) ;
-    //         debug_assert_eq! { prev, None }
-    //       }
-
-    //     }
-
-    //     // Append to result.
-    //     res.push(
-    //       TraceFrame::new(
-    //         clause, map, polarity, pred, args.clone(), rhs, lhs
-    //       )
-    //     ) ;
-
-    //     // Remember we explained this sample.
-    //     explained! { insert pred, args }
-
-    //   }
-
-    //   res.reverse() ;
-
-    //   Ok( Trace::new(res) )
-    // }
+    /// Values for the variables of a clause, given the samples of its lhs and its rhs.
+    fn values_of(
+        clause: ClsIdx,
+        rhs: &Rhs,
+        lhs: &PrdHMap<TArgMap>,
+        solver: &mut Solver<Parser>,
+        instance: &Instance,
+    ) -> Res<VarHMap<Val>> {
+        solver.comment(&format!("Working on clause #{}", clause))?;
+        let clause = &instance[clause];
+
+        solver.push(1)?;
+
+        clause.declare(solver)?;
+        for term in clause.lhs_terms() {
+            solver.assert(&smt::SmtTerm::new(term))?
+        }
+        for argss in lhs.values() {
+            for (fargs, sample) in argss {
+                solver.assert(&smt::EqConj::new(fargs, sample))?
+            }
+        }
+        if let Some((_, ref fargs, ref args)) = *rhs {
+            solver.assert(&smt::EqConj::new(fargs, args))?
+        }
+
+        let model = if solver.check_sat()? {
+            Some(solver.get_model()?)
+        } else {
+            None
+        };
+
+        solver.pop(1)?;
+
+        let model = if let Some(model) = model {
+            Parser.fix_model(model)?
+        } else {
+            bail!("error retrieving unsat proof, trace is not feasible")
+        };
+
+        let mut values = VarHMap::new();
+        for (var, _, val) in model {
+            let prev = values.insert(var, val);
+            debug_assert_eq! { prev, None }
+        }
+        Ok(values)
+    }
+
+    /// Traces the origin of a sample.
+    ///
+    /// The frames of the trace are ordered so that the samples a frame depends on are explained
+    /// by the frames before it.
+    fn trace(
+        pred: PrdIdx,
+        args: &VarVals,
+        polarity: Polarity,
+        known: &KnownSamples,
+        solver: &mut Solver<Parser>,
+        instance: &Instance,
+    ) -> Res<Trace> {
+        // Samples we already have an explanation for, negative ones first.
+        let mut explained = [PrdHMap::<VarValsSet>::new(), PrdHMap::<VarValsSet>::new()];
+
+        // Result: full trace of explanation.
+        let mut res = vec![];
+
+        // Stores the samples we need to explain. The origin of a sample is set once its
+        // antecedents have been scheduled for explanation.
+        let mut to_explain = vec![(polarity, pred, args.clone(), None)];
+
+        while let Some((polarity, pred, args, origin)) = to_explain.pop() {
+            let done = &mut explained[polarity.is_pos() as usize];
+
+            if let Some((rhs, (clause, lhs))) = origin {
+                // Antecedents are explained, explain the sample itself.
+                let is_new = done
+                    .entry(pred)
+                    .or_insert_with(VarValsSet::new)
+                    .insert(args.clone());
+                if is_new {
+                    let values = Self::values_of(clause, &rhs, &lhs, solver, instance)?;
+                    res.push(TraceFrame::new(
+                        clause, values, polarity, pred, args, rhs, lhs,
+                    ))
+                }
+                continue;
+            }
+
+            if done
+                .get(&pred)
+                .map(|set| set.contains(&args))
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            let (rhs, (clause, lhs)) = if let Some(origin) = known.get(polarity, pred, &args) {
+                origin
+            } else {
+                bail!(
+                    "unable to explain why sample ({} {}) is {}",
+                    instance[pred],
+                    args,
+                    if polarity.is_pos() {
+                        "positive"
+                    } else {
+                        "negative"
+                    }
+                )
+            };
+
+            let mut antecedents = vec![];
+            // Lhs applications are positive, except the sample itself if it is negative.
+            for (lhs_pred, argss) in &lhs {
+                for lhs_args in argss.values() {
+                    if polarity.is_pos() || *lhs_pred != pred || *lhs_args != args {
+                        antecedents.push((Polarity::pos(), *lhs_pred, lhs_args.clone(), None))
+                    }
+                }
+            }
+            // A negative sample can come from a negative rhs.
+            if !polarity.is_pos() {
+                if let Some((rhs_pred, _, ref rhs_args)) = rhs {
+                    antecedents.push((Polarity::neg(), rhs_pred, rhs_args.clone(), None))
+                }
+            }
+
+            to_explain.push((polarity, pred, args, Some((rhs, (clause, lhs)))));
+            to_explain.extend(antecedents)
+        }
+
+        Ok(Trace::new(res))
+    }
 
     /// Extracts a proof for unsat.
-    pub fn get_proof(&mut self, _instance: &Instance) -> Res<UnsatProof> {
-        bail!("unimplemented");
-        // let (
-        //   pred, pos, neg, known
-        // ) = if let Some(contradiction) = self.find_contradiction() {
-        //   contradiction
-        // } else {
-        //   bail!("could not retrieve unsat result")
-        // } ;
-
-        // let mut solver = conf.solver.spawn(
-        //   "core_extraction", Parser, & instance
-        // ) ? ;
-        // let pos_trace = self.trace(
-        //   pred, & pos, Polarity::pos(), & known, & mut solver,
-        //   instance
-        // ) ? ;
-        // let neg_trace = self.trace(
-        //   pred, & neg, Polarity::neg(), & known, & mut solver,
-        //   instance
-        // ) ? ;
-
-        // Ok(
-        //   UnsatProof {
-        //     pred, pos, neg, pos_trace, neg_trace
-        //   }
-        // )
+    pub fn get_proof(&mut self, instance: &Instance) -> Res<UnsatProof> {
+        let (pred, pos, neg, known) = if let Some(contradiction) = self.find_contradiction() {
+            contradiction
+        } else {
+            bail!("could not retrieve unsat result")
+        };
+
+        let mut solver = conf.solver.spawn("core_extraction", Parser, instance)?;
+        let pos_trace = Self::trace(pred, &pos, Polarity::pos(), &known, &mut solver, instance)?;
+        let neg_trace = Self::trace(pred, &neg, Polarity::neg(), &known, &mut solver, instance)?;
+
+        Ok(UnsatProof {
+            pred,
+            pos,
+            neg,
+            pos_trace,
+            neg_trace,
+        })
     }
 
     /// Writes the sample graph with a prefix.
@@ -4,10 +4,16 @@
 extern crate hoice;
 
 use std::fs::read_dir;
+use std::fs::read_to_string;
 use std::fs::OpenOptions;
 
+use hoice::check;
 use hoice::common::*;
+use hoice::data::sample::Sample;
+use hoice::parse;
+use hoice::preproc::{BoolSplit, Coi, LoopAccel, OneRhs, PreInstance, PredMerge, RedStrat, Strat};
 use hoice::read_and_work;
+use hoice::unsat_core::{assertions, entry_points, entry_points::Entry, Derivation, UnsatRes};
 
 static sat_files_dir: &str = "rsc/sat";
 static unsat_files_dir: &str = "rsc/unsat";
@@ -49,6 +55,362 @@ fn sat_rec_simpl() {
     run!(run_sat_on("rsc/sat/long/recursive_simplifications.smt2"))
 }
 
+#[test]
+fn coi_after_pred_creation() {
+    // Both strategies are created before `bool_split` creates predicates, as in the
+    // pre-processor.
+    let mut instance = parse::instance(&read_to_string("rsc/sat/coi.smt2").unwrap());
+    let mut bool_split = BoolSplit::new(&instance);
+    let mut coi = Coi::new(&instance);
+    let pred_count = instance.preds().len();
+
+    let mut instance = PreInstance::new(&mut instance).unwrap();
+    bool_split.apply(&mut instance).unwrap();
+    assert_eq! { instance.preds().len(), pred_count + 2 }
+
+    let info = coi.apply(&mut instance).unwrap();
+    let (q, r): (PrdIdx, PrdIdx) = (1.into(), 2.into());
+    assert_eq! { info.preds, 2 }
+    assert! { instance[q].is_defined() }
+    assert! { instance[r].is_defined() }
+    assert! { info.clauses_rmed >= 2 }
+}
+
+#[test]
+fn arg_prop_invariants_in_model() {
+    let file = OpenOptions::new()
+        .read(true)
+        .open("rsc/sat/arg_prop.smt2")
+        .unwrap();
+    let (model, instance) = read_and_work(file, true, true, true).unwrap();
+    let model = model.expect("expected sat");
+
+    // The second argument of `p` and `q` is always `7`, their definitions must say so.
+    let (p, q): (PrdIdx, PrdIdx) = (0.into(), 1.into());
+    let apps = vec![
+        (p, vec![val::int(0), val::int(7), val::int(1)].into()),
+        (p, vec![val::int(0), val::int(8), val::int(1)].into()),
+        (q, vec![val::int(0), val::int(8), val::int(1)].into()),
+    ];
+    let mut buff: Vec<u8> = vec![];
+    instance.write_values(&model, &apps, &mut buff).unwrap();
+    assert_eq! {
+        String::from_utf8(buff).unwrap(),
+        "(((p 0 7 1) true)\n ((p 0 8 1) false)\n ((q 0 8 1) false))\n"
+    }
+}
+
+#[test]
+fn pred_merge_isomorphic() {
+    let mut instance = parse::instance(&read_to_string("rsc/sat/pred_merge.smt2").unwrap());
+    let mut pred_merge = PredMerge::new(&instance);
+
+    let mut instance = PreInstance::new(&mut instance).unwrap();
+    let info = pred_merge.apply(&mut instance).unwrap();
+    let (p, q): (PrdIdx, PrdIdx) = (0.into(), 1.into());
+    assert_eq! { info.preds, 1 }
+    assert! { !instance[p].is_defined() }
+    assert! { instance[q].is_defined() }
+    // Nothing left to merge.
+    assert_eq! { pred_merge.apply(&mut instance).unwrap().preds, 0 }
+}
+
+#[test]
+fn loop_accel_same_origin() {
+    let mut instance = parse::instance(
+        "
+        (declare-fun p ( Int Int ) Bool)
+        (assert (forall ( (x Int) (y Int) ) (=> (and (= x 0) (= y 0)) (p x y))))
+        (assert (forall ( (x Int) (y Int) ) (=> (and (p x y) (< x 10)) (p (+ x 1) (+ y 2)))))
+        (assert (forall ( (x Int) (y Int) ) (=> (and (p x y) (> y (* 2 x))) false)))
+    ",
+    );
+    let mut loop_accel = LoopAccel::new(&instance);
+    let mut instance = PreInstance::new(&mut instance).unwrap();
+    let info = loop_accel.apply(&mut instance).unwrap();
+    assert_eq! { info.clauses_added, 1 }
+
+    // Another self-loop stemming from the same original clause, with a different increment.
+    let p: PrdIdx = 0.into();
+    let self_loop = instance
+        .rhs_clauses_of(p)
+        .iter()
+        .cloned()
+        .find(|clause| {
+            !instance[*clause].lhs_preds().is_empty() && instance[*clause].info != "loop_accel"
+        })
+        .unwrap();
+    let args: Vec<Term> = instance[self_loop].lhs_preds()[&p]
+        .iter()
+        .next()
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect();
+    let nu_args: VarMap<Term> = vec![
+        term::add(vec![args[0].clone(), term::int(2)]),
+        term::add(vec![args[1].clone(), term::int(4)]),
+    ]
+    .into_iter()
+    .collect();
+    let rhs = TTerm::P {
+        pred: p,
+        args: var_to::terms::new(nu_args),
+    };
+    let clause = instance[self_loop].clone_with_rhs(Some(rhs), "test");
+    let info = instance.add_clause(clause, vec![]).unwrap();
+    assert_eq! { info.clauses_added, 1 }
+
+    let info = loop_accel.apply(&mut instance).unwrap();
+    assert_eq! { info.clauses_added, 1 }
+    let info = loop_accel.apply(&mut instance).unwrap();
+    assert_eq! { info.clauses_added, 0 }
+}
+
+#[test]
+fn provenance_parents() {
+    let mut instance = parse::instance(
+        "
+        (declare-fun p ( Int ) Bool)
+        (declare-fun q ( Int ) Bool)
+        (assert (forall ( (x Int) ) (=> (>= x 0) (p x))))
+        (assert (forall ( (x Int) ) (=> (p x) (q (+ x 1)))))
+        (assert (forall ( (x Int) ) (=> (and (q x) (< x 0)) false)))
+    ",
+    );
+    instance.activate_provenance();
+    let mut one_rhs = OneRhs::new(&instance);
+
+    let mut instance = PreInstance::new(&mut instance).unwrap();
+    instance.set_provenance_strat(Some("one_rhs"));
+    one_rhs.apply(&mut instance).unwrap();
+    instance.set_provenance_strat(None);
+
+    let mut log = vec![];
+    instance
+        .provenance()
+        .write_json(&mut log, &instance)
+        .unwrap();
+    let log = String::from_utf8(log).unwrap();
+
+    // Forcing `p` rewrites the second clause using the first one.
+    let resolution = log.lines().any(|line| {
+        line.contains("\"clause_created\"")
+            && line
+                .split("\"parents\": ")
+                .nth(1)
+                .map(|parents| parents.contains("\"index\": 0") && parents.contains("\"index\": 1"))
+                .unwrap_or(false)
+    });
+    assert! { resolution, "{}", log }
+}
+
+#[test]
+fn warm_start_malformed_definition() {
+    let instance = parse::instance(
+        "
+        (declare-fun p ( Int ) Bool)
+        (declare-fun q ( Int ) Bool)
+        (declare-fun r ( Int ) Bool)
+    ",
+    );
+    let model = "
+        sat
+        (model
+          (define-fun p ( (v_0 Int) ) Bool (and (>= v_0 0) (unknown_fun (+ v_0 1))))
+          (define-fun q ( (v_0 Int) ) Bool (< v_0 3))
+          (define-fun r ( (v_0 Int) ) Bool (or (= v_0 7) (= v_0 (- 2))))
+        )
+    ";
+    let profiler = Profiler::new();
+    let mut cxt = parse::ParserCxt::new();
+    let defs = cxt.parser(model, 0, &profiler).model(&instance).unwrap();
+
+    let (p, q, r): (PrdIdx, PrdIdx, PrdIdx) = (0.into(), 1.into(), 2.into());
+    assert! { defs.get(&p).is_none() }
+    assert! { defs.get(&q).is_some() }
+    assert! { defs.get(&r).is_some() }
+}
+
+#[test]
+fn unsat_core_of_proof() {
+    let mut instance = parse::instance(&read_to_string("rsc/unsat/core.smt2").unwrap());
+    instance.finalize().unwrap();
+
+    // The refutation starts from `(p 0)`.
+    let p: PrdIdx = 0.into();
+    let mut samples = entry_points::SampleSet::new();
+    samples.insert(Sample::new(p, var_to::vals::new(vec![val::int(0)])));
+    let unsat = UnsatRes::Entry(Entry::new(samples)).complete(&instance, &instance);
+
+    let core = assertions::named_core(&instance, &unsat, &Profiler::new()).unwrap();
+    assert_eq! { core, vec!["p_init", "p_step", "p_safe"] }
+}
+
+#[test]
+fn check_proof() {
+    let input = "rsc/unsat/core.smt2";
+    let valid = read_to_string("rsc/proof/core_valid.proof").unwrap();
+    run!(check::proof::do_it_from_str(input, &valid));
+    let invalid = read_to_string("rsc/proof/core_invalid.proof").unwrap();
+    assert! { check::proof::do_it_from_str(input, &invalid).is_err() }
+}
+
+#[test]
+fn check_default_proof() {
+    let input = "rsc/unsat/core.smt2";
+    let mut instance = parse::instance(&read_to_string(input).unwrap());
+    instance.finalize().unwrap();
+
+    let p: PrdIdx = 0.into();
+    let mut samples = entry_points::SampleSet::new();
+    samples.insert(Sample::new(p, var_to::vals::new(vec![val::int(0)])));
+    let unsat = UnsatRes::Entry(Entry::new(samples)).complete(&instance, &instance);
+
+    let mut buff: Vec<u8> = vec![];
+    unsat.write_proof(&mut buff, &instance, &instance).unwrap();
+    let proof = String::from_utf8(buff).unwrap();
+    run!(check::proof::do_it_from_str(input, &proof))
+}
+
+#[test]
+fn derivation_of_proof() {
+    let input = "rsc/unsat/core.smt2";
+    let mut instance = parse::instance(&read_to_string(input).unwrap());
+    instance.finalize().unwrap();
+
+    let p: PrdIdx = 0.into();
+    let mut samples = entry_points::SampleSet::new();
+    samples.insert(Sample::new(p, var_to::vals::new(vec![val::int(0)])));
+    let derivation = Derivation::new(&instance, &Entry::new(samples)).unwrap();
+    // `p_safe` on `(p 2)`, then `p_step` twice and `p_init`.
+    assert_eq! { derivation.nodes.len(), 4 }
+    assert! { derivation.nodes[0].fact.is_none() }
+    assert_eq! { derivation.nodes[0].children.len(), 1 }
+
+    let mut buff: Vec<u8> = vec![];
+    derivation.write_sexpr(&mut buff, &instance).unwrap();
+    let proof = String::from_utf8(buff).unwrap();
+    run!(check::proof::do_it_from_str(input, &proof));
+
+    let mut buff: Vec<u8> = vec![];
+    derivation.write_json(&mut buff, &instance).unwrap();
+    let json = String::from_utf8(buff).unwrap();
+    assert! { json.contains("\"root\": 0,") }
+    assert! { json.contains("\"name\": \"p_safe\"") }
+}
+
+#[test]
+fn simplified_model() {
+    let file_name = "rsc/sat/loop_accel.smt2";
+    let file = OpenOptions::new().read(true).open(file_name).unwrap();
+    let (model, instance) = read_and_work(file, true, true, true).unwrap();
+    let model = instance.simplified_model(model.expect("expected sat"));
+
+    let mut buff: Vec<u8> = vec![];
+    instance.write_model(&model, &mut buff).unwrap();
+    let model = String::from_utf8(buff).unwrap();
+    run!(check::do_it_from_str(file_name, &model))
+}
+
+#[test]
+fn witness() {
+    for file_name in &["rsc/sat/simple-adt-horn.smt2", "rsc/sat/loop_accel.smt2"] {
+        let file = OpenOptions::new().read(true).open(file_name).unwrap();
+        let (model, instance) = read_and_work(file, true, true, true).unwrap();
+
+        let mut buff: Vec<u8> = vec![];
+        instance
+            .write_witness(&model.expect("expected sat"), &mut buff)
+            .unwrap();
+        let witness = String::from_utf8(buff).unwrap();
+        assert! { !witness.contains("model") }
+        assert! { !witness.contains("declare-datatypes") }
+        run!(check::do_it_from_str(file_name, &witness))
+    }
+}
+
+#[test]
+fn get_value() {
+    let file = OpenOptions::new()
+        .read(true)
+        .open("rsc/sat/counter.smt2")
+        .unwrap();
+    let (model, instance) = read_and_work(file, true, true, true).unwrap();
+    let model = model.expect("expected sat");
+
+    // Any model has `(p 0)` and cannot have `(p 11)`.
+    let p: PrdIdx = 0.into();
+    let apps = vec![
+        (p, vec![val::int(0)].into()),
+        (p, vec![val::int(11)].into()),
+    ];
+    let mut buff: Vec<u8> = vec![];
+    instance.write_values(&model, &apps, &mut buff).unwrap();
+    assert_eq! {
+        String::from_utf8(buff).unwrap(),
+        "(((p 0) true)\n ((p 11) false))\n"
+    }
+}
+
+#[test]
+fn preproc_script_option() {
+    let instance = parse::instance(&read_to_string("rsc/sat/preproc_script.smt2").unwrap());
+    assert_eq! {
+        instance.get_option("preproc-script"),
+        Some("\"simplify; fixpoint@2(arg_red; one_rhs@1; one_lhs@1); cfg_red\"".into())
+    }
+    let script = instance.preproc_script().unwrap();
+    assert! { script.mentions(Strat::CfgRed) }
+    assert! { !script.mentions(Strat::Coi) }
+}
+
+#[test]
+fn check_verdicts() {
+    use hoice::check::Verdict::{self, *};
+    let script = read_to_string("rsc/sat/counter.smt2").unwrap();
+
+    let model = "(model (define-fun p ( (x Int) ) Bool (and (>= x 0) (<= x 10))))";
+    let verdicts = check::verdicts_from_str(&script, model).unwrap();
+    assert_eq! { verdicts, vec![Valid, Valid, Valid] }
+    assert! { check::judge(&verdicts, true).is_ok() }
+
+    let model = "(model (define-fun p ( (x Int) ) Bool (>= x 1)))";
+    let verdicts = check::verdicts_from_str(&script, model).unwrap();
+    assert_eq! { verdicts, vec![Falsifiable, Valid, Falsifiable] }
+    assert! { check::judge(&verdicts, false).is_err() }
+
+    // Unknown verdicts are only failures in strict mode.
+    let verdicts = [Valid, Unknown, Unknown];
+    assert! { check::judge(&verdicts, false).is_ok() }
+    match check::judge(&verdicts, true) {
+        Err(e) => match e.kind() {
+            ErrorKind::CheckUnknown(2) => (),
+            kind => panic!("unexpected error {}", kind),
+        },
+        Ok(()) => panic!("expected an error"),
+    }
+    assert! { check::judge(&[Valid, Verdict::Error], false).is_err() }
+}
+
+#[test]
+fn validate_model() {
+    let file_name = "rsc/sat/counter.smt2";
+    let script = read_to_string(file_name).unwrap();
+    let file = OpenOptions::new().read(true).open(file_name).unwrap();
+    let (model, instance) = read_and_work(file, true, true, true).unwrap();
+
+    let mut buff: Vec<u8> = vec![];
+    instance
+        .write_model(&model.expect("expected sat"), &mut buff)
+        .unwrap();
+    let model = String::from_utf8(buff).unwrap();
+    run!(check::validate(&script, &model));
+
+    let model = "(model (define-fun p ( (x Int) ) Bool (>= x 1)))";
+    assert! { check::validate(&script, model).is_err() }
+}
+
 #[test]
 fn unsat() {
     run!(run_unsat())